@@ -0,0 +1,526 @@
+use std::thread::sleep;
+use std::time::Duration;
+use borsh::BorshDeserialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::client_error::ClientError;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+};
+use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
+use solana_program::hash::Hash;
+use solana_program::pubkey::{Pubkey, PubkeyError};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use counter::{id, ACCESS_PAGE_CAPACITY};
+use counter::instruction::{ed25519_instruction, CounterInstruction, OperationKind};
+use counter::state::{AccessMode, AccessPage, Counter, ResetPeriod, Role, Roles, Settings};
+use crate::error::{CounterClientError, Result};
+use crate::offline;
+use crate::send::{self, SendConfig, Sent};
+use crate::simulate::Simulation;
+
+/// Confirmed transaction with the state of the changed account before and after it
+#[derive(Debug)]
+pub struct Executed<T> {
+    pub sent: Sent,
+    /// `None` when the account did not exist
+    pub before: Option<T>,
+    pub after: T,
+}
+
+/// Operation of a user submitted by a relayer, see `CounterClient::relay`
+#[derive(Clone, Debug, PartialEq)]
+pub struct RelayRequest {
+    pub user: Pubkey,
+    pub name: String,
+    pub op: OperationKind,
+    /// Relay nonce of the counter the operation is valid for
+    pub nonce: u64,
+    /// Unix time the operation is valid until
+    pub expiry: i64,
+}
+
+impl RelayRequest {
+    /// Message the user signs
+    pub fn message(&self) -> Result<Vec<u8>> {
+        Ok(CounterInstruction::relayed_message(self.user, &self.name, self.op, self.nonce, self.expiry)?)
+    }
+}
+
+/// Typed access to the counter program over RPC. Every operation sends one transaction,
+/// waits for its confirmation and returns the changed account before and after it
+pub struct CounterClient {
+    rpc: RpcClient,
+    send_config: SendConfig,
+}
+
+impl CounterClient {
+    pub fn new(rpc: RpcClient) -> Self {
+        CounterClient { rpc, send_config: SendConfig::default() }
+    }
+
+    /// Client of the node at `url` with finalized commitment
+    pub fn with_url(url: &str) -> Self {
+        Self::with_commitment(url, CommitmentConfig::finalized())
+    }
+
+    /// Transactions are confirmed and accounts are read with `commitment`
+    pub fn with_commitment(url: &str, commitment: CommitmentConfig) -> Self {
+        Self::new(RpcClient::new_with_timeout_and_commitment(
+            url.to_string(),
+            Duration::from_millis(60_000),
+            commitment,
+        ))
+    }
+
+    /// Retries and compute budget of the sent transactions
+    pub fn with_send_config(mut self, send_config: SendConfig) -> Self {
+        self.send_config = send_config;
+        self
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    pub fn send_config(&self) -> &SendConfig {
+        &self.send_config
+    }
+
+    pub fn recent_blockhash(&self) -> Result<Hash> {
+        Ok(self.rpc.get_recent_blockhash()?.0)
+    }
+
+    /// Sign `instructions` by the fee `payer` and `signers` and wait for the confirmation.
+    /// The transaction is signed again with a fresh blockhash when the previous one expired
+    pub fn send(&self, instructions: &[Instruction], payer: &Keypair, signers: &[&Keypair]) -> Result<Sent> {
+        self.send_reading(instructions, payer, signers, &[])
+    }
+
+    /// `send` with the simulated data of `accounts` after the transaction
+    fn send_reading(
+        &self,
+        instructions: &[Instruction],
+        payer: &Keypair,
+        signers: &[&Keypair],
+        accounts: &[Pubkey],
+    ) -> Result<Sent> {
+        let instructions = self.send_config.with_compute_budget(instructions);
+        let mut keypairs = vec![payer];
+        keypairs.extend(signers.iter().copied().filter(|signer| signer.pubkey() != payer.pubkey()));
+        let sign = |blockhash| {
+            Transaction::new_signed_with_payer(&instructions, Some(&payer.pubkey()), &keypairs, blockhash)
+        };
+        let recent_hash = self.retry(|_| self.recent_blockhash())?;
+        self.submit(sign(recent_hash), Some(&sign), accounts)
+    }
+
+    /// Send a transaction signed elsewhere, see `offline`. It is sent again as is until its blockhash expires
+    pub fn broadcast(&self, tx: &Transaction) -> Result<Sent> {
+        offline::check_signed(tx)?;
+        self.submit(tx.clone(), None, &[])
+    }
+
+    /// Run `tx` on the node without sending it, with the data of `accounts` after it
+    pub fn simulate(&self, tx: &Transaction, accounts: &[Pubkey]) -> Result<Simulation> {
+        let config = RpcSimulateTransactionConfig {
+            commitment: Some(self.rpc.commitment()),
+            accounts: Some(RpcSimulateTransactionAccountsConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                addresses: accounts.iter().map(Pubkey::to_string).collect(),
+            })
+            .filter(|_| !accounts.is_empty()),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        Ok(Simulation::new(self.rpc.simulate_transaction_with_config(tx, config)?.value, tx))
+    }
+
+    /// Run `attempt` with the attempt number until it succeeds or fails with a permanent error,
+    /// waiting longer before every retry
+    fn retry<T, F>(&self, mut attempt: F) -> Result<T>
+    where
+        F: FnMut(u32) -> Result<T>,
+    {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match attempt(attempts) {
+                Err(err) if send::is_transient(&err) => {
+                    if attempts > self.send_config.retries {
+                        return Err(CounterClientError::RetriesExhausted(attempts, Box::new(err)));
+                    }
+                    sleep(self.send_config.delay(attempts - 1));
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Every attempt is simulated first, a failed simulation aborts before the fee is paid.
+    /// Before every retry the previous attempt is looked up, so a transaction that landed after an error
+    /// is not sent twice. `resign` replaces it only when its blockhash expired and it can't land anymore
+    fn submit(
+        &self,
+        mut tx: Transaction,
+        resign: Option<&dyn Fn(Hash) -> Transaction>,
+        accounts: &[Pubkey],
+    ) -> Result<Sent> {
+        // Simulation of the attempt that reached the node
+        let mut sent_simulation = None;
+        self.retry(|attempt| {
+            if sent_simulation.is_some() && self.landed(&tx)? {
+                let signature = tx.signatures[0];
+                let slot = self.confirmed_slot(&signature)?;
+                return Ok(Sent { signature, slot, attempts: attempt - 1, simulation: sent_simulation.take().unwrap() });
+            }
+            if let Some(resign) = resign.filter(|_| attempt > 1) {
+                if self.rpc.get_fee_calculator_for_blockhash(&tx.message.recent_blockhash)?.is_none() {
+                    tx = resign(self.recent_blockhash()?);
+                }
+            }
+            let simulation = self.simulate(&tx, accounts)?;
+            match &simulation.error {
+                Some(TransactionError::BlockhashNotFound) => {
+                    return Err(ClientError::from(TransactionError::BlockhashNotFound).into())
+                }
+                Some(_) => return Err(CounterClientError::SimulationFailed(Box::new(simulation))),
+                None if self.send_config.simulate_only => {
+                    return Ok(Sent { signature: tx.signatures[0], slot: None, attempts: 0, simulation })
+                }
+                None => sent_simulation = Some(simulation),
+            }
+            let signature = self
+                .rpc
+                .send_and_confirm_transaction(&tx)
+                .map_err(|err| CounterClientError::from_transaction(err, &tx))?;
+            let slot = self.confirmed_slot(&signature)?;
+            Ok(Sent { signature, slot, attempts: attempt, simulation: sent_simulation.take().unwrap() })
+        })
+    }
+
+    /// Whether the transaction was processed, waiting for the client commitment then.
+    /// Failed one is returned as its error
+    fn landed(&self, tx: &Transaction) -> Result<bool> {
+        let signature = &tx.signatures[0];
+        match self.rpc.get_signature_status_with_commitment(signature, CommitmentConfig::processed())? {
+            Some(Ok(())) => {
+                self.rpc.poll_for_signature_with_commitment(signature, self.rpc.commitment())?;
+                Ok(true)
+            }
+            Some(Err(tx_err)) => Err(CounterClientError::from_transaction(ClientError::from(tx_err), tx)),
+            None => Ok(false),
+        }
+    }
+
+    /// Slot of a confirmed transaction, `None` when the node has no status of it
+    pub fn confirmed_slot(&self, signature: &Signature) -> Result<Option<u64>> {
+        Ok(self.rpc.get_signature_statuses(&[*signature])?.value.pop().flatten().map(|status| status.slot))
+    }
+
+    /// Send the transaction and read the changed `account` before and after it, reads are retried as sending.
+    /// A transaction that was only simulated returns the simulated state of the account
+    pub fn execute<T: BorshDeserialize>(
+        &self,
+        instructions: &[Instruction],
+        payer: &Keypair,
+        signers: &[&Keypair],
+        account: &Pubkey,
+    ) -> Result<Executed<T>> {
+        let before = match self.retry(|_| self.get_borsh(account)) {
+            Ok(state) => Some(state),
+            Err(CounterClientError::AccountNotFound(_)) => None,
+            Err(err) => return Err(err),
+        };
+        let sent = self.send_reading(instructions, payer, signers, &[*account])?;
+        let after = match sent.simulation.accounts.first() {
+            Some(simulated) if sent.attempts == 0 => {
+                let data = simulated.as_ref().ok_or(CounterClientError::AccountNotFound(*account))?;
+                T::try_from_slice(data).map_err(|err| CounterClientError::InvalidAccountData(*account, err))?
+            }
+            _ => self.retry(|_| self.get_borsh(account))?,
+        };
+        Ok(Executed { sent, before, after })
+    }
+
+    fn get_borsh<T: BorshDeserialize>(&self, pk: &Pubkey) -> Result<T> {
+        let acc = self
+            .rpc
+            .get_account_with_commitment(pk, self.rpc.commitment())?
+            .value
+            .ok_or(CounterClientError::AccountNotFound(*pk))?;
+        T::try_from_slice(&acc.data).map_err(|err| CounterClientError::InvalidAccountData(*pk, err))
+    }
+
+    pub fn get_counter(&self, owner: &Pubkey, name: &str) -> Result<Counter> {
+        self.get_borsh(&Counter::generate_counter_pk(owner, name)?)
+    }
+
+    /// Counter by its account key
+    pub fn get_counter_at(&self, counter_pk: &Pubkey) -> Result<Counter> {
+        self.get_borsh(counter_pk)
+    }
+
+    pub fn get_settings(&self) -> Result<Settings> {
+        self.get_borsh(&Settings::get_settings_pk_with_bump().0)
+    }
+
+    pub fn get_roles(&self) -> Result<Roles> {
+        self.get_borsh(&Roles::get_roles_pk_with_bump().0)
+    }
+
+    /// Page `index` of the access list bucket `user` falls into
+    pub fn get_access_page(&self, user: &Pubkey, index: u8) -> Result<AccessPage> {
+        self.get_borsh(&AccessPage::get_access_page_pk_with_bump(AccessPage::bucket(user), index).0)
+    }
+
+    /// Number of pages in the access list bucket of `user`, 0 when the bucket has none
+    pub fn access_pages(&self, user: &Pubkey) -> Result<u8> {
+        match self.get_access_page(user, 0) {
+            Ok(first_page) => Ok(first_page.pages),
+            Err(CounterClientError::AccountNotFound(_)) => Ok(0),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Index of the access page listing `user`, or of the one taking it when added,
+    /// with the number of pages in the bucket
+    pub fn access_page_index(&self, user: &Pubkey) -> Result<(u8, u8)> {
+        let pages = self.access_pages(user)?;
+        let mut free = None;
+        for index in 0..pages {
+            let page = self.get_access_page(user, index)?;
+            if page.keys().contains(user) {
+                return Ok((index, pages));
+            }
+            if free.is_none() && page.keys().len() < ACCESS_PAGE_CAPACITY {
+                free = Some(index);
+            }
+        }
+        Ok((free.unwrap_or(pages), pages))
+    }
+
+    /// Counters of `owner` with their keys
+    pub fn list_counters(&self, owner: &Pubkey) -> Result<Vec<(Pubkey, Counter)>> {
+        self.find_counters(Some(owner))
+    }
+
+    /// Counters of every user, or of `owner` only, with their keys
+    pub fn find_counters(&self, owner: Option<&Pubkey>) -> Result<Vec<(Pubkey, Counter)>> {
+        let mut filters = vec![RpcFilterType::DataSize(Counter::LEN as u64)];
+        if let Some(owner) = owner {
+            filters.push(RpcFilterType::Memcmp(Memcmp {
+                offset: Counter::OWNER_OFFSET,
+                bytes: MemcmpEncodedBytes::Binary(owner.to_string()),
+                encoding: None,
+            }));
+        }
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        let accounts = self.rpc.get_program_accounts_with_config(&id(), config)?;
+        Ok(accounts
+            .into_iter()
+            .filter_map(|(counter_pk, counter_acc)| {
+                Counter::try_from_slice(&counter_acc.data).ok().map(|counter| (counter_pk, counter))
+            })
+            .collect())
+    }
+
+    /// Create settings with `admin` or update them, admin pays for the account
+    pub fn init_settings(&self, admin: &Keypair, inc_step: u32, dec_step: u32) -> Result<Executed<Settings>> {
+        self.update_settings(admin, &admin.pubkey(), inc_step, dec_step)
+    }
+
+    /// Update steps and transfer the admin rights to `new_admin`
+    pub fn update_settings(
+        &self,
+        admin: &Keypair,
+        new_admin: &Pubkey,
+        inc_step: u32,
+        dec_step: u32,
+    ) -> Result<Executed<Settings>> {
+        let instr = CounterInstruction::upd_sett_instr(admin.pubkey(), *new_admin, inc_step, dec_step);
+        self.execute(&[instr], admin, &[], &Settings::get_settings_pk_with_bump().0)
+    }
+
+    pub fn set_reset_period(&self, admin: &Keypair, period: ResetPeriod) -> Result<Executed<Settings>> {
+        let instr = CounterInstruction::set_reset_period_instr(admin.pubkey(), period);
+        self.execute(&[instr], admin, &[], &Settings::get_settings_pk_with_bump().0)
+    }
+
+    pub fn set_access_mode(&self, admin: &Keypair, mode: AccessMode) -> Result<Executed<Settings>> {
+        let instr = CounterInstruction::set_access_mode_instr(admin.pubkey(), mode);
+        self.execute(&[instr], admin, &[], &Settings::get_settings_pk_with_bump().0)
+    }
+
+    /// Add `user` to the access list, returning the page taking it
+    pub fn add_access(&self, admin: &Keypair, user: &Pubkey) -> Result<Executed<AccessPage>> {
+        let (index, pages) = self.access_page_index(user)?;
+        let instr = CounterInstruction::add_access_instr(admin.pubkey(), *user, pages);
+        self.execute(&[instr], admin, &[], &AccessPage::get_access_page_pk_with_bump(AccessPage::bucket(user), index).0)
+    }
+
+    /// Remove `user` from the access list, returning the page that listed it
+    /// or the last page of the bucket when it was not listed
+    pub fn remove_access(&self, admin: &Keypair, user: &Pubkey) -> Result<Executed<AccessPage>> {
+        let (index, pages) = self.access_page_index(user)?;
+        let index = index.min(pages.saturating_sub(1));
+        let instr = CounterInstruction::remove_access_instr(admin.pubkey(), *user, pages);
+        self.execute(&[instr], admin, &[], &AccessPage::get_access_page_pk_with_bump(AccessPage::bucket(user), index).0)
+    }
+
+    pub fn grant_role(&self, admin: &Keypair, user: &Pubkey, role: Role) -> Result<Executed<Roles>> {
+        let instr = CounterInstruction::grant_role_instr(admin.pubkey(), *user, role);
+        self.execute(&[instr], admin, &[], &Roles::get_roles_pk_with_bump().0)
+    }
+
+    pub fn revoke_role(&self, admin: &Keypair, user: &Pubkey, role: Role) -> Result<Executed<Roles>> {
+        let instr = CounterInstruction::revoke_role_instr(admin.pubkey(), *user, role);
+        self.execute(&[instr], admin, &[], &Roles::get_roles_pk_with_bump().0)
+    }
+
+    /// Create the counter `name` of `user`, the user pays for the account
+    pub fn create_counter(&self, user: &Keypair, name: &str) -> Result<Executed<Counter>> {
+        let counter_pk = Counter::generate_counter_pk(&user.pubkey(), name)?;
+        let instr = CounterInstruction::create_instr(user.pubkey(), user.pubkey(), name)?;
+        self.execute(&[instr], user, &[], &counter_pk)
+    }
+
+    pub fn inc(&self, user: &Keypair, name: &str) -> Result<Executed<Counter>> {
+        self.checked_operation(user, name, CounterInstruction::inc_instr)
+    }
+
+    pub fn dec(&self, user: &Keypair, name: &str) -> Result<Executed<Counter>> {
+        self.checked_operation(user, name, CounterInstruction::dec_instr)
+    }
+
+    pub fn reset(&self, user: &Keypair, name: &str) -> Result<Executed<Counter>> {
+        self.checked_operation(user, name, CounterInstruction::reset_instr)
+    }
+
+    pub fn set_label(&self, user: &Keypair, name: &str, label: &str) -> Result<Executed<Counter>> {
+        self.user_operation(user, name, |user, name| CounterInstruction::set_label_instr(user, name, label))
+    }
+
+    /// Operation checked against the access list, so it passes every access page of the owner bucket
+    fn checked_operation<F>(&self, user: &Keypair, name: &str, instr: F) -> Result<Executed<Counter>>
+    where
+        F: FnOnce(Pubkey, &str) -> std::result::Result<Instruction, PubkeyError>,
+    {
+        let pages = self.access_pages(&user.pubkey())?;
+        self.user_operation(user, name, |owner, name| {
+            Ok(CounterInstruction::with_access_pages(instr(owner, name)?, &owner, pages))
+        })
+    }
+
+    fn user_operation<F>(&self, user: &Keypair, name: &str, instr: F) -> Result<Executed<Counter>>
+    where
+        F: FnOnce(Pubkey, &str) -> std::result::Result<Instruction, PubkeyError>,
+    {
+        let counter_pk = Counter::generate_counter_pk(&user.pubkey(), name)?;
+        let instr = instr(user.pubkey(), name)?;
+        self.execute(&[instr], user, &[], &counter_pk)
+    }
+
+    pub fn freeze(&self, admin: &Keypair, user: &Pubkey, name: &str) -> Result<Executed<Counter>> {
+        self.moderate(admin, user, name, CounterInstruction::freeze_instr)
+    }
+
+    pub fn thaw(&self, admin: &Keypair, user: &Pubkey, name: &str) -> Result<Executed<Counter>> {
+        self.moderate(admin, user, name, CounterInstruction::thaw_instr)
+    }
+
+    pub fn admin_reset(&self, admin: &Keypair, user: &Pubkey, name: &str) -> Result<Executed<Counter>> {
+        self.moderate(admin, user, name, CounterInstruction::admin_reset_instr)
+    }
+
+    fn moderate<F>(&self, admin: &Keypair, user: &Pubkey, name: &str, instr: F) -> Result<Executed<Counter>>
+    where
+        F: FnOnce(Pubkey, Pubkey, &str) -> std::result::Result<Instruction, PubkeyError>,
+    {
+        let counter_pk = Counter::generate_counter_pk(user, name)?;
+        let instr = instr(admin.pubkey(), *user, name)?;
+        self.execute(&[instr], admin, &[], &counter_pk)
+    }
+
+    /// Operation of `user` for `relay` with the current relay nonce of the counter, valid until unix time `expiry`
+    pub fn relay_request(&self, user: &Pubkey, name: &str, op: OperationKind, expiry: i64) -> Result<RelayRequest> {
+        let counter = self.get_counter_at(&Counter::generate_counter_pk(user, name)?)?;
+        Ok(RelayRequest { user: *user, name: name.to_string(), op, nonce: counter.relay_nonce, expiry })
+    }
+
+    /// Operation signed by its user offline and submitted by `relayer`, who pays the fee.
+    /// `signature` is made by the user with `Keypair::sign_message` of `request.message()`
+    pub fn relay(&self, relayer: &Keypair, request: &RelayRequest, signature: &Signature) -> Result<Executed<Counter>> {
+        let counter_pk = Counter::generate_counter_pk(&request.user, &request.name)?;
+        let ed25519_instr = ed25519_instruction(&request.user, signature.as_ref(), &request.message()?);
+        let relayed_instr =
+            CounterInstruction::relayed_instr(request.user, &request.name, request.op, request.nonce, request.expiry)?;
+        let relayed_instr =
+            CounterInstruction::with_access_pages(relayed_instr, &request.user, self.access_pages(&request.user)?);
+        self.execute(&[ed25519_instr, relayed_instr], relayer, &[], &counter_pk)
+    }
+
+    /// Allow `session_key` to apply `ops` to counters of `user` until unix time `expiry`
+    pub fn create_session(
+        &self,
+        user: &Keypair,
+        session_key: &Pubkey,
+        expiry: i64,
+        ops: &[OperationKind],
+    ) -> Result<Sent> {
+        let instr = CounterInstruction::create_session_instr(user.pubkey(), *session_key, expiry, ops);
+        self.send(&[instr], user, &[])
+    }
+
+    /// Operation signed by `session_key` only, `payer` pays the fee
+    pub fn session_op(
+        &self,
+        payer: &Keypair,
+        session_key: &Keypair,
+        owner: &Pubkey,
+        name: &str,
+        op: OperationKind,
+    ) -> Result<Executed<Counter>> {
+        let counter_pk = Counter::generate_counter_pk(owner, name)?;
+        let instr = CounterInstruction::session_op_instr(session_key.pubkey(), *owner, name, op)?;
+        let instr = CounterInstruction::with_access_pages(instr, owner, self.access_pages(owner)?);
+        self.execute(&[instr], payer, &[session_key], &counter_pk)
+    }
+
+    pub fn revoke_session(&self, user: &Keypair, session_key: &Pubkey) -> Result<Sent> {
+        self.send(&[CounterInstruction::revoke_session_instr(user.pubkey(), *session_key)], user, &[])
+    }
+}
+
+#[cfg(test)]
+mod client_test {
+    use counter::instruction::OperationKind;
+    use solana_sdk::ed25519_instruction::new_ed25519_instruction;
+    use solana_sdk::signature::{Keypair, Signer};
+    use counter::instruction::ed25519_instruction;
+    use crate::client::RelayRequest;
+
+    #[test]
+    fn when_user_signed_request_expect_same_ed25519_instruction_as_with_keypair() {
+        let user = Keypair::new();
+        let request =
+            RelayRequest { user: user.pubkey(), name: "a".to_string(), op: OperationKind::Dec, nonce: 3, expiry: 60 };
+        let message = request.message().unwrap();
+
+        let instr = ed25519_instruction(&user.pubkey(), user.sign_message(&message).as_ref(), &message);
+
+        let user_keypair = ed25519_dalek::Keypair::from_bytes(&user.to_bytes()).unwrap();
+        assert_eq!(instr, new_ed25519_instruction(&user_keypair, &message));
+        assert_ne!(message, RelayRequest { nonce: 4, ..request.clone() }.message().unwrap());
+    }
+}
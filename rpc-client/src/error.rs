@@ -0,0 +1,160 @@
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_program::pubkey::{Pubkey, PubkeyError};
+use solana_sdk::signer::SignerError;
+use solana_sdk::transaction::Transaction;
+use counter::error::CounterError;
+use thiserror::Error;
+use crate::simulate::{decode_logs, instruction_programs, Simulation};
+
+#[derive(Error, Debug)]
+pub enum CounterClientError {
+    /// Counter instruction failed with the program error
+    #[error("{0:?}: {0}")]
+    Program(CounterError),
+    #[error(transparent)]
+    Rpc(Box<ClientError>),
+    #[error("Account {0} not found")]
+    AccountNotFound(Pubkey),
+    #[error("Account {0} data is invalid: {1}")]
+    InvalidAccountData(Pubkey, std::io::Error),
+    #[error("Account {0} is not an initialized nonce account")]
+    InvalidNonceAccount(Pubkey),
+    #[error("Counter name is invalid: {0}")]
+    InvalidCounterName(#[from] PubkeyError),
+    #[error("Transaction can't be decoded: {0}")]
+    InvalidTransaction(String),
+    #[error("Transaction can't be signed: {0}")]
+    Signer(#[from] SignerError),
+    #[error("{0} is not a signer of the transaction")]
+    UnexpectedSigner(Pubkey),
+    #[error("Signature of {0} does not match the transaction")]
+    InvalidSignature(Pubkey),
+    #[error("Transaction is not signed by {0}")]
+    MissingSignature(Pubkey),
+    /// Transaction failed in the simulation before sending, no fee was paid
+    #[error("Simulation failed: {}", simulation_error(.0))]
+    SimulationFailed(Box<Simulation>),
+    /// Transient failures did not stop before the retries ran out
+    #[error("Failed after {0} attempts: {1}")]
+    RetriesExhausted(u32, Box<CounterClientError>),
+}
+
+/// Error codes can't be told from the ones of other programs without the transaction, see `from_transaction`
+impl From<ClientError> for CounterClientError {
+    fn from(err: ClientError) -> Self {
+        CounterClientError::Rpc(Box::new(err))
+    }
+}
+
+impl CounterClientError {
+    /// Error of sending `tx`, a failed counter instruction is reported by its `CounterError` instead of the raw code.
+    /// Preflight logs returned by the node tell a failed inner program from the counter one
+    pub fn from_transaction(err: ClientError, tx: &Transaction) -> Self {
+        let logs = match err.kind() {
+            ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+                ..
+            }) => result.logs.clone().unwrap_or_default(),
+            _ => vec![],
+        };
+        let simulation = Simulation {
+            error: err.get_transaction_error(),
+            logs: decode_logs(&logs),
+            accounts: vec![],
+            programs: instruction_programs(tx),
+        };
+        match simulation.counter_error() {
+            Some(counter_err) => CounterClientError::Program(counter_err),
+            None => CounterClientError::Rpc(Box::new(err)),
+        }
+    }
+
+    /// Error of the counter program that rejected the transaction, sent or simulated
+    pub fn counter_error(&self) -> Option<CounterError> {
+        match self {
+            CounterClientError::Program(counter_err) => Some(*counter_err),
+            CounterClientError::SimulationFailed(simulation) => simulation.counter_error(),
+            _ => None,
+        }
+    }
+}
+
+fn simulation_error(simulation: &Simulation) -> String {
+    match (simulation.counter_error(), &simulation.error) {
+        (Some(counter_err), _) => format!("{:?}: {}", counter_err, counter_err),
+        (None, Some(tx_err)) => tx_err.to_string(),
+        (None, None) => "no error".to_string(),
+    }
+}
+
+pub type Result<T> = std::result::Result<T, CounterClientError>;
+
+#[cfg(test)]
+mod error_test {
+    use counter::error::CounterError;
+    use counter::id;
+    use counter::instruction::CounterInstruction;
+    use serde_json::json;
+    use solana_client::client_error::ClientError;
+    use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+    use solana_program::pubkey::Pubkey;
+    use solana_program::system_instruction;
+    use solana_sdk::instruction::{Instruction, InstructionError};
+    use solana_sdk::transaction::{Transaction, TransactionError};
+    use crate::error::CounterClientError;
+
+    fn tx(instr: Instruction) -> Transaction {
+        Transaction::new_with_payer(&[instr], Some(&Pubkey::new_unique()))
+    }
+
+    fn custom(code: u32) -> ClientError {
+        ClientError::from(TransactionError::InstructionError(0, InstructionError::Custom(code)))
+    }
+
+    #[test]
+    fn when_counter_instruction_failed_expect_program_error() {
+        let user = Pubkey::new_unique();
+        let code = CounterError::Frozen as u32;
+
+        let err = CounterClientError::from_transaction(custom(code), &tx(CounterInstruction::inc_instr(user, "").unwrap()));
+
+        assert!(matches!(err, CounterClientError::Program(CounterError::Frozen)));
+    }
+
+    #[test]
+    fn when_other_program_failed_expect_rpc_error() {
+        let transfer_instr = system_instruction::transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1);
+
+        let err = CounterClientError::from_transaction(custom(0), &tx(transfer_instr));
+
+        assert!(matches!(err, CounterClientError::Rpc(_)));
+        assert!(matches!(CounterClientError::from(custom(0)), CounterClientError::Rpc(_)));
+    }
+
+    #[test]
+    fn when_inner_program_failed_in_preflight_expect_rpc_error() {
+        let system = Pubkey::default();
+        let result = serde_json::from_value(json!({
+            "err": {"InstructionError": [0, {"Custom": 0}]},
+            "logs": [
+                format!("Program {} invoke [1]", id()),
+                format!("Program {} invoke [2]", system),
+                format!("Program {} failed: custom program error: 0x0", system),
+                format!("Program {} failed: custom program error: 0x0", id()),
+            ],
+            "accounts": null,
+        }))
+        .unwrap();
+        let preflight_err = ClientError::from(RpcError::RpcResponseError {
+            code: -32002,
+            message: "Transaction simulation failed".to_string(),
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+        });
+        let create_instr = CounterInstruction::create_instr(Pubkey::new_unique(), Pubkey::new_unique(), "").unwrap();
+
+        let err = CounterClientError::from_transaction(preflight_err, &tx(create_instr));
+
+        assert!(matches!(err, CounterClientError::Rpc(_)));
+    }
+}
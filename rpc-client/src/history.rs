@@ -0,0 +1,223 @@
+use std::str::FromStr;
+use borsh::BorshDeserialize;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction_status::{EncodedTransaction, UiMessage, UiTransactionEncoding};
+use counter::id;
+use counter::instruction::CounterInstruction;
+use crate::client::CounterClient;
+use crate::error::Result;
+
+/// Maximum number of signatures the node returns per request
+const PAGE_SIZE: usize = 1000;
+
+/// Counter instruction of a transaction with its accounts
+#[derive(Debug, PartialEq)]
+pub struct Call {
+    /// `None` when the data is not a known instruction
+    pub instruction: Option<CounterInstruction>,
+    pub accounts: Vec<Pubkey>,
+    /// Set when the transaction failed on this instruction
+    pub error: Option<InstructionError>,
+}
+
+/// Transaction that referenced the account
+#[derive(Debug)]
+pub struct Entry {
+    pub signature: Signature,
+    pub slot: u64,
+    /// Unix timestamp, `None` when the node does not know it
+    pub block_time: Option<i64>,
+    /// First signer, `None` when the transaction was not returned by the node
+    pub payer: Option<Pubkey>,
+    /// `None` for a successful transaction
+    pub error: Option<TransactionError>,
+    /// Counter instructions in transaction order, instructions of other programs are skipped
+    pub calls: Vec<Call>,
+}
+
+/// Last `limit` transactions referencing `address` with decoded counter instructions, oldest first
+pub fn timeline(client: &CounterClient, address: &Pubkey, limit: usize) -> Result<Vec<Entry>> {
+    let rpc = client.rpc();
+    let mut statuses = vec![];
+    let mut before = None;
+    while statuses.len() < limit {
+        let requested = (limit - statuses.len()).min(PAGE_SIZE);
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until: None,
+            limit: Some(requested),
+            commitment: Some(rpc.commitment()),
+        };
+        let page = rpc.get_signatures_for_address_with_config(address, config)?;
+        let last_page = page.len() < requested;
+        // Newest first, the next page starts before the oldest one
+        before = page.last().and_then(|status| Signature::from_str(&status.signature).ok());
+        statuses.extend(page);
+        if last_page || before.is_none() {
+            break;
+        }
+    }
+
+    let mut entries = vec![];
+    for status in statuses.into_iter().rev() {
+        let signature = match Signature::from_str(&status.signature) {
+            Ok(signature) => signature,
+            Err(_) => continue,
+        };
+        // Pruned or unavailable transaction is listed with its status only
+        let transaction = rpc.get_transaction(&signature, UiTransactionEncoding::Json).ok();
+        let encoded = transaction.as_ref().map(|transaction| &transaction.transaction.transaction);
+        let (payer, calls) = decode_calls(encoded, status.err.as_ref());
+        entries.push(Entry {
+            signature,
+            slot: status.slot,
+            block_time: status.block_time,
+            payer,
+            error: status.err,
+            calls,
+        });
+    }
+    Ok(entries)
+}
+
+/// Fee payer and counter instructions of a JSON encoded transaction, none when the node did not return it
+fn decode_calls(
+    transaction: Option<&EncodedTransaction>,
+    error: Option<&TransactionError>,
+) -> (Option<Pubkey>, Vec<Call>) {
+    let message = match transaction {
+        Some(EncodedTransaction::Json(transaction)) => match &transaction.message {
+            UiMessage::Raw(message) => message,
+            UiMessage::Parsed(_) => return (None, vec![]),
+        },
+        _ => return (None, vec![]),
+    };
+    let keys: Vec<Pubkey> = message.account_keys.iter().filter_map(|key| Pubkey::from_str(key).ok()).collect();
+    if keys.len() != message.account_keys.len() {
+        return (None, vec![]);
+    }
+    let failed = match error {
+        Some(TransactionError::InstructionError(index, instr_err)) => Some((*index as usize, instr_err)),
+        _ => None,
+    };
+    let calls = message
+        .instructions
+        .iter()
+        .enumerate()
+        .filter(|(_, instr)| keys.get(instr.program_id_index as usize) == Some(&id()))
+        .map(|(index, instr)| Call {
+            instruction: bs58::decode(&instr.data)
+                .into_vec()
+                .ok()
+                .and_then(|data| CounterInstruction::try_from_slice(&data).ok()),
+            accounts: instr.accounts.iter().filter_map(|account| keys.get(*account as usize).copied()).collect(),
+            error: failed.filter(|(failed_index, _)| *failed_index == index).map(|(_, instr_err)| instr_err.clone()),
+        })
+        .collect();
+    (keys.first().copied(), calls)
+}
+
+#[cfg(test)]
+mod history_test {
+    use borsh::BorshSerialize;
+    use counter::id;
+    use counter::instruction::CounterInstruction;
+    use serde_json::json;
+    use solana_program::pubkey::Pubkey;
+    use solana_sdk::instruction::InstructionError;
+    use solana_sdk::transaction::TransactionError;
+    use solana_transaction_status::EncodedTransaction;
+    use crate::history::{decode_calls, Call};
+
+    /// JSON encoded transaction with instructions `(program, accounts, data)` referencing `keys` by index
+    fn transaction(keys: &[Pubkey], instructions: &[(u8, Vec<u8>, Vec<u8>)]) -> EncodedTransaction {
+        let instructions: Vec<_> = instructions
+            .iter()
+            .map(|(program, accounts, data)| {
+                json!({
+                    "programIdIndex": program,
+                    "accounts": accounts,
+                    "data": bs58::encode(data).into_string(),
+                })
+            })
+            .collect();
+        serde_json::from_value(json!({
+            "signatures": [],
+            "message": {
+                "header": {
+                    "numRequiredSignatures": 1,
+                    "numReadonlySignedAccounts": 0,
+                    "numReadonlyUnsignedAccounts": 1,
+                },
+                "accountKeys": keys.iter().map(Pubkey::to_string).collect::<Vec<_>>(),
+                "recentBlockhash": Pubkey::default().to_string(),
+                "instructions": instructions,
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn when_counter_instructions_expect_decoded_with_accounts() {
+        let (payer, counter, other_program) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let keys = [payer, counter, other_program, id()];
+        let tx = transaction(
+            &keys,
+            &[
+                (3, vec![0, 1], CounterInstruction::Inc.try_to_vec().unwrap()),
+                (2, vec![0], vec![1, 2, 3]),
+                (3, vec![0, 1], CounterInstruction::Dec.try_to_vec().unwrap()),
+            ],
+        );
+
+        let (decoded_payer, calls) = decode_calls(Some(&tx), None);
+
+        assert_eq!(decoded_payer, Some(payer));
+        assert_eq!(
+            calls,
+            vec![
+                Call { instruction: Some(CounterInstruction::Inc), accounts: vec![payer, counter], error: None },
+                Call { instruction: Some(CounterInstruction::Dec), accounts: vec![payer, counter], error: None },
+            ]
+        );
+    }
+
+    #[test]
+    fn when_transaction_failed_expect_error_on_failed_instruction() {
+        let keys = [Pubkey::new_unique(), Pubkey::new_unique(), id()];
+        let tx = transaction(
+            &keys,
+            &[
+                (2, vec![0, 1], CounterInstruction::Inc.try_to_vec().unwrap()),
+                (2, vec![0, 1], CounterInstruction::Freeze.try_to_vec().unwrap()),
+            ],
+        );
+        let error = TransactionError::InstructionError(1, InstructionError::Custom(3));
+
+        let (_, calls) = decode_calls(Some(&tx), Some(&error));
+
+        assert_eq!(calls[0].error, None);
+        assert_eq!(calls[1].error, Some(InstructionError::Custom(3)));
+    }
+
+    #[test]
+    fn when_unknown_instruction_data_expect_none() {
+        let keys = [Pubkey::new_unique(), id()];
+        let tx = transaction(&keys, &[(1, vec![0], vec![255])]);
+
+        let (_, calls) = decode_calls(Some(&tx), None);
+
+        assert_eq!(calls[0].instruction, None);
+    }
+
+    #[test]
+    fn when_transaction_not_returned_expect_no_payer_and_calls() {
+        let error = TransactionError::InstructionError(0, InstructionError::Custom(3));
+
+        assert_eq!(decode_calls(None, Some(&error)), (None, vec![]));
+    }
+}
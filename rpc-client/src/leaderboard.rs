@@ -0,0 +1,147 @@
+use counter::state::Counter;
+use solana_program::pubkey::Pubkey;
+
+/// Range of counter values, both ends inclusive, with the number of counters in it
+#[derive(Debug, PartialEq)]
+pub struct Bucket {
+    pub from: i64,
+    pub to: i64,
+    pub count: usize,
+}
+
+/// Counters ranked by value
+pub struct Leaderboard {
+    /// Highest value first, counters with equal values are ordered by key
+    pub counters: Vec<(Pubkey, Counter)>,
+    /// Sum of the values, wide enough for any number of counters
+    pub total: i128,
+}
+
+impl Leaderboard {
+    pub fn new(mut counters: Vec<(Pubkey, Counter)>) -> Self {
+        counters.sort_by(|(a_pk, a), (b_pk, b)| b.value.cmp(&a.value).then_with(|| a_pk.cmp(b_pk)));
+        let total = counters.iter().map(|(_, counter)| counter.value as i128).sum();
+        Leaderboard { counters, total }
+    }
+
+    /// Highest `limit` counters, highest first
+    pub fn top(&self, limit: usize) -> &[(Pubkey, Counter)] {
+        &self.counters[..limit.min(self.counters.len())]
+    }
+
+    /// Lowest `limit` counters, lowest first
+    pub fn bottom(&self, limit: usize) -> impl Iterator<Item = &(Pubkey, Counter)> {
+        self.counters.iter().rev().take(limit)
+    }
+
+    /// `None` without counters
+    pub fn mean(&self) -> Option<f64> {
+        match self.counters.len() {
+            0 => None,
+            count => Some(self.total as f64 / count as f64),
+        }
+    }
+
+    /// At most `buckets` ranges of equal width from the lowest to the highest value
+    pub fn histogram(&self, buckets: usize) -> Vec<Bucket> {
+        let (max, min) = match (self.counters.first(), self.counters.last()) {
+            (Some((_, max)), Some((_, min))) if buckets > 0 => (max.value as i128, min.value as i128),
+            _ => return vec![],
+        };
+        let span = max - min + 1;
+        let width = (span + buckets as i128 - 1) / buckets as i128;
+        let mut histogram: Vec<Bucket> = (0..(span + width - 1) / width)
+            .map(|index| {
+                let from = min + index * width;
+                Bucket { from: from as i64, to: (from + width - 1).min(max) as i64, count: 0 }
+            })
+            .collect();
+        for (_, counter) in &self.counters {
+            histogram[((counter.value as i128 - min) / width) as usize].count += 1;
+        }
+        histogram
+    }
+}
+
+#[cfg(test)]
+mod leaderboard_test {
+    use counter::state::Counter;
+    use solana_program::pubkey::Pubkey;
+    use crate::leaderboard::{Bucket, Leaderboard};
+
+    fn counters(values: &[i64]) -> Vec<(Pubkey, Counter)> {
+        values
+            .iter()
+            .map(|value| {
+                let mut counter = Counter::new(Pubkey::new_unique(), "", 0);
+                counter.value = *value;
+                (Pubkey::new_unique(), counter)
+            })
+            .collect()
+    }
+
+    fn values<'a>(counters: impl IntoIterator<Item = &'a (Pubkey, Counter)>) -> Vec<i64> {
+        counters.into_iter().map(|(_, counter)| counter.value).collect()
+    }
+
+    #[test]
+    fn when_counters_expect_ranked_by_value() {
+        let leaderboard = Leaderboard::new(counters(&[3, -5, 10, 0, 7]));
+
+        assert_eq!(values(leaderboard.top(3)), vec![10, 7, 3]);
+        assert_eq!(values(leaderboard.bottom(2)), vec![-5, 0]);
+        assert_eq!(values(leaderboard.top(10)).len(), 5);
+        assert_eq!(leaderboard.total, 15);
+        assert_eq!(leaderboard.mean(), Some(3.0));
+    }
+
+    #[test]
+    fn when_equal_values_expect_ordered_by_key() {
+        let leaderboard = Leaderboard::new(counters(&[1, 1, 1]));
+        let keys: Vec<_> = leaderboard.counters.iter().map(|(counter_pk, _)| *counter_pk).collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+
+        assert_eq!(keys, sorted);
+    }
+
+    #[test]
+    fn when_no_counters_expect_empty_statistics() {
+        let leaderboard = Leaderboard::new(vec![]);
+
+        assert_eq!(leaderboard.mean(), None);
+        assert_eq!(leaderboard.histogram(10), vec![]);
+    }
+
+    #[test]
+    fn when_histogram_expect_equal_ranges_covering_values() {
+        let leaderboard = Leaderboard::new(counters(&[-5, -1, 0, 4, 4, 10]));
+
+        assert_eq!(
+            leaderboard.histogram(4),
+            vec![
+                Bucket { from: -5, to: -2, count: 1 },
+                Bucket { from: -1, to: 2, count: 2 },
+                Bucket { from: 3, to: 6, count: 2 },
+                Bucket { from: 7, to: 10, count: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn when_extreme_values_expect_no_overflow() {
+        let leaderboard = Leaderboard::new(counters(&[i64::MIN, i64::MAX, i64::MAX]));
+
+        assert_eq!(leaderboard.total, i64::MAX as i128 - 1);
+        let histogram = leaderboard.histogram(2);
+        assert_eq!(histogram.iter().map(|bucket| bucket.count).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(histogram[1].to, i64::MAX);
+    }
+
+    #[test]
+    fn when_fewer_values_than_buckets_expect_unit_ranges() {
+        let leaderboard = Leaderboard::new(counters(&[2, 4]));
+
+        assert_eq!(leaderboard.histogram(10).len(), 3);
+    }
+}
@@ -0,0 +1,14 @@
+//! Client library of the counter program, used by the `counter-rpc-client` binary
+
+pub mod client;
+pub mod error;
+pub mod history;
+pub mod leaderboard;
+pub mod nonce;
+pub mod offline;
+pub mod send;
+pub mod simulate;
+pub mod watch;
+
+pub use client::CounterClient;
+pub use error::{CounterClientError, Result};
@@ -1,199 +1,1052 @@
+use std::collections::HashMap;
 use std::error::Error;
-use std::borrow::Borrow;
-use std::env;
-use std::time::Duration;
-use counter::state::{Counter, Settings};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use clap::{crate_version, App, AppSettings, Arg, ArgMatches, SubCommand};
+use counter::state::{AccessMode, AccessPage, Counter, ResetPeriod, Role, Roles, Session, Settings};
+use counter::instruction::{CounterInstruction, OperationKind};
+use counter_rpc_client::history;
+use counter_rpc_client::leaderboard::Leaderboard;
+use counter_rpc_client::nonce;
+use counter_rpc_client::offline::{self, Encoding};
+use counter_rpc_client::send::SendConfig;
+use counter_rpc_client::watch::{self, websocket_url, AccountState, Watch, WatchEvent};
+use counter_rpc_client::client::RelayRequest;
+use counter_rpc_client::{CounterClient, CounterClientError};
+use solana_program::hash::Hash;
 use solana_program::pubkey::Pubkey;
-use solana_client::rpc_client::RpcClient;
-use solana_sdk::signature::{Keypair, read_keypair_file};
-use counter;
-use counter::instruction::CounterInstruction;
-use solana_sdk::account::Account;
 use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signature, read_keypair_file};
 use solana_sdk::signer::Signer;
-use solana_sdk::transaction::Transaction;
-use borsh::BorshSerialize;
-use borsh::BorshDeserialize;
-use counter::COUNTER_SEED;
-use solana_program::system_instruction;
+use output::{print_update, Format, Report, State};
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let app = App::new()?;
+mod output;
 
-    app.update_settings()?;
-    app.create_counter_account()?;
+/// Wrong arguments or unreadable keypair
+const EXIT_USAGE: i32 = 1;
+/// RPC request failed or returned unexpected data
+const EXIT_RPC: i32 = 2;
+/// Transaction was rejected by the counter program
+const EXIT_PROGRAM: i32 = 3;
+/// Requested account does not exist
+const EXIT_NOT_FOUND: i32 = 4;
 
-    app.increment_counter()?;
-    app.decrement_counter()?;
-    app.reset_counter()?;
+fn main() {
+    let matches = app().get_matches();
+    let format = match matches.value_of("output") {
+        Some("json") => Format::Json,
+        _ => Format::Text,
+    };
+    let mut report = Report::new(command_name(&matches));
+    let result = run(&matches, format, &mut report);
+    report.print(format, result.as_ref().err().map(AsRef::as_ref));
+    if let Err(err) = result {
+        std::process::exit(exit_code(err.as_ref()));
+    }
+}
+
+/// Subcommand path, e.g. `settings set`
+fn command_name(matches: &ArgMatches) -> String {
+    let mut names = vec![];
+    let mut matches = matches;
+    while let (name, Some(sub_matches)) = matches.subcommand() {
+        names.push(name);
+        matches = sub_matches;
+    }
+    names.join(" ")
+}
+
+fn exit_code(err: &(dyn Error + 'static)) -> i32 {
+    match err.downcast_ref::<CounterClientError>() {
+        Some(err) if err.counter_error().is_some() => EXIT_PROGRAM,
+        Some(CounterClientError::AccountNotFound(_)) => EXIT_NOT_FOUND,
+        Some(CounterClientError::Rpc(_))
+        | Some(CounterClientError::RetriesExhausted(..))
+        | Some(CounterClientError::SimulationFailed(_))
+        | Some(CounterClientError::InvalidAccountData(..))
+        | Some(CounterClientError::InvalidNonceAccount(_)) => EXIT_RPC,
+        _ => EXIT_USAGE,
+    }
+}
+
+fn is_pubkey(value: String) -> Result<(), String> {
+    Pubkey::from_str(&value).map(|_| ()).map_err(|err| format!("{}: {}", value, err))
+}
+
+fn is_hash(value: String) -> Result<(), String> {
+    Hash::from_str(&value).map(|_| ()).map_err(|err| format!("{}: {}", value, err))
+}
+
+fn is_signature(value: String) -> Result<(), String> {
+    Signature::from_str(&value).map(|_| ()).map_err(|err| format!("{}: {}", value, err))
+}
+
+/// `PUBKEY=SIGNATURE` of a signer
+fn is_signer_signature(value: String) -> Result<(), String> {
+    match value.split_once('=') {
+        Some((pubkey, signature)) => {
+            is_pubkey(pubkey.to_string())?;
+            is_signature(signature.to_string())
+        }
+        None => Err(format!("{}: expected PUBKEY=SIGNATURE", value)),
+    }
+}
+
+fn is_number(value: String) -> Result<(), String> {
+    value.parse::<u64>().map(|_| ()).map_err(|err| format!("{}: {}", value, err))
+}
+
+fn is_u32(value: String) -> Result<(), String> {
+    value.parse::<u32>().map(|_| ()).map_err(|err| format!("{}: {}", value, err))
+}
+
+fn pubkey_arg(name: &'static str, help: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name(name).required(true).validator(is_pubkey).help(help)
+}
+
+fn name_arg() -> Arg<'static, 'static> {
+    Arg::with_name("name")
+        .long("name")
+        .takes_value(true)
+        .default_value("")
+        .help("Counter name, empty name is the default counter of a user")
+}
+
+fn user_arg() -> Arg<'static, 'static> {
+    Arg::with_name("user")
+        .long("user")
+        .value_name("KEYPAIR")
+        .takes_value(true)
+        .help("Keypair of the counter owner instead of --keypair")
+}
+
+fn op_arg() -> Arg<'static, 'static> {
+    Arg::with_name("op").required(true).possible_values(&["inc", "dec"])
+}
+
+/// Operation on a counter of the user
+fn counter_command(name: &'static str, about: &'static str) -> App<'static, 'static> {
+    SubCommand::with_name(name).about(about).arg(name_arg()).arg(user_arg())
+}
+
+/// Moderation of a counter of any user
+fn moderation_command(name: &'static str, about: &'static str) -> App<'static, 'static> {
+    SubCommand::with_name(name)
+        .about(about)
+        .arg(pubkey_arg("owner", "Owner of the counter"))
+        .arg(name_arg())
+}
+
+fn app() -> App<'static, 'static> {
+    App::new("counter")
+        .version(crate_version!())
+        .about("Client of the counter program")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .after_help(
+            "EXIT CODES:\n    0    success\n    1    wrong arguments or keypair\n    2    RPC failure\n    \
+             3    transaction rejected by the counter program\n    4    account not found",
+        )
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .short("u")
+                .takes_value(true)
+                .default_value("http://localhost:8899")
+                .help("Cluster RPC URL"),
+        )
+        .arg(
+            Arg::with_name("keypair")
+                .long("keypair")
+                .short("k")
+                .takes_value(true)
+                .default_value("../keypair/user.json")
+                .help("Keypair of the user, pays for user transactions"),
+        )
+        .arg(
+            Arg::with_name("admin-keypair")
+                .long("admin-keypair")
+                .takes_value(true)
+                .default_value("../keypair/admin.json")
+                .help("Keypair of the admin or a role holder, pays for admin transactions"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .default_value("text")
+                .help("Output format, json prints one document with the signature, accounts and states"),
+        )
+        .arg(
+            Arg::with_name("sign-only")
+                .long("sign-only")
+                .help("Print an admin command as a transaction to sign offline instead of sending it"),
+        )
+        .arg(
+            Arg::with_name("simulate")
+                .long("simulate")
+                .conflicts_with("sign-only")
+                .help("Simulate the transaction of a command and print its program log instead of sending it"),
+        )
+        .arg(
+            Arg::with_name("blockhash")
+                .long("blockhash")
+                .takes_value(true)
+                .validator(is_hash)
+                .requires("sign-only")
+                .help("Blockhash of the --sign-only transaction, the latest one of the cluster by default"),
+        )
+        .arg(
+            Arg::with_name("nonce")
+                .long("nonce")
+                .takes_value(true)
+                .validator(is_pubkey)
+                .requires("sign-only")
+                .conflicts_with("blockhash")
+                .help("Nonce account replacing the blockhash of the --sign-only transaction, so it does not expire"),
+        )
+        .arg(
+            Arg::with_name("admin-pubkey")
+                .long("admin-pubkey")
+                .takes_value(true)
+                .validator(is_pubkey)
+                .requires("sign-only")
+                .help("Admin of the --sign-only transaction when its keypair is kept offline"),
+        )
+        .arg(
+            Arg::with_name("encoding")
+                .long("encoding")
+                .takes_value(true)
+                .possible_values(&["base58", "base64"])
+                .default_value("base64")
+                .help("Encoding of transactions signed offline"),
+        )
+        .arg(
+            Arg::with_name("commitment")
+                .long("commitment")
+                .takes_value(true)
+                .possible_values(&["processed", "confirmed", "finalized"])
+                .default_value("finalized")
+                .help("Commitment of confirmations and reads"),
+        )
+        .arg(
+            Arg::with_name("retries")
+                .long("retries")
+                .takes_value(true)
+                .validator(is_u32)
+                .default_value("3")
+                .help("Attempts to send a transaction after the first one failed with a network error or expired"),
+        )
+        .arg(
+            Arg::with_name("retry-delay")
+                .long("retry-delay")
+                .value_name("MILLISECONDS")
+                .takes_value(true)
+                .validator(is_number)
+                .default_value("500")
+                .help("Delay before the first retry, doubled before every next one up to 8 seconds"),
+        )
+        .arg(
+            Arg::with_name("priority-fee")
+                .long("priority-fee")
+                .value_name("MICRO_LAMPORTS")
+                .takes_value(true)
+                .validator(is_number)
+                .help("Price of a compute unit paid on top of the base fee to prioritize transactions"),
+        )
+        .arg(
+            Arg::with_name("compute-unit-limit")
+                .long("compute-unit-limit")
+                .value_name("UNITS")
+                .takes_value(true)
+                .validator(is_u32)
+                .help("Compute units a transaction may consume, the priority fee is paid for all of them"),
+        )
+        .subcommand(
+            SubCommand::with_name("settings")
+                .about("Program settings")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(SubCommand::with_name("show").about("Show settings"))
+                .subcommand(
+                    SubCommand::with_name("set")
+                        .about("Create settings or update steps, the first caller becomes the admin")
+                        .arg(Arg::with_name("inc-step").long("inc-step").takes_value(true).required(true).validator(is_u32))
+                        .arg(Arg::with_name("dec-step").long("dec-step").takes_value(true).required(true).validator(is_u32)),
+                )
+                .subcommand(
+                    SubCommand::with_name("period")
+                        .about("Reset period of all counters")
+                        .arg(Arg::with_name("kind").required(true).possible_values(&["never", "slots", "time", "epochs"]))
+                        .arg(
+                            Arg::with_name("length")
+                                .required_ifs(&[("kind", "slots"), ("kind", "time"), ("kind", "epochs")])
+                                .validator(is_number)
+                                .help("Slots, seconds or epochs"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("access")
+                        .about("Access mode of counter operations")
+                        .arg(Arg::with_name("mode").required(true).possible_values(&["off", "allowlist", "denylist"])),
+                ),
+        )
+        .subcommand(counter_command("create", "Create a counter"))
+        .subcommand(counter_command("inc", "Increment a counter"))
+        .subcommand(counter_command("dec", "Decrement a counter"))
+        .subcommand(counter_command("reset", "Reset a counter"))
+        .subcommand(counter_command("label", "Set label of a counter").arg(Arg::with_name("label").required(true)))
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Show a counter")
+                .arg(pubkey_arg("pubkey", "Counter account")),
+        )
+        .subcommand(
+            SubCommand::with_name("list")
+                .about("List counters of a user")
+                .arg(Arg::with_name("owner").validator(is_pubkey).help("Owner, the user from --keypair by default")),
+        )
+        .subcommand(
+            SubCommand::with_name("leaderboard")
+                .about("Rank counters of all users by value with the total, mean and histogram")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Only counters of the owner"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .takes_value(true)
+                        .validator(is_number)
+                        .default_value("10")
+                        .help("Number of the highest and the lowest counters"),
+                )
+                .arg(
+                    Arg::with_name("buckets")
+                        .long("buckets")
+                        .takes_value(true)
+                        .validator(is_number)
+                        .default_value("10")
+                        .help("Maximum number of histogram ranges"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("history")
+                .about("Decode counter instructions of the transactions of an account, oldest first")
+                .arg(pubkey_arg("account", "Counter, settings or any other account of the program"))
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .takes_value(true)
+                        .validator(is_number)
+                        .default_value("100")
+                        .help("Number of the latest transactions"),
+                ),
+        )
+        .subcommand(moderation_command("freeze", "Freeze a counter"))
+        .subcommand(moderation_command("thaw", "Thaw a counter"))
+        .subcommand(moderation_command("admin-reset", "Reset a counter by the admin"))
+        .subcommand(
+            SubCommand::with_name("access")
+                .about("Access list of counter operations")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(SubCommand::with_name("add").about("Add a user").arg(pubkey_arg("user", "User")))
+                .subcommand(SubCommand::with_name("remove").about("Remove a user").arg(pubkey_arg("user", "User"))),
+        )
+        .subcommand(
+            SubCommand::with_name("role")
+                .about("Roles of users")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommands(vec!["grant", "revoke"].into_iter().map(|name| {
+                    SubCommand::with_name(name)
+                        .about(if name == "grant" { "Grant a role" } else { "Revoke a role" })
+                        .arg(
+                            Arg::with_name("role")
+                                .required(true)
+                                .possible_values(&["super-admin", "settings-manager", "moderator"]),
+                        )
+                        .arg(pubkey_arg("user", "Role holder"))
+                })),
+        )
+        .subcommand(
+            SubCommand::with_name("admin")
+                .about("Admin rights")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("transfer")
+                        .about("Transfer admin rights keeping the steps")
+                        .arg(pubkey_arg("new-admin", "New admin")),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("relay")
+                .about("Operation signed by the owner offline, the admin submits it and pays the fee")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    counter_command("request", "Print the message of an operation for the owner to sign")
+                        .arg(op_arg())
+                        .arg(
+                            Arg::with_name("owner")
+                                .long("owner")
+                                .takes_value(true)
+                                .validator(is_pubkey)
+                                .conflicts_with_all(&["user", "sign"])
+                                .help("Owner of the counter, the user from --keypair by default"),
+                        )
+                        .arg(
+                            Arg::with_name("valid-for")
+                                .long("valid-for")
+                                .value_name("SECONDS")
+                                .takes_value(true)
+                                .default_value("60")
+                                .validator(is_number)
+                                .help("Time the operation stays valid"),
+                        )
+                        .arg(Arg::with_name("sign").long("sign").help("Sign the message with the owner keypair")),
+                )
+                .subcommand(
+                    SubCommand::with_name("submit")
+                        .about("Submit an operation signed by the owner")
+                        .arg(pubkey_arg("owner", "Owner of the counter"))
+                        .arg(op_arg())
+                        .arg(name_arg())
+                        .arg(
+                            Arg::with_name("nonce")
+                                .long("nonce")
+                                .takes_value(true)
+                                .required(true)
+                                .validator(is_number)
+                                .help("Relay nonce of the request"),
+                        )
+                        .arg(
+                            Arg::with_name("expiry")
+                                .long("expiry")
+                                .value_name("UNIX_TIME")
+                                .takes_value(true)
+                                .required(true)
+                                .validator(is_number)
+                                .help("Expiry of the request"),
+                        )
+                        .arg(
+                            Arg::with_name("signature")
+                                .long("signature")
+                                .takes_value(true)
+                                .required(true)
+                                .validator(is_signature)
+                                .help("Signature of the request message made by the owner"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("session")
+                .about("Session keys signing operations on counters of the user")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("create")
+                        .about("Allow a session key to apply operations until it expires")
+                        .arg(pubkey_arg("session-key", "Session key"))
+                        .arg(
+                            Arg::with_name("ops")
+                                .required(true)
+                                .multiple(true)
+                                .possible_values(&["inc", "dec"])
+                                .help("Operations allowed to the session key"),
+                        )
+                        .arg(
+                            Arg::with_name("valid-for")
+                                .long("valid-for")
+                                .value_name("SECONDS")
+                                .takes_value(true)
+                                .default_value("600")
+                                .validator(is_number)
+                                .help("Time the session stays valid"),
+                        )
+                        .arg(user_arg()),
+                )
+                .subcommand(
+                    SubCommand::with_name("revoke")
+                        .about("Revoke a session, the rent returns to the user")
+                        .arg(pubkey_arg("session-key", "Session key"))
+                        .arg(user_arg()),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("sign")
+                .about("Sign a --sign-only transaction with --admin-keypair, works offline")
+                .arg(Arg::with_name("transaction").required(true).help("Encoded transaction"))
+                .arg(
+                    Arg::with_name("signer")
+                        .long("signer")
+                        .takes_value(true)
+                        .help("Keypair of another signer, e.g. the nonce authority, instead of the admin"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("broadcast")
+                .about("Send a transaction signed offline")
+                .arg(Arg::with_name("transaction").required(true).help("Encoded transaction"))
+                .arg(
+                    Arg::with_name("signature")
+                        .long("signature")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .validator(is_signer_signature)
+                        .help("PUBKEY=SIGNATURE made by a signer, repeated for every missing signature"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("nonce")
+                .about("Nonce accounts of --sign-only transactions, --keypair pays the fees")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("create")
+                        .about("Create a nonce account")
+                        .arg(Arg::with_name("nonce-keypair").required(true).help("Keypair of the new account"))
+                        .arg(
+                            Arg::with_name("authority")
+                                .long("authority")
+                                .takes_value(true)
+                                .validator(is_pubkey)
+                                .help("Signer advancing the nonce, the user from --keypair by default"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("show")
+                        .about("Show a nonce")
+                        .arg(pubkey_arg("nonce", "Nonce account")),
+                )
+                .subcommand(
+                    SubCommand::with_name("authorize")
+                        .about("Change the authority, --keypair is the current one")
+                        .arg(pubkey_arg("nonce", "Nonce account"))
+                        .arg(pubkey_arg("new-authority", "New authority")),
+                )
+                .subcommand(
+                    SubCommand::with_name("withdraw")
+                        .about("Close a nonce account, --keypair is its authority")
+                        .arg(pubkey_arg("nonce", "Nonce account"))
+                        .arg(
+                            Arg::with_name("to")
+                                .long("to")
+                                .takes_value(true)
+                                .validator(is_pubkey)
+                                .help("Receiver of the lamports, the user from --keypair by default"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("watch")
+                .about("Print changes of counters and settings until interrupted, one line per update")
+                .arg(
+                    Arg::with_name("account")
+                        .long("account")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Counter or settings account, every account of the program by default"),
+                )
+                .arg(
+                    Arg::with_name("ws-url")
+                        .long("ws-url")
+                        .takes_value(true)
+                        .help("Websocket URL, the port next to the --url one by default"),
+                ),
+        )
+}
+
+fn read_keypair(path: &str) -> Result<Keypair, Box<dyn Error>> {
+    read_keypair_file(path).map_err(|err| format!("can't read keypair {}: {}", path, err).into())
+}
+
+fn pubkey_of(matches: &ArgMatches, name: &str) -> Pubkey {
+    // Validated by clap
+    Pubkey::from_str(matches.value_of(name).unwrap()).unwrap()
+}
+
+fn number_of(matches: &ArgMatches, name: &str) -> u64 {
+    matches.value_of(name).map_or(0, |value| value.parse().unwrap())
+}
+
+fn encoding_of(matches: &ArgMatches) -> Encoding {
+    match matches.value_of("encoding") {
+        Some("base58") => Encoding::Base58,
+        _ => Encoding::Base64,
+    }
+}
+
+fn send_config_of(matches: &ArgMatches) -> SendConfig {
+    SendConfig {
+        retries: number_of(matches, "retries") as u32,
+        retry_delay: Duration::from_millis(number_of(matches, "retry-delay")),
+        priority_fee: matches.value_of("priority-fee").map(|_| number_of(matches, "priority-fee")),
+        compute_unit_limit: matches
+            .value_of("compute-unit-limit")
+            .map(|_| number_of(matches, "compute-unit-limit") as u32),
+        simulate_only: matches.is_present("simulate"),
+    }
+}
+
+fn period_of(matches: &ArgMatches) -> ResetPeriod {
+    let length = number_of(matches, "length");
+    match matches.value_of("kind").unwrap() {
+        "slots" => ResetPeriod::Slots(length),
+        "time" => ResetPeriod::UnixTime(length),
+        "epochs" => ResetPeriod::Epochs(length),
+        _ => ResetPeriod::Never,
+    }
+}
+
+fn access_mode_of(matches: &ArgMatches) -> AccessMode {
+    match matches.value_of("mode").unwrap() {
+        "allowlist" => AccessMode::Allowlist,
+        "denylist" => AccessMode::Denylist,
+        _ => AccessMode::Off,
+    }
+}
+
+fn op_of(value: &str) -> OperationKind {
+    match value {
+        "dec" => OperationKind::Dec,
+        _ => OperationKind::Inc,
+    }
+}
+
+/// Unix time `valid-for` seconds from now
+fn expiry_of(matches: &ArgMatches) -> Result<i64, Box<dyn Error>> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64 + number_of(matches, "valid-for") as i64)
+}
+
+fn role_of(matches: &ArgMatches) -> Role {
+    match matches.value_of("role").unwrap() {
+        "super-admin" => Role::SuperAdmin,
+        "settings-manager" => Role::SettingsManager,
+        _ => Role::Moderator,
+    }
+}
+
+struct Config<'a> {
+    client: CounterClient,
+    matches: &'a ArgMatches<'a>,
+}
+
+impl<'a> Config<'a> {
+    /// Keypair from `--user` of the subcommand or from `--keypair`
+    fn user(&self, sub_matches: &ArgMatches) -> Result<Keypair, Box<dyn Error>> {
+        read_keypair(sub_matches.value_of("user").or_else(|| self.matches.value_of("keypair")).unwrap())
+    }
+
+    /// Keypair from `--admin-keypair`, reported as the `admin` account
+    fn admin(&self, report: &mut Report) -> Result<Keypair, Box<dyn Error>> {
+        let admin = read_keypair(self.matches.value_of("admin-keypair").unwrap())?;
+        report.account("admin", admin.pubkey());
+        Ok(admin)
+    }
+}
+
+fn run(matches: &ArgMatches, format: Format, report: &mut Report) -> Result<(), Box<dyn Error>> {
+    let commitment = CommitmentConfig::from_str(matches.value_of("commitment").unwrap())?;
+    let config = Config {
+        client: CounterClient::with_commitment(matches.value_of("url").unwrap(), commitment)
+            .with_send_config(send_config_of(matches)),
+        matches,
+    };
+    let client = &config.client;
+    let settings_pk = Settings::get_settings_pk_with_bump().0;
+    let encoding = encoding_of(matches);
+    if matches.is_present("sign-only") {
+        return sign_only(&config, encoding, report);
+    }
+
+    match matches.subcommand() {
+        ("settings", Some(settings_matches)) => {
+            report.account("settings", settings_pk);
+            match settings_matches.subcommand() {
+                ("show", _) => report.read(State::Settings(client.get_settings()?)),
+                ("set", Some(sub_matches)) => {
+                    let inc_step = number_of(sub_matches, "inc-step") as u32;
+                    let dec_step = number_of(sub_matches, "dec-step") as u32;
+                    let admin = config.admin(report)?;
+                    report.executed(client.init_settings(&admin, inc_step, dec_step)?, State::Settings);
+                }
+                ("period", Some(sub_matches)) => {
+                    let admin = config.admin(report)?;
+                    report.executed(client.set_reset_period(&admin, period_of(sub_matches))?, State::Settings);
+                }
+                ("access", Some(sub_matches)) => {
+                    let admin = config.admin(report)?;
+                    report.executed(client.set_access_mode(&admin, access_mode_of(sub_matches))?, State::Settings);
+                }
+                _ => unreachable!(),
+            }
+        }
+        (command @ "create", Some(sub_matches))
+        | (command @ "inc", Some(sub_matches))
+        | (command @ "dec", Some(sub_matches))
+        | (command @ "reset", Some(sub_matches))
+        | (command @ "label", Some(sub_matches)) => {
+            let user = config.user(sub_matches)?;
+            let name = sub_matches.value_of("name").unwrap();
+            let counter_pk = Counter::generate_counter_pk(&user.pubkey(), name)?;
+            report.account("counter", counter_pk);
+            report.account("owner", user.pubkey());
+            let executed = match command {
+                "create" => client.create_counter(&user, name)?,
+                "inc" => client.inc(&user, name)?,
+                "dec" => client.dec(&user, name)?,
+                "reset" => client.reset(&user, name)?,
+                _ => client.set_label(&user, name, sub_matches.value_of("label").unwrap())?,
+            };
+            report.executed(executed, |counter| State::Counter(counter_pk, counter));
+        }
+        ("show", Some(sub_matches)) => {
+            let counter_pk = pubkey_of(sub_matches, "pubkey");
+            report.account("counter", counter_pk);
+            report.read(State::Counter(counter_pk, client.get_counter_at(&counter_pk)?));
+        }
+        ("list", Some(sub_matches)) => {
+            let owner = match sub_matches.value_of("owner") {
+                Some(_) => pubkey_of(sub_matches, "owner"),
+                None => config.user(sub_matches)?.pubkey(),
+            };
+            report.account("owner", owner);
+            report.read(State::Counters(client.list_counters(&owner)?));
+        }
+        ("leaderboard", Some(sub_matches)) => {
+            let owner = sub_matches.value_of("owner").map(|_| pubkey_of(sub_matches, "owner"));
+            if let Some(owner) = owner {
+                report.account("owner", owner);
+            }
+            let leaderboard = Leaderboard::new(client.find_counters(owner.as_ref())?);
+            let limit = number_of(sub_matches, "limit") as usize;
+            let buckets = number_of(sub_matches, "buckets") as usize;
+            report.read(State::Leaderboard { leaderboard, limit, buckets });
+        }
+        ("history", Some(sub_matches)) => {
+            let address = pubkey_of(sub_matches, "account");
+            report.account("account", address);
+            let limit = number_of(sub_matches, "limit") as usize;
+            report.read(State::History(address, history::timeline(client, &address, limit)?));
+        }
+        (command @ "freeze", Some(sub_matches))
+        | (command @ "thaw", Some(sub_matches))
+        | (command @ "admin-reset", Some(sub_matches)) => {
+            let owner = pubkey_of(sub_matches, "owner");
+            let name = sub_matches.value_of("name").unwrap();
+            let counter_pk = Counter::generate_counter_pk(&owner, name)?;
+            report.account("counter", counter_pk);
+            report.account("owner", owner);
+            let admin = config.admin(report)?;
+            let executed = match command {
+                "freeze" => client.freeze(&admin, &owner, name)?,
+                "thaw" => client.thaw(&admin, &owner, name)?,
+                _ => client.admin_reset(&admin, &owner, name)?,
+            };
+            report.executed(executed, |counter| State::Counter(counter_pk, counter));
+        }
+        ("access", Some(access_matches)) => {
+            let (command, sub_matches) = access_matches.subcommand();
+            let user = pubkey_of(sub_matches.unwrap(), "user");
+            report.account("access_page", AccessPage::get_access_page_pk_with_bump(AccessPage::bucket(&user), 0).0);
+            report.account("user", user);
+            let admin = config.admin(report)?;
+            let executed = match command {
+                "add" => client.add_access(&admin, &user)?,
+                _ => client.remove_access(&admin, &user)?,
+            };
+            report.executed(executed, |access_page| {
+                let (access_page_pk, _) = AccessPage::get_access_page_pk_with_bump(access_page.bucket, access_page.index);
+                State::AccessPage(access_page_pk, Box::new(access_page))
+            });
+        }
+        ("role", Some(role_matches)) => {
+            let (command, sub_matches) = role_matches.subcommand();
+            let sub_matches = sub_matches.unwrap();
+            let role = role_of(sub_matches);
+            let user = pubkey_of(sub_matches, "user");
+            report.account("roles", Roles::get_roles_pk_with_bump().0);
+            report.account("user", user);
+            let admin = config.admin(report)?;
+            let executed = match command {
+                "grant" => client.grant_role(&admin, &user, role)?,
+                _ => client.revoke_role(&admin, &user, role)?,
+            };
+            report.executed(executed, |roles| State::Roles(user, roles.roles_of(&user)));
+        }
+        ("admin", Some(admin_matches)) => {
+            let (_, sub_matches) = admin_matches.subcommand();
+            let new_admin = pubkey_of(sub_matches.unwrap(), "new-admin");
+            report.account("settings", settings_pk);
+            report.account("new_admin", new_admin);
+            let admin = config.admin(report)?;
+            let settings = client.get_settings()?;
+            let executed = client.update_settings(&admin, &new_admin, settings.inc_step, settings.dec_step)?;
+            report.executed(executed, State::Settings);
+        }
+        ("relay", Some(relay_matches)) => match relay_matches.subcommand() {
+            ("request", Some(sub_matches)) => {
+                let user = match sub_matches.value_of("owner") {
+                    Some(_) => None,
+                    None => Some(config.user(sub_matches)?),
+                };
+                let owner = user.as_ref().map_or_else(|| pubkey_of(sub_matches, "owner"), Keypair::pubkey);
+                let name = sub_matches.value_of("name").unwrap();
+                report.account("counter", Counter::generate_counter_pk(&owner, name)?);
+                report.account("owner", owner);
+                let op = op_of(sub_matches.value_of("op").unwrap());
+                let request = client.relay_request(&owner, name, op, expiry_of(sub_matches)?)?;
+                let message = request.message()?;
+                let signature = match (&user, sub_matches.is_present("sign")) {
+                    (Some(user), true) => Some(user.sign_message(&message)),
+                    _ => None,
+                };
+                report.read(State::Relay { request, message, signature });
+            }
+            (_, sub_matches) => {
+                let sub_matches = sub_matches.unwrap();
+                let request = RelayRequest {
+                    user: pubkey_of(sub_matches, "owner"),
+                    name: sub_matches.value_of("name").unwrap().to_string(),
+                    op: op_of(sub_matches.value_of("op").unwrap()),
+                    nonce: number_of(sub_matches, "nonce"),
+                    expiry: number_of(sub_matches, "expiry") as i64,
+                };
+                // Validated by clap
+                let signature = Signature::from_str(sub_matches.value_of("signature").unwrap()).unwrap();
+                let counter_pk = Counter::generate_counter_pk(&request.user, &request.name)?;
+                report.account("counter", counter_pk);
+                report.account("owner", request.user);
+                let admin = config.admin(report)?;
+                let executed = client.relay(&admin, &request, &signature)?;
+                report.executed(executed, |counter| State::Counter(counter_pk, counter));
+            }
+        },
+        ("session", Some(session_matches)) => {
+            let (command, sub_matches) = session_matches.subcommand();
+            let sub_matches = sub_matches.unwrap();
+            let user = config.user(sub_matches)?;
+            let session_key = pubkey_of(sub_matches, "session-key");
+            report.account("owner", user.pubkey());
+            report.account("session_key", session_key);
+            report.account("session", Session::get_session_pk_with_bump(&user.pubkey(), &session_key).0);
+            let sent = match command {
+                "create" => {
+                    let ops: Vec<_> = sub_matches.values_of("ops").unwrap().map(op_of).collect();
+                    client.create_session(&user, &session_key, expiry_of(sub_matches)?, &ops)?
+                }
+                _ => client.revoke_session(&user, &session_key)?,
+            };
+            report.sent(sent);
+        }
+        ("sign", Some(sub_matches)) => {
+            let mut tx = encoding.decode(sub_matches.value_of("transaction").unwrap())?;
+            let signer = match sub_matches.value_of("signer") {
+                Some(path) => read_keypair(path)?,
+                None => config.admin(report)?,
+            };
+            offline::sign(&mut tx, &signer)?;
+            report.transaction(&tx, encoding);
+        }
+        ("broadcast", Some(sub_matches)) => {
+            let mut tx = encoding.decode(sub_matches.value_of("transaction").unwrap())?;
+            for value in sub_matches.values_of("signature").into_iter().flatten() {
+                // Validated by clap
+                let (pubkey, signature) = value.split_once('=').unwrap();
+                offline::add_signature(&mut tx, &Pubkey::from_str(pubkey)?, Signature::from_str(signature)?)?;
+            }
+            report.transaction(&tx, encoding);
+            report.sent(client.broadcast(&tx)?);
+        }
+        ("nonce", Some(nonce_matches)) => {
+            match nonce_matches.subcommand() {
+                ("create", Some(sub_matches)) => {
+                    let user = config.user(sub_matches)?;
+                    let nonce_keypair = read_keypair(sub_matches.value_of("nonce-keypair").unwrap())?;
+                    let authority = match sub_matches.value_of("authority") {
+                        Some(_) => pubkey_of(sub_matches, "authority"),
+                        None => user.pubkey(),
+                    };
+                    report.account("nonce", nonce_keypair.pubkey());
+                    report.account("authority", authority);
+                    report.sent(nonce::create(client, &user, &nonce_keypair, &authority)?);
+                    // A simulated account is not created
+                    if !client.send_config().simulate_only {
+                        let nonce_account = nonce::get(client, &nonce_keypair.pubkey())?;
+                        report.read(State::Nonce(nonce_keypair.pubkey(), nonce_account));
+                    }
+                }
+                ("show", Some(sub_matches)) => {
+                    let nonce_pk = pubkey_of(sub_matches, "nonce");
+                    report.account("nonce", nonce_pk);
+                    report.read(State::Nonce(nonce_pk, nonce::get(client, &nonce_pk)?));
+                }
+                ("authorize", Some(sub_matches)) => {
+                    let user = config.user(sub_matches)?;
+                    let nonce_pk = pubkey_of(sub_matches, "nonce");
+                    let new_authority = pubkey_of(sub_matches, "new-authority");
+                    report.account("nonce", nonce_pk);
+                    report.account("authority", user.pubkey());
+                    report.account("new_authority", new_authority);
+                    report.sent(nonce::authorize(client, &user, &nonce_pk, &new_authority)?);
+                    report.read(State::Nonce(nonce_pk, nonce::get(client, &nonce_pk)?));
+                }
+                (_, sub_matches) => {
+                    let sub_matches = sub_matches.unwrap();
+                    let user = config.user(sub_matches)?;
+                    let nonce_pk = pubkey_of(sub_matches, "nonce");
+                    let to = match sub_matches.value_of("to") {
+                        Some(_) => pubkey_of(sub_matches, "to"),
+                        None => user.pubkey(),
+                    };
+                    report.account("nonce", nonce_pk);
+                    report.account("authority", user.pubkey());
+                    report.account("to", to);
+                    report.sent(nonce::withdraw(client, &user, &nonce_pk, &to)?);
+                }
+            }
+        }
+        ("watch", Some(sub_matches)) => {
+            let target = match sub_matches.value_of("account") {
+                Some(_) => Watch::Account(pubkey_of(sub_matches, "account")),
+                None => Watch::Program,
+            };
+            if let Watch::Account(pubkey) = target {
+                report.account("account", pubkey);
+            }
+            let ws_url = match sub_matches.value_of("ws-url") {
+                Some(ws_url) => ws_url.to_string(),
+                None => websocket_url(matches.value_of("url").unwrap()),
+            };
+            let mut states = HashMap::new();
+            let mut update = |pubkey: Pubkey, slot: Option<u64>, state: AccountState| {
+                let state = match state {
+                    AccountState::Counter(counter) => State::Counter(pubkey, counter),
+                    AccountState::Settings(settings) => State::Settings(settings),
+                };
+                print_update(format, &pubkey, slot, states.get(&pubkey), &state);
+                states.insert(pubkey, state);
+            };
+            watch::watch(client, &ws_url, &target, commitment, |event| {
+                match event {
+                    WatchEvent::Snapshot(accounts) => {
+                        for (pubkey, state) in accounts {
+                            update(pubkey, None, state);
+                        }
+                    }
+                    WatchEvent::Update { pubkey, slot, state } => update(pubkey, Some(slot), state),
+                    WatchEvent::Disconnected { reason, retry_in } => {
+                        eprintln!("Disconnected: {}, reconnecting in {} s", reason, retry_in.as_secs())
+                    }
+                }
+                true
+            });
+        }
+        _ => unreachable!(),
+    }
 
     Ok(())
 }
 
-fn get_account(client: &RpcClient, pk: &Pubkey) -> Option<Account> {
-    let rpc_result = client.get_account_with_commitment(
-        pk,
-        client.commitment(),
-    );
-    match rpc_result {
-        Ok(resp) => resp.value,
-        Err(_) => None
-    }
-}
-
-struct App {
-    rpc: RpcClient,
-    user: Keypair,
-    admin: Keypair,
-    program: Keypair,
-    counter_pk: Pubkey,
-    settings_pk: Pubkey,
-}
-
-impl App {
-    fn new() -> Result<App, Box<dyn Error>> {
-        // Load keypairs
-        println!("Load keypairs");
-        let user = read_keypair_file("../keypair/user.json")?;
-        let admin = read_keypair_file("../keypair/admin.json")?;
-        let program = read_keypair_file("../keypair/program.json")?;
-        let counter_pk = Counter::generate_counter_pk(&user.pubkey())?;
-        let (settings_pk, _) = Settings::get_settings_pk_with_bump();
-        println!("user pk '{:?}'", user.pubkey());
-        println!("admin pk '{:?}'", admin.pubkey());
-        println!("program pk '{:?}'", program.pubkey());
-        println!("counter pk '{:?}'", counter_pk);
-        println!("settings pk '{:?}'", settings_pk);
-
-        // Init RPC client
-        let args: Vec<String> = env::args().collect();
-        let url = args.get(1).unwrap_or(&"http://localhost:8899".to_string()).to_string();
-        println!("Init RPC client URL '{}'", url);
-        let rpc_client = RpcClient::new_with_timeout_and_commitment(
-            url,
-            Duration::from_millis(60_000),
-            CommitmentConfig::finalized(),
-        );
-        println!("Init RPC client done");
-
-        Ok(App { rpc: rpc_client, user, admin, program, counter_pk, settings_pk })
-    }
-
-    fn update_settings(&self) -> Result<(), Box<dyn Error>> {
-        println!("Update counter settings");
-        let (recent_hash, _) = self.rpc.get_recent_blockhash()?;
-        let upd_sett_instr = CounterInstruction::upd_sett_instr(
-            self.admin.pubkey(),
-            self.admin.pubkey(),
-            2,
-            1,
-        );
-        let upd_sett_tx = Transaction::new_signed_with_payer(
-            &[upd_sett_instr],
-            Some(&self.admin.pubkey()),
-            &[&self.admin],
-            recent_hash,
-        );
-        self.rpc.send_and_confirm_transaction(&upd_sett_tx)?;
-        println!("Update counter settings done");
-        let settings_acc = self.rpc.get_account(&self.settings_pk)?;
-        let settings = Settings::try_from_slice(&settings_acc.data.borrow())?;
-        println!("settings '{:?}'", settings);
-
-        Ok(())
-    }
-
-    fn create_counter_account(&self) -> Result<(), Box<dyn Error>> {
-        let counter_acc = get_account(&self.rpc, &self.counter_pk);
-        if counter_acc == None {
-            println!("Create counter account");
-            let (recent_hash, _) = self.rpc.get_recent_blockhash()?;
-
-            let counter = Counter { value: 0 };
-            let space = counter.try_to_vec()?.len();
-            let rent_value = self.rpc.get_minimum_balance_for_rent_exemption(space)?;
-            let create_counter_acc_instr = system_instruction::create_account_with_seed(
-                &self.user.pubkey(),
-                &self.counter_pk,
-                &self.user.pubkey(),
-                COUNTER_SEED,
-                rent_value,
-                space as u64,
-                &self.program.pubkey(),
-            );
-            let create_counter_acc_tx = Transaction::new_signed_with_payer(
-                &[create_counter_acc_instr],
-                Some(&self.user.pubkey()),
-                &[&self.user],
-                recent_hash,
-            );
-            self.rpc.send_and_confirm_transaction(&create_counter_acc_tx)?;
-            println!("Create counter account done");
-        }
-        let counter_acc = self.rpc.get_account(&self.counter_pk)?;
-        let counter = Counter::try_from_slice(&counter_acc.data.borrow())?;
-        println!("counter '{:?}'", counter);
-
-        Ok(())
-    }
-
-    fn increment_counter(&self) -> Result<(), Box<dyn Error>> {
-        println!("Increment counter");
-        let (recent_hash, _) = self.rpc.get_recent_blockhash()?;
-        let inc_instr = CounterInstruction::inc_instr(self.user.pubkey());
-        let inc_tx = Transaction::new_signed_with_payer(
-            &[inc_instr],
-            Some(&self.user.pubkey()),
-            &[&self.user],
-            recent_hash,
-        );
-        self.rpc.send_and_confirm_transaction(&inc_tx)?;
-        println!("Increment counter done");
-
-        let counter_acc = self.rpc.get_account(&self.counter_pk)?;
-        let counter = Counter::try_from_slice(&counter_acc.data.borrow())?;
-        println!("counter '{:?}'", counter);
-
-        Ok(())
-    }
-
-    fn decrement_counter(&self) -> Result<(), Box<dyn Error>> {
-        println!("Decrement counter");
-        let (recent_hash, _) = self.rpc.get_recent_blockhash()?;
-        let dec_instr = CounterInstruction::dec_instr(self.user.pubkey());
-        let dec_tx = Transaction::new_signed_with_payer(
-            &[dec_instr],
-            Some(&self.user.pubkey()),
-            &[&self.user],
-            recent_hash,
-        );
-        self.rpc.send_and_confirm_transaction(&dec_tx)?;
-        println!("Decrement counter done");
-
-        let counter_acc = self.rpc.get_account(&self.counter_pk)?;
-        let counter = Counter::try_from_slice(&counter_acc.data.borrow())?;
-        println!("counter '{:?}'", counter);
-
-        Ok(())
-    }
-
-    fn reset_counter(&self) -> Result<(), Box<dyn Error>> {
-        println!("Reset counter");
-        let (recent_hash, _) = self.rpc.get_recent_blockhash()?;
-        let reset_instr = CounterInstruction::reset_instr(self.user.pubkey());
-        let reset_tx = Transaction::new_signed_with_payer(
-            &[reset_instr],
-            Some(&self.user.pubkey()),
-            &[&self.user],
-            recent_hash,
-        );
-        self.rpc.send_and_confirm_transaction(&reset_tx)?;
-        println!("Reset counter done");
-
-        let counter_acc = self.rpc.get_account(&self.counter_pk)?;
-        let counter = Counter::try_from_slice(&counter_acc.data.borrow())?;
-        println!("counter '{:?}'", counter);
-
-        Ok(())
-    }
-}
\ No newline at end of file
+/// Commands supported by `--sign-only`
+const ADMIN_COMMANDS: &[&str] = &["settings", "freeze", "thaw", "admin-reset", "access", "role", "admin"];
+
+/// Transaction of an admin command for offline signing, signed now when the admin keypair is at hand
+fn sign_only(config: &Config, encoding: Encoding, report: &mut Report) -> Result<(), Box<dyn Error>> {
+    let matches = config.matches;
+    if !ADMIN_COMMANDS.contains(&matches.subcommand_name().unwrap()) {
+        return Err(format!("{} can't be signed offline, only admin commands can", command_name(matches)).into());
+    }
+    let admin = match matches.value_of("admin-pubkey") {
+        Some(_) => None,
+        None => Some(config.admin(report)?),
+    };
+    let admin_pk = match &admin {
+        Some(admin) => admin.pubkey(),
+        None => {
+            let admin_pk = pubkey_of(matches, "admin-pubkey");
+            report.account("admin", admin_pk);
+            admin_pk
+        }
+    };
+    let instrs = config.client.send_config().with_compute_budget(&[admin_instruction(config, admin_pk)?]);
+    let mut tx = match matches.value_of("nonce") {
+        Some(_) => {
+            let nonce_pk = pubkey_of(matches, "nonce");
+            let nonce_account = nonce::get(&config.client, &nonce_pk)?;
+            report.account("nonce", nonce_pk);
+            report.account("nonce_authority", nonce_account.authority);
+            nonce::unsigned_transaction(&instrs, &admin_pk, &nonce_pk, &nonce_account)
+        }
+        None => {
+            let blockhash = match matches.value_of("blockhash") {
+                Some(blockhash) => Hash::from_str(blockhash)?,
+                None => config.client.recent_blockhash()?,
+            };
+            offline::unsigned_transaction(&instrs, &admin_pk, blockhash)
+        }
+    };
+    if let Some(admin) = &admin {
+        offline::sign(&mut tx, admin)?;
+    }
+    report.transaction(&tx, encoding);
+    Ok(())
+}
+
+/// Instruction of the admin command in `config`, `admin transfer` reads the current steps
+/// and `access` the pages of the user bucket from the cluster
+fn admin_instruction(config: &Config, admin: Pubkey) -> Result<Instruction, Box<dyn Error>> {
+    let instr = match config.matches.subcommand() {
+        ("settings", Some(settings_matches)) => match settings_matches.subcommand() {
+            ("set", Some(sub_matches)) => {
+                let inc_step = number_of(sub_matches, "inc-step") as u32;
+                let dec_step = number_of(sub_matches, "dec-step") as u32;
+                CounterInstruction::upd_sett_instr(admin, admin, inc_step, dec_step)
+            }
+            ("period", Some(sub_matches)) => {
+                CounterInstruction::set_reset_period_instr(admin, period_of(sub_matches))
+            }
+            ("access", Some(sub_matches)) => {
+                CounterInstruction::set_access_mode_instr(admin, access_mode_of(sub_matches))
+            }
+            _ => return Err("settings show can't be signed offline".into()),
+        },
+        (command @ "freeze", Some(sub_matches))
+        | (command @ "thaw", Some(sub_matches))
+        | (command @ "admin-reset", Some(sub_matches)) => {
+            let owner = pubkey_of(sub_matches, "owner");
+            let name = sub_matches.value_of("name").unwrap();
+            match command {
+                "freeze" => CounterInstruction::freeze_instr(admin, owner, name)?,
+                "thaw" => CounterInstruction::thaw_instr(admin, owner, name)?,
+                _ => CounterInstruction::admin_reset_instr(admin, owner, name)?,
+            }
+        }
+        ("access", Some(access_matches)) => {
+            let (command, sub_matches) = access_matches.subcommand();
+            let user = pubkey_of(sub_matches.unwrap(), "user");
+            let pages = config.client.access_pages(&user)?;
+            match command {
+                "add" => CounterInstruction::add_access_instr(admin, user, pages),
+                _ => CounterInstruction::remove_access_instr(admin, user, pages),
+            }
+        }
+        ("role", Some(role_matches)) => match role_matches.subcommand() {
+            ("grant", Some(sub_matches)) => {
+                CounterInstruction::grant_role_instr(admin, pubkey_of(sub_matches, "user"), role_of(sub_matches))
+            }
+            (_, sub_matches) => {
+                let sub_matches = sub_matches.unwrap();
+                CounterInstruction::revoke_role_instr(admin, pubkey_of(sub_matches, "user"), role_of(sub_matches))
+            }
+        },
+        ("admin", Some(admin_matches)) => {
+            let new_admin = pubkey_of(admin_matches.subcommand().1.unwrap(), "new-admin");
+            let settings = config.client.get_settings()?;
+            CounterInstruction::upd_sett_instr(admin, new_admin, settings.inc_step, settings.dec_step)
+        }
+        _ => unreachable!(),
+    };
+    Ok(instr)
+}
@@ -0,0 +1,153 @@
+use std::convert::TryInto;
+use solana_program::hash::Hash;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_program::system_program;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::nonce::State;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use crate::client::CounterClient;
+use crate::error::{CounterClientError, Result};
+use crate::offline;
+use crate::send::Sent;
+
+/// Offset of the authority in nonce account data, after the version and state tags
+const AUTHORITY_OFFSET: usize = 8;
+/// Offset of the stored blockhash, the fee calculator follows it
+const BLOCKHASH_OFFSET: usize = AUTHORITY_OFFSET + 32;
+/// State tag of an initialized nonce account
+const INITIALIZED: u32 = 1;
+
+/// Durable nonce replacing the recent blockhash of transactions signed long before sending
+#[derive(Debug, PartialEq)]
+pub struct NonceAccount {
+    /// Signer of `advance_nonce_account`
+    pub authority: Pubkey,
+    /// Blockhash of the next transaction using the nonce
+    pub blockhash: Hash,
+    pub lamports: u64,
+}
+
+impl NonceAccount {
+    /// `None` for accounts other than initialized nonce accounts. Data is parsed by offsets,
+    /// the layout is the same in every cluster version while the sdk types of it changed
+    pub fn decode(data: &[u8], lamports: u64) -> Option<Self> {
+        if data.len() != State::size() || data[4..8] != INITIALIZED.to_le_bytes() {
+            return None;
+        }
+        Some(NonceAccount {
+            authority: Pubkey::new_from_array(data[AUTHORITY_OFFSET..BLOCKHASH_OFFSET].try_into().ok()?),
+            blockhash: Hash::new_from_array(data[BLOCKHASH_OFFSET..BLOCKHASH_OFFSET + 32].try_into().ok()?),
+            lamports,
+        })
+    }
+}
+
+pub fn get(client: &CounterClient, nonce: &Pubkey) -> Result<NonceAccount> {
+    let rpc = client.rpc();
+    let account = rpc
+        .get_account_with_commitment(nonce, rpc.commitment())?
+        .value
+        .ok_or(CounterClientError::AccountNotFound(*nonce))?;
+    if account.owner != system_program::id() {
+        return Err(CounterClientError::InvalidNonceAccount(*nonce));
+    }
+    NonceAccount::decode(&account.data, account.lamports).ok_or(CounterClientError::InvalidNonceAccount(*nonce))
+}
+
+/// Create the rent exempt `nonce` account advanced by `authority`, `payer` pays for it
+pub fn create(client: &CounterClient, payer: &Keypair, nonce: &Keypair, authority: &Pubkey) -> Result<Sent> {
+    let lamports = client.rpc().get_minimum_balance_for_rent_exemption(State::size())?;
+    let instrs = system_instruction::create_nonce_account(&payer.pubkey(), &nonce.pubkey(), authority, lamports);
+    client.send(&instrs, payer, &[nonce])
+}
+
+/// Pass the nonce to `new_authority`, the current `authority` pays the fee
+pub fn authorize(
+    client: &CounterClient,
+    authority: &Keypair,
+    nonce: &Pubkey,
+    new_authority: &Pubkey,
+) -> Result<Sent> {
+    let instr = system_instruction::authorize_nonce_account(nonce, &authority.pubkey(), new_authority);
+    client.send(&[instr], authority, &[])
+}
+
+/// Close the nonce account moving its lamports to `to`, the `authority` pays the fee
+pub fn withdraw(client: &CounterClient, authority: &Keypair, nonce: &Pubkey, to: &Pubkey) -> Result<Sent> {
+    let lamports = get(client, nonce)?.lamports;
+    let instr = system_instruction::withdraw_nonce_account(nonce, &authority.pubkey(), to, lamports);
+    client.send(&[instr], authority, &[])
+}
+
+/// Transaction of `instructions` valid until the nonce is advanced. `advance_nonce_account` signed by
+/// the nonce authority goes first as the runtime requires
+pub fn unsigned_transaction(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    nonce: &Pubkey,
+    nonce_account: &NonceAccount,
+) -> Transaction {
+    let mut instrs = vec![system_instruction::advance_nonce_account(nonce, &nonce_account.authority)];
+    instrs.extend_from_slice(instructions);
+    offline::unsigned_transaction(&instrs, payer, nonce_account.blockhash)
+}
+
+#[cfg(test)]
+mod nonce_test {
+    use counter::instruction::CounterInstruction;
+    use counter::state::AccessMode;
+    use solana_program::hash::Hash;
+    use solana_program::pubkey::Pubkey;
+    use solana_program::system_program;
+    use solana_sdk::nonce::State;
+    use crate::nonce::{unsigned_transaction, NonceAccount};
+    use crate::offline;
+
+    /// Nonce account data, tags of the current version and the initialized state
+    fn nonce_data(authority: &Pubkey, blockhash: &Hash) -> Vec<u8> {
+        let mut data = vec![0; State::size()];
+        data[0..4].copy_from_slice(&1u32.to_le_bytes());
+        data[4..8].copy_from_slice(&1u32.to_le_bytes());
+        data[8..40].copy_from_slice(authority.as_ref());
+        data[40..72].copy_from_slice(blockhash.as_ref());
+        data
+    }
+
+    #[test]
+    fn when_initialized_nonce_expect_decoded() {
+        let (authority, blockhash) = (Pubkey::new_unique(), Hash::new_unique());
+
+        assert_eq!(
+            NonceAccount::decode(&nonce_data(&authority, &blockhash), 10),
+            Some(NonceAccount { authority, blockhash, lamports: 10 })
+        );
+    }
+
+    #[test]
+    fn when_uninitialized_or_other_data_expect_none() {
+        let mut data = nonce_data(&Pubkey::new_unique(), &Hash::new_unique());
+        data[4..8].copy_from_slice(&0u32.to_le_bytes());
+
+        assert_eq!(NonceAccount::decode(&data, 10), None);
+        assert_eq!(NonceAccount::decode(&[0; 3], 10), None);
+    }
+
+    #[test]
+    fn when_nonce_transaction_expect_advance_first_and_nonce_blockhash() {
+        let (payer, nonce) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let nonce_account = NonceAccount { authority: Pubkey::new_unique(), blockhash: Hash::new_unique(), lamports: 0 };
+        let instr = CounterInstruction::set_access_mode_instr(payer, AccessMode::Allowlist);
+
+        let tx = unsigned_transaction(&[instr], &payer, &nonce, &nonce_account);
+
+        assert_eq!(tx.message.recent_blockhash, nonce_account.blockhash);
+        let first = &tx.message.instructions[0];
+        assert_eq!(tx.message.account_keys[first.program_id_index as usize], system_program::id());
+        assert_eq!(tx.message.account_keys[first.accounts[0] as usize], nonce);
+        let signers: Vec<_> = offline::signers(&tx).into_iter().map(|(signer, _)| signer).collect();
+        assert_eq!(signers, vec![payer, nonce_account.authority]);
+    }
+}
@@ -0,0 +1,159 @@
+use solana_program::hash::Hash;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use crate::error::{CounterClientError, Result};
+
+/// Text encoding of serialized transactions passed between the machines
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Base58,
+    Base64,
+}
+
+impl Encoding {
+    pub fn encode(self, tx: &Transaction) -> String {
+        // Serialization of a transaction can't fail
+        let data = bincode::serialize(tx).unwrap();
+        match self {
+            Encoding::Base58 => bs58::encode(data).into_string(),
+            Encoding::Base64 => base64::encode(data),
+        }
+    }
+
+    pub fn decode(self, text: &str) -> Result<Transaction> {
+        let data = match self {
+            Encoding::Base58 => bs58::decode(text.trim()).into_vec().map_err(|err| err.to_string()),
+            Encoding::Base64 => base64::decode(text.trim()).map_err(|err| err.to_string()),
+        }
+        .map_err(CounterClientError::InvalidTransaction)?;
+        bincode::deserialize(&data).map_err(|err| CounterClientError::InvalidTransaction(err.to_string()))
+    }
+}
+
+/// Transaction of `instructions` paid by `payer` without signatures. It is valid while `blockhash` is recent
+pub fn unsigned_transaction(instructions: &[Instruction], payer: &Pubkey, blockhash: Hash) -> Transaction {
+    let mut tx = Transaction::new_with_payer(instructions, Some(payer));
+    tx.message.recent_blockhash = blockhash;
+    tx
+}
+
+/// Required signers in the transaction order with their signatures, `None` for missing ones
+pub fn signers(tx: &Transaction) -> Vec<(Pubkey, Option<Signature>)> {
+    let signers = &tx.message.account_keys[..tx.message.header.num_required_signatures as usize];
+    signers
+        .iter()
+        .zip(&tx.signatures)
+        .map(|(pubkey, signature)| (*pubkey, Some(*signature).filter(|signature| *signature != Signature::default())))
+        .collect()
+}
+
+/// Sign by `keypair`, one of the required signers. The blockhash is kept
+pub fn sign(tx: &mut Transaction, keypair: &Keypair) -> Result<Signature> {
+    let blockhash = tx.message.recent_blockhash;
+    tx.try_partial_sign(&[keypair], blockhash)?;
+    Ok(keypair.sign_message(&tx.message_data()))
+}
+
+/// Add the signature of `pubkey` made elsewhere, it must sign the message of `tx`
+pub fn add_signature(tx: &mut Transaction, pubkey: &Pubkey, signature: Signature) -> Result<()> {
+    let index = signers(tx)
+        .iter()
+        .position(|(signer, _)| signer == pubkey)
+        .ok_or(CounterClientError::UnexpectedSigner(*pubkey))?;
+    if !signature.verify(pubkey.as_ref(), &tx.message_data()) {
+        return Err(CounterClientError::InvalidSignature(*pubkey));
+    }
+    tx.signatures[index] = signature;
+    Ok(())
+}
+
+/// Transaction with every required signature
+pub fn check_signed(tx: &Transaction) -> Result<()> {
+    match signers(tx).into_iter().find(|(_, signature)| signature.is_none()) {
+        Some((pubkey, _)) => Err(CounterClientError::MissingSignature(pubkey)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod offline_test {
+    use counter::instruction::CounterInstruction;
+    use solana_program::hash::Hash;
+    use solana_program::pubkey::Pubkey;
+    use solana_sdk::signature::Keypair;
+    use solana_sdk::signer::Signer;
+    use solana_sdk::transaction::Transaction;
+    use crate::error::CounterClientError;
+    use crate::offline::{add_signature, check_signed, sign, signers, unsigned_transaction, Encoding};
+
+    fn upd_sett_tx(admin: &Pubkey) -> Transaction {
+        let instr = CounterInstruction::upd_sett_instr(*admin, Pubkey::new_unique(), 2, 1);
+        unsigned_transaction(&[instr], admin, Hash::new_unique())
+    }
+
+    #[test]
+    fn when_encoded_expect_same_transaction_decoded() {
+        let admin = Keypair::new();
+        let mut tx = upd_sett_tx(&admin.pubkey());
+        sign(&mut tx, &admin).unwrap();
+
+        for encoding in [Encoding::Base58, Encoding::Base64].iter() {
+            assert_eq!(encoding.decode(&encoding.encode(&tx)).unwrap(), tx);
+        }
+    }
+
+    #[test]
+    fn when_garbage_expect_invalid_transaction() {
+        assert!(matches!(Encoding::Base64.decode("AAAA"), Err(CounterClientError::InvalidTransaction(_))));
+        assert!(matches!(Encoding::Base58.decode("0OIl"), Err(CounterClientError::InvalidTransaction(_))));
+    }
+
+    #[test]
+    fn when_signed_offline_expect_signature_accepted() {
+        let admin = Keypair::new();
+        let tx = upd_sett_tx(&admin.pubkey());
+        let mut offline_tx = tx.clone();
+        let signature = sign(&mut offline_tx, &admin).unwrap();
+        let mut online_tx = tx;
+
+        assert!(matches!(
+            check_signed(&online_tx),
+            Err(CounterClientError::MissingSignature(pk)) if pk == admin.pubkey()
+        ));
+        add_signature(&mut online_tx, &admin.pubkey(), signature).unwrap();
+
+        assert_eq!(signers(&online_tx), vec![(admin.pubkey(), Some(signature))]);
+        assert!(check_signed(&online_tx).is_ok());
+        assert!(online_tx.verify().is_ok());
+    }
+
+    #[test]
+    fn when_not_a_signer_expect_error() {
+        let admin = Keypair::new();
+        let other = Keypair::new();
+        let mut tx = upd_sett_tx(&admin.pubkey());
+
+        assert!(sign(&mut tx, &other).is_err());
+        let signature = other.sign_message(&tx.message_data());
+        assert!(matches!(
+            add_signature(&mut tx, &other.pubkey(), signature),
+            Err(CounterClientError::UnexpectedSigner(pk)) if pk == other.pubkey()
+        ));
+    }
+
+    #[test]
+    fn when_signature_of_other_message_expect_invalid_signature() {
+        let admin = Keypair::new();
+        let mut tx = upd_sett_tx(&admin.pubkey());
+        let signature = admin.sign_message(b"other message");
+
+        assert!(matches!(
+            add_signature(&mut tx, &admin.pubkey(), signature),
+            Err(CounterClientError::InvalidSignature(_))
+        ));
+        assert_eq!(signers(&tx), vec![(admin.pubkey(), None)]);
+    }
+}
@@ -0,0 +1,608 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use counter::error::CounterError;
+use counter::instruction::OperationKind;
+use counter::state::{AccessPage, Counter, ResetPeriod, Role, Settings};
+use counter_rpc_client::client::{Executed, RelayRequest};
+use counter_rpc_client::history::{Call, Entry};
+use counter_rpc_client::leaderboard::Leaderboard;
+use counter_rpc_client::nonce::NonceAccount;
+use counter_rpc_client::offline::{self, Encoding};
+use counter_rpc_client::send::{compute_budget, Sent};
+use counter_rpc_client::simulate::{Event, Log, Simulation};
+use counter_rpc_client::CounterClientError;
+use serde_json::{json, Map, Value};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Transaction;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    /// One JSON document per command, field names are stable
+    Json,
+}
+
+/// Decoded account state
+pub enum State {
+    Counter(Pubkey, Counter),
+    Counters(Vec<(Pubkey, Counter)>),
+    Settings(Settings),
+    AccessPage(Pubkey, Box<AccessPage>),
+    /// Roles of a single user
+    Roles(Pubkey, Vec<Role>),
+    /// `limit` highest and lowest counters, histogram of at most `buckets` ranges
+    Leaderboard { leaderboard: Leaderboard, limit: usize, buckets: usize },
+    /// Transactions of an account, oldest first
+    History(Pubkey, Vec<Entry>),
+    Nonce(Pubkey, NonceAccount),
+    /// Operation for a relayer, `message` is signed by the owner. `signature` is present when it was signed here
+    Relay { request: RelayRequest, message: Vec<u8>, signature: Option<Signature> },
+}
+
+/// Result of a command, filled while the command runs so a failed command reports its accounts too
+pub struct Report {
+    command: String,
+    signature: Option<Signature>,
+    slot: Option<u64>,
+    /// Number of times the transaction was sent
+    attempts: Option<u32>,
+    /// Simulation before sending, the only result with `--simulate`
+    simulation: Option<Simulation>,
+    accounts: Vec<(&'static str, Pubkey)>,
+    before: Option<State>,
+    after: Option<State>,
+    /// Encoded transaction signed offline
+    transaction: Option<String>,
+    /// Signers of `transaction`, `None` for missing signatures
+    signers: Vec<(Pubkey, Option<Signature>)>,
+}
+
+impl Report {
+    pub fn new(command: String) -> Self {
+        Report {
+            command,
+            signature: None,
+            slot: None,
+            attempts: None,
+            simulation: None,
+            accounts: vec![],
+            before: None,
+            after: None,
+            transaction: None,
+            signers: vec![],
+        }
+    }
+
+    pub fn account(&mut self, name: &'static str, pubkey: Pubkey) {
+        self.accounts.push((name, pubkey));
+    }
+
+    pub fn executed<T, F>(&mut self, executed: Executed<T>, state: F)
+    where
+        F: Fn(T) -> State,
+    {
+        self.sent(executed.sent);
+        self.before = executed.before.map(&state);
+        self.after = Some(state(executed.after));
+    }
+
+    /// Transaction sent, or only simulated, without reading the changed account
+    pub fn sent(&mut self, sent: Sent) {
+        if sent.attempts > 0 {
+            self.signature = Some(sent.signature);
+            self.slot = sent.slot;
+            self.attempts = Some(sent.attempts);
+        }
+        self.simulation = Some(sent.simulation);
+    }
+
+    pub fn transaction(&mut self, tx: &Transaction, encoding: Encoding) {
+        self.transaction = Some(encoding.encode(tx));
+        self.signers = offline::signers(tx);
+    }
+
+    /// State read without a transaction
+    pub fn read(&mut self, state: State) {
+        self.after = Some(state);
+    }
+
+    pub fn print(&self, format: Format, err: Option<&(dyn Error + 'static)>) {
+        match format {
+            Format::Text => self.print_text(err),
+            Format::Json => println!("{}", serde_json::to_string_pretty(&self.to_json(err)).unwrap()),
+        }
+    }
+
+    fn print_text(&self, err: Option<&(dyn Error + 'static)>) {
+        if let Some(err) = err {
+            eprintln!("Error: {}", err);
+            if let Some(CounterClientError::SimulationFailed(simulation)) = err.downcast_ref::<CounterClientError>() {
+                for log in &simulation.logs {
+                    eprintln!("{}", log_text(log));
+                }
+            }
+            return;
+        }
+        if let Some(tx) = &self.transaction {
+            println!("transaction '{}'", tx);
+            for (signer, signature) in &self.signers {
+                match signature {
+                    Some(signature) => println!("signature '{}={}'", signer, signature),
+                    None => println!("missing signature '{}'", signer),
+                }
+            }
+        }
+        let units = self.simulation.as_ref().map(Simulation::units_consumed);
+        match (&self.signature, &self.simulation) {
+            (Some(signature), _) => {
+                let slot = self.slot.map_or("unknown".to_string(), |slot| slot.to_string());
+                let attempts = self.attempts.unwrap_or(1);
+                let units = units.map_or("unknown".to_string(), |units| units.to_string());
+                println!("signature '{}' slot '{}' attempts '{}' units '{}'", signature, slot, attempts, units);
+            }
+            (None, Some(simulation)) => {
+                println!("simulation units '{}'", simulation.units_consumed());
+                for log in &simulation.logs {
+                    println!("{}", log_text(log));
+                }
+            }
+            (None, None) => {}
+        }
+        match &self.after {
+            Some(State::Counter(counter_pk, counter)) => println!("{}", counter_text(counter_pk, counter)),
+            Some(State::Counters(counters)) => {
+                for (counter_pk, counter) in counters {
+                    println!("{}", counter_text(counter_pk, counter));
+                }
+            }
+            Some(State::Settings(settings)) => println!("{}", settings_text(settings)),
+            Some(State::AccessPage(access_page_pk, access_page)) => println!(
+                "access page '{}' bucket '{}' index '{}' pages '{}' keys '{:?}'",
+                access_page_pk,
+                access_page.bucket,
+                access_page.index,
+                access_page.pages,
+                access_page.keys(),
+            ),
+            Some(State::Roles(user, roles)) => println!("user '{}' roles '{:?}'", user, roles),
+            Some(State::Leaderboard { leaderboard, limit, buckets }) => {
+                print_leaderboard(leaderboard, *limit, *buckets)
+            }
+            Some(State::History(address, entries)) => print_history(address, entries),
+            Some(State::Nonce(nonce_pk, nonce_account)) => println!(
+                "nonce '{}' authority '{}' blockhash '{}' lamports '{}'",
+                nonce_pk, nonce_account.authority, nonce_account.blockhash, nonce_account.lamports,
+            ),
+            Some(State::Relay { request, message, signature }) => {
+                println!(
+                    "relay owner '{}' name '{}' op '{}' nonce '{}' expiry '{}' message '{}'",
+                    request.user,
+                    request.name,
+                    op_name(request.op),
+                    request.nonce,
+                    request.expiry,
+                    bs58::encode(message).into_string(),
+                );
+                if let Some(signature) = signature {
+                    println!("signature '{}'", signature);
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn to_json(&self, err: Option<&(dyn Error + 'static)>) -> Value {
+        let accounts: Map<String, Value> = self
+            .accounts
+            .iter()
+            .map(|(name, pubkey)| (name.to_string(), json!(pubkey.to_string())))
+            .collect();
+        json!({
+            "command": self.command,
+            "success": err.is_none(),
+            "signature": self.signature.map(|signature| signature.to_string()),
+            "slot": self.slot,
+            "attempts": self.attempts,
+            "simulation": self.simulation.as_ref().or_else(|| failed_simulation(err)).map(simulation_json),
+            "accounts": accounts,
+            "before": self.before.as_ref().map(state_json),
+            "after": self.after.as_ref().map(state_json),
+            "transaction": self.transaction.as_ref().map(|tx| json!({
+                "data": tx,
+                "signers": self
+                    .signers
+                    .iter()
+                    .map(|(signer, signature)| json!({
+                        "pubkey": signer.to_string(),
+                        "signature": signature.map(|signature| signature.to_string()),
+                    }))
+                    .collect::<Vec<_>>(),
+            })),
+            "error": err.map(error_json),
+        })
+    }
+}
+
+fn counter_text(counter_pk: &Pubkey, counter: &Counter) -> String {
+    format!(
+        "counter '{}' value '{}' name '{}' label '{}' owner '{}' created at '{}' updated at '{}' operations '{}' period '{}' relay nonce '{}' frozen '{}'",
+        counter_pk,
+        counter.value,
+        counter.name(),
+        counter.label(),
+        counter.owner,
+        counter.created_at,
+        counter.updated_at,
+        counter.op_count,
+        counter.period_id,
+        counter.relay_nonce,
+        counter.frozen,
+    )
+}
+
+fn settings_text(settings: &Settings) -> String {
+    format!(
+        "settings admin '{}' inc step '{}' dec step '{}' reset period '{:?}' access mode '{:?}'",
+        settings.admin,
+        settings.inc_step,
+        settings.dec_step,
+        settings.reset_period(),
+        settings.access_mode(),
+    )
+}
+
+fn print_leaderboard(leaderboard: &Leaderboard, limit: usize, buckets: usize) {
+    let mean = leaderboard.mean().map_or("none".to_string(), |mean| format!("{:.2}", mean));
+    println!("counters '{}' total '{}' mean '{}'", leaderboard.counters.len(), leaderboard.total, mean);
+    println!("top");
+    for (rank, (counter_pk, counter)) in leaderboard.top(limit).iter().enumerate() {
+        println!("{:>4} {}", rank + 1, counter_text(counter_pk, counter));
+    }
+    println!("bottom");
+    for (rank, (counter_pk, counter)) in leaderboard.bottom(limit).enumerate() {
+        println!("{:>4} {}", rank + 1, counter_text(counter_pk, counter));
+    }
+    println!("histogram");
+    let histogram = leaderboard.histogram(buckets);
+    let largest = histogram.iter().map(|bucket| bucket.count).max().unwrap_or(0);
+    for bucket in histogram {
+        // Bars are scaled to 40 characters, a nonempty range gets at least one
+        let bar = "#".repeat(if bucket.count == 0 { 0 } else { (bucket.count * 40 / largest).max(1) });
+        println!("{:>20} .. {:<20} {:>6} {}", bucket.from, bucket.to, bucket.count, bar);
+    }
+}
+
+fn print_history(address: &Pubkey, entries: &[Entry]) {
+    println!("history of '{}' transactions '{}'", address, entries.len());
+    for entry in entries {
+        let time = entry.block_time.map_or("unknown".to_string(), |time| time.to_string());
+        let payer = entry.payer.map_or("unknown".to_string(), |payer| payer.to_string());
+        let result = entry.error.as_ref().map_or("ok".to_string(), |err| format!("failed: {}", err));
+        println!(
+            "slot '{}' time '{}' signature '{}' payer '{}' result '{}'",
+            entry.slot, time, entry.signature, payer, result
+        );
+        for call in &entry.calls {
+            let accounts: Vec<_> = call.accounts.iter().map(Pubkey::to_string).collect();
+            let error = match &call.error {
+                Some(err) => format!(" error '{}'", instruction_error_text(err)),
+                None => String::new(),
+            };
+            println!("    {} accounts '{}'{}", instruction_text(call), accounts.join(", "), error);
+        }
+    }
+}
+
+fn instruction_text(call: &Call) -> String {
+    call.instruction.as_ref().map_or("unknown instruction".to_string(), |instruction| format!("{:?}", instruction))
+}
+
+/// Program errors are named by `CounterError`
+fn instruction_error_text(err: &InstructionError) -> String {
+    match CounterError::from_instruction_error(err) {
+        Some(counter_err) => format!("{:?}: {}", counter_err, counter_err),
+        None => err.to_string(),
+    }
+}
+
+/// Fields of `after` that differ from `before`, every field of a new account
+fn changes(before: Option<&Value>, after: &Value) -> Map<String, Value> {
+    let empty = Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    after
+        .as_object()
+        .unwrap_or(&empty)
+        .iter()
+        .filter(|(field, value)| before.get(*field) != Some(value))
+        .map(|(field, value)| (field.clone(), json!({ "before": before.get(field), "after": value })))
+        .collect()
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        Value::Null => "none".to_string(),
+        _ => value.to_string(),
+    }
+}
+
+/// Update of a watched account, a line with the changed fields or the whole state of a new account.
+/// Updates without changes in the decoded state are skipped
+pub fn print_update(format: Format, pubkey: &Pubkey, slot: Option<u64>, before: Option<&State>, after: &State) {
+    let after_json = state_json(after);
+    let changes = changes(before.map(state_json).as_ref(), &after_json);
+    if changes.is_empty() {
+        return;
+    }
+    let kind = match after {
+        State::Settings(_) => "settings",
+        _ => "counter",
+    };
+    match format {
+        Format::Text => {
+            let slot = slot.map_or("unknown".to_string(), |slot| slot.to_string());
+            match (before, after) {
+                (Some(_), _) => {
+                    let fields: Vec<_> = changes
+                        .iter()
+                        .map(|(field, change)| {
+                            format!("{} '{}' -> '{}'", field, value_text(&change["before"]), value_text(&change["after"]))
+                        })
+                        .collect();
+                    println!("{} '{}' slot '{}' {}", kind, pubkey, slot, fields.join(" "));
+                }
+                (None, State::Counter(counter_pk, counter)) => {
+                    println!("slot '{}' {}", slot, counter_text(counter_pk, counter))
+                }
+                (None, State::Settings(settings)) => println!("slot '{}' {}", slot, settings_text(settings)),
+                (None, _) => println!("{} '{}' slot '{}' {}", kind, pubkey, slot, after_json),
+            }
+        }
+        Format::Json => println!(
+            "{}",
+            json!({
+                "pubkey": pubkey.to_string(),
+                "slot": slot,
+                "kind": kind,
+                "changes": changes,
+                "state": after_json,
+            })
+        ),
+    }
+}
+
+/// Simulation that aborted the command
+fn failed_simulation<'a>(err: Option<&'a (dyn Error + 'static)>) -> Option<&'a Simulation> {
+    match err?.downcast_ref::<CounterClientError>()? {
+        CounterClientError::SimulationFailed(simulation) => Some(simulation),
+        _ => None,
+    }
+}
+
+fn program_name(program: &Pubkey) -> String {
+    if *program == counter::id() {
+        "counter".to_string()
+    } else if *program == compute_budget::id() {
+        "compute-budget".to_string()
+    } else if *program == system_program::id() {
+        "system".to_string()
+    } else {
+        program.to_string()
+    }
+}
+
+/// Log line indented by the invocation depth
+fn log_text(log: &Log) -> String {
+    let indent = "  ".repeat(log.depth);
+    match &log.event {
+        Event::Invoke => format!("{}invoke '{}'", indent, log.program.as_ref().map_or(String::new(), program_name)),
+        Event::Message(text) => format!("{}log '{}'", indent, text),
+        Event::Consumed { units, limit } => format!("{}consumed '{}' of '{}' compute units", indent, units, limit),
+        Event::Success => format!("{}success", indent),
+        Event::Failed { counter_error: Some(counter_err), .. } => {
+            format!("{}failed '{:?}: {}'", indent, counter_err, counter_err)
+        }
+        Event::Failed { error, .. } => format!("{}failed '{}'", indent, error),
+        Event::Other(line) => format!("{}{}", indent, line),
+    }
+}
+
+fn op_name(op: OperationKind) -> &'static str {
+    match op {
+        OperationKind::Inc => "inc",
+        OperationKind::Dec => "dec",
+    }
+}
+
+pub fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::SuperAdmin => "super-admin",
+        Role::SettingsManager => "settings-manager",
+        Role::Moderator => "moderator",
+    }
+}
+
+fn counter_json(counter_pk: &Pubkey, counter: &Counter) -> Value {
+    json!({
+        "pubkey": counter_pk.to_string(),
+        "value": counter.value,
+        "owner": counter.owner.to_string(),
+        "name": counter.name(),
+        "label": counter.label(),
+        "created_at": counter.created_at,
+        "updated_at": counter.updated_at,
+        "op_count": counter.op_count,
+        "period_id": counter.period_id,
+        "relay_nonce": counter.relay_nonce,
+        "frozen": counter.frozen,
+    })
+}
+
+fn state_json(state: &State) -> Value {
+    match state {
+        State::Counter(counter_pk, counter) => counter_json(counter_pk, counter),
+        State::Counters(counters) => counters
+            .iter()
+            .map(|(counter_pk, counter)| counter_json(counter_pk, counter))
+            .collect(),
+        State::Settings(settings) => {
+            let reset_period = settings.reset_period().map(|period| match period {
+                ResetPeriod::Never => json!({ "kind": "never" }),
+                ResetPeriod::Slots(length) => json!({ "kind": "slots", "length": length }),
+                ResetPeriod::UnixTime(length) => json!({ "kind": "time", "length": length }),
+                ResetPeriod::Epochs(length) => json!({ "kind": "epochs", "length": length }),
+            });
+            json!({
+                "admin": settings.admin.to_string(),
+                "inc_step": settings.inc_step,
+                "dec_step": settings.dec_step,
+                "reset_period": reset_period,
+                "access_mode": settings.access_mode().map(|mode| format!("{:?}", mode).to_lowercase()),
+            })
+        }
+        State::AccessPage(access_page_pk, access_page) => json!({
+            "pubkey": access_page_pk.to_string(),
+            "bucket": access_page.bucket,
+            "index": access_page.index,
+            "pages": access_page.pages,
+            "keys": access_page.keys().iter().map(Pubkey::to_string).collect::<Vec<_>>(),
+        }),
+        State::Roles(user, roles) => json!({
+            "user": user.to_string(),
+            "roles": roles.iter().map(|role| role_name(*role)).collect::<Vec<_>>(),
+        }),
+        State::Leaderboard { leaderboard, limit, buckets } => {
+            // Sum beyond the JSON integer range is written as a string
+            let total = i64::try_from(leaderboard.total)
+                .map_or_else(|_| json!(leaderboard.total.to_string()), |total| json!(total));
+            json!({
+                "count": leaderboard.counters.len(),
+                "total": total,
+                "mean": leaderboard.mean(),
+                "top": leaderboard
+                    .top(*limit)
+                    .iter()
+                    .map(|(counter_pk, counter)| counter_json(counter_pk, counter))
+                    .collect::<Vec<_>>(),
+                "bottom": leaderboard
+                    .bottom(*limit)
+                    .map(|(counter_pk, counter)| counter_json(counter_pk, counter))
+                    .collect::<Vec<_>>(),
+                "histogram": leaderboard
+                    .histogram(*buckets)
+                    .iter()
+                    .map(|bucket| json!({ "from": bucket.from, "to": bucket.to, "count": bucket.count }))
+                    .collect::<Vec<_>>(),
+            })
+        }
+        State::History(address, entries) => json!({
+            "address": address.to_string(),
+            "transactions": entries.iter().map(entry_json).collect::<Vec<_>>(),
+        }),
+        State::Nonce(nonce_pk, nonce_account) => json!({
+            "pubkey": nonce_pk.to_string(),
+            "authority": nonce_account.authority.to_string(),
+            "blockhash": nonce_account.blockhash.to_string(),
+            "lamports": nonce_account.lamports,
+        }),
+        State::Relay { request, message, signature } => json!({
+            "owner": request.user.to_string(),
+            "name": request.name,
+            "op": op_name(request.op),
+            "nonce": request.nonce,
+            "expiry": request.expiry,
+            "message": bs58::encode(message).into_string(),
+            "signature": signature.map(|signature| signature.to_string()),
+        }),
+    }
+}
+
+fn entry_json(entry: &Entry) -> Value {
+    let calls: Vec<_> = entry
+        .calls
+        .iter()
+        .map(|call| {
+            json!({
+                "instruction": call.instruction.as_ref().map(|instruction| format!("{:?}", instruction)),
+                "accounts": call.accounts.iter().map(Pubkey::to_string).collect::<Vec<_>>(),
+                "error": call.error.as_ref().map(|err| {
+                    let counter_err = CounterError::from_instruction_error(err);
+                    json!({
+                        "code": counter_err.map(|counter_err| counter_err as u32),
+                        "name": counter_err.map(|counter_err| format!("{:?}", counter_err)),
+                        "message": instruction_error_text(err),
+                    })
+                }),
+            })
+        })
+        .collect();
+    json!({
+        "signature": entry.signature.to_string(),
+        "slot": entry.slot,
+        "block_time": entry.block_time,
+        "payer": entry.payer.map(|payer| payer.to_string()),
+        "success": entry.error.is_none(),
+        "error": entry.error.as_ref().map(ToString::to_string),
+        "calls": calls,
+    })
+}
+
+fn simulation_json(simulation: &Simulation) -> Value {
+    json!({
+        "success": simulation.error.is_none(),
+        "error": simulation.error.as_ref().map(ToString::to_string),
+        "units_consumed": simulation.units_consumed(),
+        "logs": simulation.logs.iter().map(log_json).collect::<Vec<_>>(),
+    })
+}
+
+fn log_json(log: &Log) -> Value {
+    let mut value = match &log.event {
+        Event::Invoke => json!({ "event": "invoke" }),
+        Event::Message(text) => json!({ "event": "log", "message": text }),
+        Event::Consumed { units, limit } => json!({ "event": "consumed", "units": units, "limit": limit }),
+        Event::Success => json!({ "event": "success" }),
+        Event::Failed { error, counter_error } => json!({
+            "event": "failed",
+            "error": error,
+            "code": counter_error.map(|counter_err| counter_err as u32),
+            "name": counter_error.map(|counter_err| format!("{:?}", counter_err)),
+        }),
+        Event::Other(line) => json!({ "event": "other", "line": line }),
+    };
+    value["depth"] = json!(log.depth);
+    value["program"] = json!(log.program.map(|program| program.to_string()));
+    value
+}
+
+/// `code` is the `CounterError` code of a transaction rejected by the program
+fn error_json(err: &(dyn Error + 'static)) -> Value {
+    let (kind, code, name) = match err.downcast_ref::<CounterClientError>() {
+        Some(CounterClientError::Program(_)) | Some(CounterClientError::SimulationFailed(_)) => {
+            match err.downcast_ref::<CounterClientError>().and_then(CounterClientError::counter_error) {
+                Some(counter_err) => ("program", Some(counter_err as u32), Some(format!("{:?}", counter_err))),
+                None => ("simulation", None, None),
+            }
+        }
+        Some(CounterClientError::Rpc(_)) | Some(CounterClientError::RetriesExhausted(..)) => ("rpc", None, None),
+        Some(CounterClientError::AccountNotFound(_)) => ("account_not_found", None, None),
+        Some(CounterClientError::InvalidAccountData(..)) | Some(CounterClientError::InvalidNonceAccount(_)) => {
+            ("invalid_account_data", None, None)
+        }
+        Some(CounterClientError::InvalidTransaction(_))
+        | Some(CounterClientError::Signer(_))
+        | Some(CounterClientError::UnexpectedSigner(_))
+        | Some(CounterClientError::InvalidSignature(_))
+        | Some(CounterClientError::MissingSignature(_)) => ("transaction", None, None),
+        Some(CounterClientError::InvalidCounterName(_)) | None => ("usage", None, None),
+    };
+    json!({
+        "kind": kind,
+        "code": code,
+        "name": name,
+        "message": err.to_string(),
+    })
+}
@@ -0,0 +1,158 @@
+use std::time::Duration;
+use solana_client::client_error::ClientErrorKind;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use crate::error::CounterClientError;
+use crate::simulate::Simulation;
+
+/// Upper bound of the delay between attempts
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(8);
+/// Tags of the compute budget instructions, Borsh encoded enum
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+/// Compute budget program, its id and instructions are not exported by every sdk version
+pub mod compute_budget {
+    solana_program::declare_id!("ComputeBudget111111111111111111111111111111");
+}
+
+/// How transactions are sent and retried
+#[derive(Clone, Debug, PartialEq)]
+pub struct SendConfig {
+    /// Attempts after the first one, only transient failures are retried
+    pub retries: u32,
+    /// Delay before the first retry, doubled before every next one up to `MAX_RETRY_DELAY`
+    pub retry_delay: Duration,
+    /// Price of a compute unit in micro-lamports paid on top of the base fee
+    pub priority_fee: Option<u64>,
+    /// Compute units the transaction may consume, the cluster default when `None`
+    pub compute_unit_limit: Option<u32>,
+    /// Stop after the simulation that precedes every attempt, nothing is sent
+    pub simulate_only: bool,
+}
+
+impl Default for SendConfig {
+    fn default() -> Self {
+        SendConfig {
+            retries: 3,
+            retry_delay: Duration::from_millis(500),
+            priority_fee: None,
+            compute_unit_limit: None,
+            simulate_only: false,
+        }
+    }
+}
+
+impl SendConfig {
+    /// Delay before the retry number `retry`, starting from 0
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.retry_delay
+            .checked_mul(2u32.saturating_pow(retry))
+            .map_or(MAX_RETRY_DELAY, |delay| delay.min(MAX_RETRY_DELAY))
+    }
+
+    /// Compute budget instructions of the configured limit and priority fee
+    pub fn compute_budget(&self) -> Vec<Instruction> {
+        let mut instrs = vec![];
+        if let Some(units) = self.compute_unit_limit {
+            let mut data = vec![SET_COMPUTE_UNIT_LIMIT];
+            data.extend_from_slice(&units.to_le_bytes());
+            instrs.push(Instruction::new_with_bytes(compute_budget::id(), &data, vec![]));
+        }
+        if let Some(price) = self.priority_fee {
+            let mut data = vec![SET_COMPUTE_UNIT_PRICE];
+            data.extend_from_slice(&price.to_le_bytes());
+            instrs.push(Instruction::new_with_bytes(compute_budget::id(), &data, vec![]));
+        }
+        instrs
+    }
+
+    /// `instructions` preceded by the compute budget ones
+    pub fn with_compute_budget(&self, instructions: &[Instruction]) -> Vec<Instruction> {
+        let mut instrs = self.compute_budget();
+        instrs.extend_from_slice(instructions);
+        instrs
+    }
+}
+
+/// Confirmed transaction
+#[derive(Debug)]
+pub struct Sent {
+    pub signature: Signature,
+    /// `None` when the node has no status of the transaction
+    pub slot: Option<u64>,
+    /// Number of times the transaction was sent, with a fresh blockhash after the previous one expired.
+    /// 0 when it was only simulated
+    pub attempts: u32,
+    /// Simulation of the sent transaction
+    pub simulation: Simulation,
+}
+
+/// Failure that may pass on another attempt: the node is unreachable or busy, or the blockhash expired.
+/// Transactions rejected by the runtime or the program fail the same way again
+pub fn is_transient(err: &CounterClientError) -> bool {
+    match err {
+        CounterClientError::Rpc(err) => match err.get_transaction_error() {
+            Some(tx_err) => tx_err == TransactionError::BlockhashNotFound,
+            None => matches!(
+                err.kind(),
+                ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) | ClientErrorKind::RpcError(_)
+            ),
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod send_test {
+    use std::time::Duration;
+    use counter::error::CounterError;
+    use solana_client::client_error::ClientError;
+    use solana_program::pubkey::Pubkey;
+    use solana_program::system_instruction;
+    use solana_sdk::instruction::InstructionError;
+    use solana_sdk::transaction::{Transaction, TransactionError};
+    use crate::error::CounterClientError;
+    use crate::send::{compute_budget, is_transient, SendConfig};
+
+    #[test]
+    fn when_retried_expect_doubled_delay_up_to_max() {
+        let config = SendConfig { retry_delay: Duration::from_millis(500), ..SendConfig::default() };
+
+        let delays: Vec<_> = (0..6).map(|retry| config.delay(retry).as_millis()).collect();
+
+        assert_eq!(delays, vec![500, 1000, 2000, 4000, 8000, 8000]);
+        assert_eq!(config.delay(u32::MAX), Duration::from_secs(8));
+    }
+
+    #[test]
+    fn when_compute_budget_expect_limit_and_price_before_instructions() {
+        let config = SendConfig { priority_fee: Some(1000), compute_unit_limit: Some(20_000), ..SendConfig::default() };
+
+        let instrs = config.compute_budget();
+
+        assert!(instrs.iter().all(|instr| instr.program_id == compute_budget::id() && instr.accounts.is_empty()));
+        assert_eq!(instrs[0].data, vec![2, 0x20, 0x4e, 0, 0]);
+        assert_eq!(instrs[1].data, vec![3, 0xe8, 0x03, 0, 0, 0, 0, 0, 0]);
+        assert!(SendConfig::default().compute_budget().is_empty());
+    }
+
+    #[test]
+    fn when_transport_error_or_expired_blockhash_expect_transient() {
+        let io_err = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset");
+        let expired = ClientError::from(TransactionError::BlockhashNotFound);
+        let no_funds = ClientError::from(TransactionError::InsufficientFundsForFee);
+        let transfer_instr = system_instruction::transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1);
+        let tx = Transaction::new_with_payer(&[transfer_instr], None);
+        let custom = ClientError::from(TransactionError::InstructionError(0, InstructionError::Custom(0)));
+        let rejected = CounterClientError::from_transaction(custom, &tx);
+
+        assert!(is_transient(&ClientError::from(io_err).into()));
+        assert!(is_transient(&expired.into()));
+        assert!(!is_transient(&no_funds.into()));
+        assert!(!matches!(rejected, CounterClientError::Program(_)));
+        assert!(!is_transient(&rejected));
+        assert!(!is_transient(&CounterClientError::Program(CounterError::AdminRequired)));
+    }
+}
@@ -0,0 +1,268 @@
+use std::str::FromStr;
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use counter::error::CounterError;
+use counter::id;
+
+/// What a program log line reports
+#[derive(Debug, PartialEq)]
+pub enum Event {
+    Invoke,
+    /// `msg!` output of the program
+    Message(String),
+    Consumed { units: u64, limit: u64 },
+    Success,
+    /// `counter_error` is set for the custom errors of the counter program
+    Failed { error: String, counter_error: Option<CounterError> },
+    /// Line of an unknown format
+    Other(String),
+}
+
+/// Decoded program log line
+#[derive(Debug, PartialEq)]
+pub struct Log {
+    /// Invocation depth, 1 for instructions of the transaction, 0 outside of programs
+    pub depth: usize,
+    /// Running program, `None` outside of programs
+    pub program: Option<Pubkey>,
+    pub event: Event,
+}
+
+/// Transaction run by the node without sending it
+#[derive(Debug)]
+pub struct Simulation {
+    /// `None` when the transaction would succeed
+    pub error: Option<TransactionError>,
+    pub logs: Vec<Log>,
+    /// Data of the requested accounts after the transaction, `None` for missing accounts
+    pub accounts: Vec<Option<Vec<u8>>>,
+    /// Program of every instruction of the transaction
+    pub programs: Vec<Pubkey>,
+}
+
+impl Simulation {
+    pub fn new(result: RpcSimulateTransactionResult, tx: &Transaction) -> Self {
+        Simulation {
+            error: result.err,
+            logs: decode_logs(&result.logs.unwrap_or_default()),
+            accounts: result
+                .accounts
+                .unwrap_or_default()
+                .into_iter()
+                .map(|account| account.and_then(|account| account.decode::<Account>()).map(|account| account.data))
+                .collect(),
+            programs: instruction_programs(tx),
+        }
+    }
+
+    /// Compute units of the transaction instructions, inner invocations are included in them
+    pub fn units_consumed(&self) -> u64 {
+        self.logs
+            .iter()
+            .filter(|log| log.depth == 1)
+            .map(|log| match log.event {
+                Event::Consumed { units, .. } => units,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Error of the counter program from its log. When the log is truncated, from the error code
+    /// of a failed counter instruction, a failed inner program can't be told apart then
+    pub fn counter_error(&self) -> Option<CounterError> {
+        let logged = self.logs.iter().find_map(|log| match log.event {
+            Event::Failed { counter_error, .. } => Some(counter_error),
+            _ => None,
+        });
+        match (logged, &self.error) {
+            (Some(counter_error), _) => counter_error,
+            (None, Some(TransactionError::InstructionError(index, instr_err)))
+                if self.programs.get(*index as usize) == Some(&id()) =>
+            {
+                CounterError::from_instruction_error(instr_err)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Program of every instruction of `tx`
+pub fn instruction_programs(tx: &Transaction) -> Vec<Pubkey> {
+    tx.message
+        .instructions
+        .iter()
+        .map(|instr| tx.message.account_keys[instr.program_id_index as usize])
+        .collect()
+}
+
+/// Runtime log lines, e.g. `Program <id> invoke [1]`, with the program of every line
+pub fn decode_logs(lines: &[String]) -> Vec<Log> {
+    let mut programs: Vec<Pubkey> = vec![];
+    let mut logs = vec![];
+    for line in lines {
+        let log = match decode_line(line) {
+            Some((program, event)) => {
+                if event == Event::Invoke {
+                    programs.push(program);
+                }
+                let log = Log { depth: programs.len(), program: Some(program), event };
+                if matches!(log.event, Event::Success | Event::Failed { .. }) {
+                    programs.pop();
+                }
+                log
+            }
+            None => {
+                let event = match line.strip_prefix("Program log: ") {
+                    Some(text) => Event::Message(text.to_string()),
+                    None => Event::Other(line.clone()),
+                };
+                Log { depth: programs.len(), program: programs.last().copied(), event }
+            }
+        };
+        logs.push(log);
+    }
+    logs
+}
+
+/// Line starting with the program id
+fn decode_line(line: &str) -> Option<(Pubkey, Event)> {
+    let (program, rest) = line.strip_prefix("Program ")?.split_once(' ')?;
+    let program = Pubkey::from_str(program).ok()?;
+    let event = if rest.starts_with("invoke [") {
+        Event::Invoke
+    } else if rest == "success" {
+        Event::Success
+    } else if let Some(error) = rest.strip_prefix("failed: ") {
+        let counter_error = error
+            .strip_prefix("custom program error: 0x")
+            .and_then(|code| u32::from_str_radix(code, 16).ok())
+            .filter(|_| program == id())
+            .and_then(|code| CounterError::from_instruction_error(&InstructionError::Custom(code)));
+        Event::Failed { error: error.to_string(), counter_error }
+    } else {
+        let words: Vec<&str> = rest.split_whitespace().collect();
+        match words.as_slice() {
+            ["consumed", units, "of", limit, "compute", "units"] => {
+                Event::Consumed { units: units.parse().ok()?, limit: limit.parse().ok()? }
+            }
+            _ => return None,
+        }
+    };
+    Some((program, event))
+}
+
+#[cfg(test)]
+mod simulate_test {
+    use counter::error::CounterError;
+    use counter::id;
+    use solana_program::pubkey::Pubkey;
+    use solana_sdk::instruction::InstructionError;
+    use solana_sdk::transaction::TransactionError;
+    use crate::simulate::{decode_logs, Event, Log, Simulation};
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn when_nested_invocations_expect_program_and_depth_of_every_line() {
+        let other = Pubkey::new_unique();
+        let logs = decode_logs(&lines(&[
+            &format!("Program {} invoke [1]", id()),
+            "Program log: Instruction: Inc",
+            &format!("Program {} invoke [2]", other),
+            &format!("Program {} success", other),
+            &format!("Program {} consumed 1500 of 200000 compute units", id()),
+            &format!("Program {} success", id()),
+        ]));
+
+        assert_eq!(
+            logs,
+            vec![
+                Log { depth: 1, program: Some(id()), event: Event::Invoke },
+                Log { depth: 1, program: Some(id()), event: Event::Message("Instruction: Inc".to_string()) },
+                Log { depth: 2, program: Some(other), event: Event::Invoke },
+                Log { depth: 2, program: Some(other), event: Event::Success },
+                Log { depth: 1, program: Some(id()), event: Event::Consumed { units: 1500, limit: 200000 } },
+                Log { depth: 1, program: Some(id()), event: Event::Success },
+            ]
+        );
+    }
+
+    #[test]
+    fn when_counter_program_failed_expect_counter_error() {
+        let code = CounterError::Frozen as u32;
+        let simulation = Simulation {
+            error: Some(TransactionError::InstructionError(0, InstructionError::Custom(code))),
+            logs: decode_logs(&lines(&[
+                &format!("Program {} invoke [1]", id()),
+                &format!("Program {} consumed 800 of 200000 compute units", id()),
+                &format!("Program {} failed: custom program error: {:#x}", id(), code),
+            ])),
+            accounts: vec![],
+            programs: vec![id()],
+        };
+
+        assert_eq!(simulation.counter_error(), Some(CounterError::Frozen));
+        assert_eq!(simulation.units_consumed(), 800);
+        assert_eq!(simulation.logs[2].depth, 1);
+    }
+
+    #[test]
+    fn when_other_program_failed_expect_no_counter_error() {
+        let system = Pubkey::default();
+        let simulation = Simulation {
+            error: Some(TransactionError::InstructionError(0, InstructionError::Custom(0))),
+            logs: decode_logs(&lines(&[
+                &format!("Program {} invoke [1]", system),
+                &format!("Program {} failed: custom program error: 0x0", system),
+            ])),
+            accounts: vec![],
+            programs: vec![system],
+        };
+
+        assert_eq!(simulation.counter_error(), None);
+    }
+
+    #[test]
+    fn when_log_truncated_expect_counter_error_of_counter_instruction_only() {
+        let other = Pubkey::new_unique();
+        let failed = |index, code| Simulation {
+            error: Some(TransactionError::InstructionError(index, InstructionError::Custom(code))),
+            logs: decode_logs(&lines(&["Log truncated"])),
+            accounts: vec![],
+            programs: vec![other, id()],
+        };
+
+        assert_eq!(failed(1, CounterError::Frozen as u32).counter_error(), Some(CounterError::Frozen));
+        assert_eq!(failed(0, 0).counter_error(), None);
+    }
+
+    #[test]
+    fn when_several_instructions_expect_units_of_top_level_summed() {
+        let other = Pubkey::new_unique();
+        let logs = decode_logs(&lines(&[
+            &format!("Program {} invoke [1]", id()),
+            &format!("Program {} invoke [2]", other),
+            &format!("Program {} consumed 300 of 199000 compute units", other),
+            &format!("Program {} success", other),
+            &format!("Program {} consumed 1000 of 200000 compute units", id()),
+            &format!("Program {} success", id()),
+            &format!("Program {} invoke [1]", id()),
+            &format!("Program {} consumed 700 of 199000 compute units", id()),
+            &format!("Program {} success", id()),
+            "Log truncated",
+        ]));
+
+        let simulation = Simulation { error: None, logs, accounts: vec![], programs: vec![id(), id()] };
+
+        assert_eq!(simulation.units_consumed(), 1700);
+        assert_eq!(
+            simulation.logs.last(),
+            Some(&Log { depth: 0, program: None, event: Event::Other("Log truncated".to_string()) })
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::Duration;
+use borsh::BorshDeserialize;
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::pubsub_client::PubsubClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_response::{Response, RpcKeyedAccount};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use counter::id;
+use counter::state::{Counter, Settings};
+use crate::client::CounterClient;
+use crate::error::Result;
+
+/// Reconnect delay doubles after every failed attempt up to this limit
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Accounts to watch
+pub enum Watch {
+    Account(Pubkey),
+    /// Every counter and the settings
+    Program,
+}
+
+/// Decoded counter program account
+#[derive(Debug, PartialEq)]
+pub enum AccountState {
+    Counter(Counter),
+    Settings(Settings),
+}
+
+impl AccountState {
+    /// `None` for accounts of other kinds and undecodable data
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        match data.len() {
+            Counter::LEN => Counter::try_from_slice(data).ok().map(AccountState::Counter),
+            Settings::LEN => Settings::try_from_slice(data).ok().map(AccountState::Settings),
+            _ => None,
+        }
+    }
+}
+
+pub enum WatchEvent {
+    /// State of the watched accounts taken after every subscription, the base of the diffs of the next updates.
+    /// After a reconnect it holds the changes made while disconnected
+    Snapshot(Vec<(Pubkey, AccountState)>),
+    Update { pubkey: Pubkey, slot: u64, state: AccountState },
+    /// Subscription is lost or can't be created, the next attempt is made after `retry_in`
+    Disconnected { reason: String, retry_in: Duration },
+}
+
+/// Websocket URL of the node at `rpc_url`, by convention it listens on the next port
+pub fn websocket_url(rpc_url: &str) -> String {
+    let (scheme, rest) = match rpc_url.split_once("://") {
+        Some(("https", rest)) => ("wss", rest),
+        Some((_, rest)) => ("ws", rest),
+        None => ("ws", rpc_url),
+    };
+    let (host, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+    let host = match host.rsplit_once(':').map(|(name, port)| (name, port.parse::<u16>())) {
+        Some((name, Ok(port))) => format!("{}:{}", name, port.wrapping_add(1)),
+        _ => host.to_string(),
+    };
+    format!("{}://{}{}", scheme, host, path)
+}
+
+fn decode_update(pubkey: Pubkey, response: Response<UiAccount>) -> Option<WatchEvent> {
+    let account: Account = response.value.decode()?;
+    let state = AccountState::decode(&account.data)?;
+    Some(WatchEvent::Update { pubkey, slot: response.context.slot, state })
+}
+
+fn decode_keyed_update(response: Response<RpcKeyedAccount>) -> Option<WatchEvent> {
+    let pubkey = Pubkey::from_str(&response.value.pubkey).ok()?;
+    decode_update(pubkey, Response { context: response.context, value: response.value.account })
+}
+
+/// Current state of the watched accounts
+pub fn snapshot(client: &CounterClient, target: &Watch) -> Result<Vec<(Pubkey, AccountState)>> {
+    let rpc = client.rpc();
+    let accounts = match target {
+        Watch::Account(pubkey) => rpc
+            .get_account_with_commitment(pubkey, rpc.commitment())?
+            .value
+            .map(|account| vec![(*pubkey, account)])
+            .unwrap_or_default(),
+        Watch::Program => {
+            let config = RpcProgramAccountsConfig {
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(rpc.commitment()),
+                    ..RpcAccountInfoConfig::default()
+                },
+                ..RpcProgramAccountsConfig::default()
+            };
+            rpc.get_program_accounts_with_config(&id(), config)?
+        }
+    };
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(pubkey, account)| AccountState::decode(&account.data).map(|state| (pubkey, state)))
+        .collect())
+}
+
+/// Pass the snapshot of `target` taken after subscribing and then decoded messages to `on_event`,
+/// `None` when it asks to stop or the reason of the disconnect
+fn receive<T, E, R, D, F>(
+    client: &CounterClient,
+    target: &Watch,
+    mut recv: R,
+    decode: D,
+    on_event: &mut F,
+) -> Option<String>
+where
+    E: ToString,
+    R: FnMut() -> std::result::Result<T, E>,
+    D: Fn(T) -> Option<WatchEvent>,
+    F: FnMut(WatchEvent) -> bool,
+{
+    match snapshot(client, target) {
+        Ok(accounts) => {
+            if !on_event(WatchEvent::Snapshot(accounts)) {
+                return None;
+            }
+        }
+        Err(err) => return Some(err.to_string()),
+    }
+    loop {
+        match recv() {
+            Ok(message) => {
+                if let Some(event) = decode(message) {
+                    if !on_event(event) {
+                        return None;
+                    }
+                }
+            }
+            Err(err) => return Some(err.to_string()),
+        }
+    }
+}
+
+/// Stream updates of `target` to `on_event` until it returns `false`. Lost subscriptions are
+/// recreated, the snapshot after them replaces the updates made while disconnected
+pub fn watch<F>(client: &CounterClient, ws_url: &str, target: &Watch, commitment: CommitmentConfig, mut on_event: F)
+where
+    F: FnMut(WatchEvent) -> bool,
+{
+    let account_config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(commitment),
+        ..RpcAccountInfoConfig::default()
+    };
+    let mut retry_in = Duration::from_secs(1);
+    loop {
+        // Subscription is unsubscribed when dropped at the end of the iteration
+        let disconnected = match target {
+            Watch::Account(pubkey) => {
+                match PubsubClient::account_subscribe(ws_url, pubkey, Some(account_config.clone())) {
+                    Ok((_subscription, receiver)) => {
+                        retry_in = Duration::from_secs(1);
+                        let decode = |response| decode_update(*pubkey, response);
+                        receive(client, target, || receiver.recv(), decode, &mut on_event)
+                    }
+                    Err(err) => Some(err.to_string()),
+                }
+            }
+            Watch::Program => {
+                let config = RpcProgramAccountsConfig {
+                    account_config: account_config.clone(),
+                    ..RpcProgramAccountsConfig::default()
+                };
+                match PubsubClient::program_subscribe(ws_url, &id(), Some(config)) {
+                    Ok((_subscription, receiver)) => {
+                        retry_in = Duration::from_secs(1);
+                        receive(client, target, || receiver.recv(), decode_keyed_update, &mut on_event)
+                    }
+                    Err(err) => Some(err.to_string()),
+                }
+            }
+        };
+        let reason = match disconnected {
+            Some(reason) => reason,
+            None => return,
+        };
+        if !on_event(WatchEvent::Disconnected { reason, retry_in }) {
+            return;
+        }
+        sleep(retry_in);
+        retry_in = (retry_in * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+#[cfg(test)]
+mod watch_test {
+    use borsh::BorshSerialize;
+    use counter::state::Settings;
+    use solana_program::pubkey::Pubkey;
+    use crate::watch::{websocket_url, AccountState};
+
+    #[test]
+    fn when_http_url_expect_ws_on_next_port() {
+        assert_eq!(websocket_url("http://localhost:8899"), "ws://localhost:8900");
+        assert_eq!(websocket_url("http://127.0.0.1:8899/"), "ws://127.0.0.1:8900/");
+    }
+
+    #[test]
+    fn when_https_url_without_port_expect_wss() {
+        assert_eq!(websocket_url("https://api.testnet.solana.com/"), "wss://api.testnet.solana.com/");
+    }
+
+    #[test]
+    fn when_settings_data_expect_settings_state() {
+        let settings = Settings {
+            admin: Pubkey::new_unique(),
+            inc_step: 2,
+            dec_step: 1,
+            reset_period_kind: 0,
+            reset_period: 0,
+            access_mode: 0,
+        };
+        let data = settings.try_to_vec().unwrap();
+
+        assert_eq!(AccountState::decode(&data), Some(AccountState::Settings(settings)));
+    }
+
+    #[test]
+    fn when_unknown_data_expect_none() {
+        assert_eq!(AccountState::decode(&[0; 3]), None);
+    }
+}
@@ -0,0 +1,14 @@
+use std::thread::sleep;
+use std::time::Duration;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use counter_rpc_client::CounterClient;
+
+pub fn funded_keypair(client: &CounterClient) -> Keypair {
+    let keypair = Keypair::new();
+    let signature = client.rpc().request_airdrop(&keypair.pubkey(), 1_000_000_000).unwrap();
+    while !client.rpc().confirm_transaction(&signature).unwrap() {
+        sleep(Duration::from_millis(200));
+    }
+    keypair
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "test-validator")]
+
+//! Runs against a local validator with the program deployed, see README
+
+use std::thread::sleep;
+use std::time::Duration;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use counter::instruction::CounterInstruction;
+use counter_rpc_client::offline::{self, Encoding};
+use counter_rpc_client::{nonce, CounterClient};
+use common::funded_keypair;
+
+mod common;
+
+/// Nonce can't be advanced or closed while it equals the recent blockhash
+fn wait_for_new_blockhash(client: &CounterClient, nonce_pk: &Pubkey) {
+    let nonce_hash = nonce::get(client, nonce_pk).unwrap().blockhash;
+    while client.recent_blockhash().unwrap() == nonce_hash {
+        sleep(Duration::from_millis(200));
+    }
+}
+
+#[test]
+fn nonce_transaction_signed_offline() {
+    let client = CounterClient::with_commitment("http://localhost:8899", CommitmentConfig::confirmed());
+    let user = funded_keypair(&client);
+    let nonce_keypair = Keypair::new();
+    nonce::create(&client, &user, &nonce_keypair, &user.pubkey()).unwrap();
+    let nonce_account = nonce::get(&client, &nonce_keypair.pubkey()).unwrap();
+    assert_eq!(nonce_account.authority, user.pubkey());
+
+    let instr = CounterInstruction::create_instr(user.pubkey(), user.pubkey(), "nonce").unwrap();
+    let tx = nonce::unsigned_transaction(&[instr], &user.pubkey(), &nonce_keypair.pubkey(), &nonce_account);
+    let encoded = Encoding::Base64.encode(&tx);
+    // Signer gets only the encoded transaction and returns the signature
+    let mut offline_tx = Encoding::Base64.decode(&encoded).unwrap();
+    let signature = offline::sign(&mut offline_tx, &user).unwrap();
+    let mut online_tx = Encoding::Base64.decode(&encoded).unwrap();
+    offline::add_signature(&mut online_tx, &user.pubkey(), signature).unwrap();
+    wait_for_new_blockhash(&client, &nonce_keypair.pubkey());
+    client.broadcast(&online_tx).unwrap();
+
+    assert_eq!(client.get_counter(&user.pubkey(), "nonce").unwrap().owner, user.pubkey());
+    assert_ne!(nonce::get(&client, &nonce_keypair.pubkey()).unwrap().blockhash, nonce_account.blockhash);
+    assert!(client.broadcast(&online_tx).is_err());
+}
+
+#[test]
+fn nonce_authorize_and_withdraw() {
+    let client = CounterClient::with_commitment("http://localhost:8899", CommitmentConfig::confirmed());
+    let user = funded_keypair(&client);
+    let new_authority = funded_keypair(&client);
+    let nonce_keypair = Keypair::new();
+    nonce::create(&client, &user, &nonce_keypair, &user.pubkey()).unwrap();
+
+    nonce::authorize(&client, &user, &nonce_keypair.pubkey(), &new_authority.pubkey()).unwrap();
+    assert_eq!(nonce::get(&client, &nonce_keypair.pubkey()).unwrap().authority, new_authority.pubkey());
+    wait_for_new_blockhash(&client, &nonce_keypair.pubkey());
+    assert!(nonce::withdraw(&client, &user, &nonce_keypair.pubkey(), &user.pubkey()).is_err());
+
+    nonce::withdraw(&client, &new_authority, &nonce_keypair.pubkey(), &user.pubkey()).unwrap();
+    assert!(nonce::get(&client, &nonce_keypair.pubkey()).is_err());
+}
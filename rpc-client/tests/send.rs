@@ -0,0 +1,49 @@
+#![cfg(feature = "test-validator")]
+
+//! Runs against a local validator with the program deployed, see README
+
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signer::Signer;
+use solana_transaction_status::UiTransactionEncoding;
+use counter_rpc_client::send::SendConfig;
+use counter_rpc_client::{CounterClient, CounterClientError};
+use common::funded_keypair;
+
+mod common;
+
+#[test]
+fn priority_fee_transaction_confirmed() {
+    let send_config = SendConfig {
+        priority_fee: Some(1_000),
+        compute_unit_limit: Some(50_000),
+        ..SendConfig::default()
+    };
+    let client = CounterClient::with_commitment("http://localhost:8899", CommitmentConfig::confirmed())
+        .with_send_config(send_config);
+    let user = funded_keypair(&client);
+
+    let executed = client.create_counter(&user, "priority").unwrap();
+
+    assert_eq!(executed.sent.attempts, 1);
+    assert_eq!(executed.after.owner, user.pubkey());
+    let tx = client.rpc().get_transaction(&executed.sent.signature, UiTransactionEncoding::Json).unwrap();
+    assert!(tx.transaction.meta.unwrap().fee > 5_000);
+}
+
+#[test]
+fn simulated_transaction_not_sent() {
+    let send_config = SendConfig { simulate_only: true, ..SendConfig::default() };
+    let client = CounterClient::with_commitment("http://localhost:8899", CommitmentConfig::confirmed())
+        .with_send_config(send_config);
+    let user = funded_keypair(&client);
+
+    let executed = client.create_counter(&user, "simulated").unwrap();
+
+    assert_eq!(executed.sent.attempts, 0);
+    assert!(executed.sent.simulation.units_consumed() > 0);
+    assert_eq!(executed.after.owner, user.pubkey());
+    assert!(matches!(
+        client.get_counter(&user.pubkey(), "simulated"),
+        Err(CounterClientError::AccountNotFound(_))
+    ));
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "test-validator")]
+
+//! Runs against a local validator with the program deployed, see README
+
+use std::thread;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use counter::state::Counter;
+use counter_rpc_client::watch::{self, websocket_url, AccountState, Watch, WatchEvent};
+use counter_rpc_client::CounterClient;
+use common::funded_keypair;
+
+mod common;
+
+const URL: &str = "http://localhost:8899";
+
+#[test]
+fn watch_reports_counter_update() {
+    let client = CounterClient::with_commitment(URL, CommitmentConfig::confirmed());
+    let user = funded_keypair(&client);
+    let created = client.create_counter(&user, "watched").unwrap().after;
+    let counter_pk = Counter::generate_counter_pk(&user.pubkey(), "watched").unwrap();
+
+    let mut inc_user = Some(Keypair::from_bytes(&user.to_bytes()).unwrap());
+    let mut inc = None;
+    let mut snapshot = None;
+    let mut updated = None;
+    watch::watch(&client, &websocket_url(URL), &Watch::Account(counter_pk), CommitmentConfig::confirmed(), |event| {
+        match event {
+            WatchEvent::Snapshot(accounts) => {
+                snapshot = Some(accounts);
+                // Subscribed, the increment is reported by the next update
+                let user = inc_user.take().unwrap();
+                inc = Some(thread::spawn(move || {
+                    CounterClient::with_commitment(URL, CommitmentConfig::confirmed()).inc(&user, "watched").unwrap()
+                }));
+                true
+            }
+            WatchEvent::Update { pubkey, state, .. } => {
+                updated = Some((pubkey, state));
+                false
+            }
+            WatchEvent::Disconnected { reason, .. } => panic!("Disconnected: {}", reason),
+        }
+    });
+    let executed = inc.unwrap().join().unwrap();
+
+    assert_eq!(snapshot, Some(vec![(counter_pk, AccountState::Counter(created))]));
+    assert_eq!(updated, Some((counter_pk, AccountState::Counter(executed.after))));
+}
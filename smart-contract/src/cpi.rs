@@ -0,0 +1,113 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::Instruction;
+use solana_program::program::{invoke, invoke_signed};
+use solana_program::program_error::ProgramError;
+use crate::instruction::CounterInstruction;
+use crate::id;
+
+/// Accounts for `Inc`, `Dec` and `Reset` calls from another program
+pub struct Operation<'a> {
+    /// Counter program account
+    pub counter_program: AccountInfo<'a>,
+    /// Owner of the counter, must be a signer or a PDA of the calling program
+    pub user: AccountInfo<'a>,
+    /// Counter account
+    pub counter: AccountInfo<'a>,
+    /// Settings account, PDA
+    pub settings: AccountInfo<'a>,
+    /// Access page of the user, PDA, required only when access list is enabled
+    pub access_page: Option<AccountInfo<'a>>,
+}
+
+/// Accounts for `Create` calls from another program
+pub struct Create<'a> {
+    /// Counter program account
+    pub counter_program: AccountInfo<'a>,
+    /// Pays for the counter account
+    pub payer: AccountInfo<'a>,
+    /// Owner of the counter, must be a signer or a PDA of the calling program
+    pub user: AccountInfo<'a>,
+    /// Counter account
+    pub counter: AccountInfo<'a>,
+    /// Rent sysvar
+    pub rent: AccountInfo<'a>,
+    /// System program
+    pub system_program: AccountInfo<'a>,
+}
+
+/// Create named counter.
+/// `signer_seeds` are required only when `payer` or `user` is a PDA of the calling program
+pub fn create(accounts: Create, name: &str, signer_seeds: &[&[&[u8]]]) -> ProgramResult {
+    check_program(&accounts.counter_program)?;
+    let instr = CounterInstruction::create_instr_with_keys(
+        *accounts.payer.key,
+        *accounts.user.key,
+        *accounts.counter.key,
+        name,
+    );
+    call(
+        &instr,
+        &[
+            accounts.payer,
+            accounts.user,
+            accounts.counter,
+            accounts.rent,
+            accounts.system_program,
+            accounts.counter_program,
+        ],
+        signer_seeds,
+    )
+}
+
+/// Increment counter.
+/// `signer_seeds` are required only when `user` is a PDA of the calling program
+pub fn inc(accounts: Operation, signer_seeds: &[&[&[u8]]]) -> ProgramResult {
+    operation(accounts, &CounterInstruction::Inc, signer_seeds)
+}
+
+/// Decrement counter.
+/// `signer_seeds` are required only when `user` is a PDA of the calling program
+pub fn dec(accounts: Operation, signer_seeds: &[&[&[u8]]]) -> ProgramResult {
+    operation(accounts, &CounterInstruction::Dec, signer_seeds)
+}
+
+/// Reset counter.
+/// `signer_seeds` are required only when `user` is a PDA of the calling program
+pub fn reset(accounts: Operation, signer_seeds: &[&[&[u8]]]) -> ProgramResult {
+    operation(accounts, &CounterInstruction::Reset, signer_seeds)
+}
+
+fn operation(
+    accounts: Operation,
+    instr: &CounterInstruction,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    check_program(&accounts.counter_program)?;
+    let instr = CounterInstruction::operation_instr_with_keys(
+        *accounts.user.key,
+        *accounts.counter.key,
+        *accounts.settings.key,
+        accounts.access_page.as_ref().map(|access_page| *access_page.key),
+        instr,
+    );
+    let mut account_infos = vec![accounts.user, accounts.counter, accounts.settings];
+    account_infos.extend(accounts.access_page);
+    account_infos.push(accounts.counter_program);
+    call(&instr, &account_infos, signer_seeds)
+}
+
+fn check_program(counter_program: &AccountInfo) -> ProgramResult {
+    if *counter_program.key != id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+fn call(instr: &Instruction, account_infos: &[AccountInfo], signer_seeds: &[&[&[u8]]]) -> ProgramResult {
+    if signer_seeds.is_empty() {
+        invoke(instr, account_infos)
+    } else {
+        invoke_signed(instr, account_infos, signer_seeds)
+    }
+}
@@ -2,7 +2,11 @@ use solana_program::pubkey::Pubkey;
 use solana_program::account_info::AccountInfo;
 use solana_program::entrypoint::ProgramResult;
 use solana_program::entrypoint;
+#[cfg(feature = "log-errors")]
+use solana_program::program_error::PrintProgramError;
 use crate::processor::Processor;
+#[cfg(feature = "log-errors")]
+use crate::error::CounterError;
 
 entrypoint!(process_instruction);
 
@@ -11,5 +15,10 @@ pub fn process_instruction(
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    Processor::process(program_id, accounts, instruction_data)
-}
\ No newline at end of file
+    let result = Processor::process(program_id, accounts, instruction_data);
+    #[cfg(feature = "log-errors")]
+    if let Err(error) = &result {
+        error.print::<CounterError>();
+    }
+    result
+}
@@ -1,18 +1,160 @@
-use solana_program::program_error::ProgramError;
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use solana_program::decode_error::DecodeError;
+use solana_program::instruction::InstructionError;
+use solana_program::program_error::{PrintProgramError, ProgramError};
 use thiserror::Error;
 
-#[derive(Error, Debug, Copy, Clone)]
+/// Codes are stable, new variants are added to the end only
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq, FromPrimitive)]
 pub enum CounterError {
-    #[error("Admin signature required")]
+    #[error("Admin or role holder signature required")]
     AdminRequired,
     #[error("Wrong counter PDA for user")]
     WrongCounterPDA,
     #[error("Wrong settings PDA")]
     WrongSettingsPDA,
+    #[error("Counter name is too long")]
+    CounterNameTooLong,
+    #[error("Counter label is too long")]
+    CounterLabelTooLong,
+    #[error("Reset period length must be positive")]
+    WrongResetPeriod,
+    #[error("Counter is frozen by admin")]
+    Frozen,
+    #[error("User is not allowed to use counters")]
+    AccessDenied,
+    #[error("Wrong access page PDA for user")]
+    WrongAccessPagePDA,
+    #[error("Access page is full")]
+    AccessPageFull,
+    #[error("Wrong roles PDA")]
+    WrongRolesPDA,
+    #[error("Roles account is full")]
+    RolesFull,
+    #[error("Relayed operation is expired")]
+    RelayExpired,
+    #[error("Wrong nonce of relayed operation")]
+    WrongRelayNonce,
+    #[error("Ed25519 signature of the counter owner is missing")]
+    MissingRelaySignature,
+    #[error("Wrong session PDA for owner and session key")]
+    WrongSessionPDA,
+    #[error("Session is expired")]
+    SessionExpired,
+    #[error("Operation is not allowed for session")]
+    SessionOpNotAllowed,
+    #[error("Instruction data can't be decoded")]
+    InvalidInstruction,
+    #[error("User signature required")]
+    UserSignatureRequired,
+    #[error("Fee payer signature required")]
+    PayerSignatureRequired,
+    #[error("Session key signature required")]
+    SessionKeySignatureRequired,
+    #[error("Counter account must be writable")]
+    CounterNotWritable,
+    #[error("Counter belongs to another user")]
+    WrongCounterOwner,
+    #[error("Counter already exists")]
+    CounterAlreadyExists,
+    #[error("Counter account data is invalid")]
+    InvalidCounterData,
+    #[error("Settings account data is invalid")]
+    InvalidSettingsData,
+    #[error("Access page account data is invalid")]
+    InvalidAccessPageData,
+    #[error("Roles account data is invalid")]
+    InvalidRolesData,
+    #[error("Session account data is invalid")]
+    InvalidSessionData,
+    #[error("Admin account must be writable to pay for settings")]
+    AdminNotWritable,
+    #[error("Wrong instructions sysvar")]
+    WrongInstructionsSysvar,
+    #[error("Counter value overflow")]
+    Overflow,
+    #[error("Access page account required while access list is on")]
+    AccessPageRequired,
+    #[error("Session expiry must be in the future")]
+    SessionExpiryNotInFuture,
+    #[error("Session must allow at least one operation")]
+    EmptySessionOps,
+    #[error("Lamports overflow")]
+    LamportsOverflow,
+    #[error("Every access page of the bucket is required")]
+    AccessPagesMissing,
+}
+
+impl CounterError {
+    /// Program error of a failed counter instruction, e.g. from
+    /// `TransactionError::InstructionError(_, InstructionError::Custom(n))`
+    pub fn from_instruction_error(err: &InstructionError) -> Option<Self> {
+        match err {
+            InstructionError::Custom(code) => Self::from_u32(*code),
+            _ => None,
+        }
+    }
 }
 
 impl From<CounterError> for ProgramError {
     fn from(e: CounterError) -> Self {
         ProgramError::Custom(e as u32)
     }
-}
\ No newline at end of file
+}
+
+impl<T> DecodeError<T> for CounterError {
+    fn type_of() -> &'static str {
+        "CounterError"
+    }
+}
+
+impl PrintProgramError for CounterError {
+    fn print<E>(&self)
+    where
+        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
+    {
+        log_error!("CounterError: {}", self);
+    }
+}
+
+#[cfg(test)]
+mod error_test {
+    use solana_program::decode_error::DecodeError;
+    use solana_program::instruction::InstructionError;
+    use crate::error::CounterError;
+
+    #[test]
+    fn when_custom_code_expect_counter_error() {
+        let err = InstructionError::Custom(CounterError::Frozen as u32);
+
+        assert_eq!(CounterError::from_instruction_error(&err), Some(CounterError::Frozen));
+    }
+
+    #[test]
+    fn when_last_code_expect_counter_error() {
+        let err = InstructionError::Custom(CounterError::AccessPagesMissing as u32);
+
+        assert_eq!(CounterError::from_instruction_error(&err), Some(CounterError::AccessPagesMissing));
+    }
+
+    #[test]
+    fn when_unknown_code_expect_none() {
+        let err = InstructionError::Custom(CounterError::AccessPagesMissing as u32 + 1);
+
+        assert_eq!(CounterError::from_instruction_error(&err), None);
+        assert_eq!(CounterError::from_instruction_error(&InstructionError::InvalidArgument), None);
+    }
+
+    #[test]
+    fn when_codes_expect_stable() {
+        assert_eq!(CounterError::AdminRequired as u32, 0);
+        assert_eq!(CounterError::SessionOpNotAllowed as u32, 17);
+        assert_eq!(CounterError::InvalidInstruction as u32, 18);
+    }
+
+    #[test]
+    fn when_type_of_expect_counter_error() {
+        assert_eq!(<CounterError as DecodeError<CounterError>>::type_of(), "CounterError");
+    }
+}
@@ -1,28 +1,36 @@
 use borsh::BorshSerialize;
 use borsh::BorshDeserialize;
+use borsh::BorshSchema;
 use solana_program::instruction::{AccountMeta, Instruction};
-use solana_program::pubkey::Pubkey;
+use solana_program::pubkey::{Pubkey, PubkeyError};
 use solana_program::{system_program, sysvar};
-use crate::state::{Counter, Settings};
-use crate::id;
+use crate::state::{AccessMode, AccessPage, Counter, ResetPeriod, Role, Roles, Session, Settings};
+use crate::{ed25519_program, id, MAX_ACCESS_PAGES};
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Debug, Clone, PartialEq)]
 pub enum CounterInstruction {
     /// Increment counter
     /// 0. [signer] - owner counter
     /// 1. [writable] - counter_account, PDA
     /// 2. [] - settings account, PDA
+    /// 3. [] - first access page of the owner bucket, PDA, required only when access list is enabled
+    /// 4.. [] - other access pages of the bucket, every one in order for denylist, the one listing the owner for allowlist
     Inc,
 
     /// Decrement counter
     /// 0. [signer] - owner account
     /// 1. [writable] - counter account, PDA
     /// 2. [] - settings account, PDA
+    /// 3. [] - first access page of the owner bucket, PDA, required only when access list is enabled
+    /// 4.. [] - other access pages of the bucket, every one in order for denylist, the one listing the owner for allowlist
     Dec,
 
     /// Reset counter
     /// 0. [signer] - owner account
     /// 1. [writable] - counter account, PDA
+    /// 2. [] - settings account, PDA
+    /// 3. [] - first access page of the owner bucket, PDA, required only when access list is enabled
+    /// 4.. [] - other access pages of the bucket, every one in order for denylist, the one listing the owner for allowlist
     Reset,
 
     /// Update counter settings
@@ -30,57 +38,485 @@ pub enum CounterInstruction {
     /// 1. [writable] - settings account
     /// 2. [] - Rent sysvar (calculate rent for creating settings accounts)
     /// 3. [] - System program (creating accounts, transfer lamports)
+    /// 4. [] - roles account, PDA, required only for role holders other than `Settings.admin`
     UpdSett { admin: Pubkey, inc_step: u32, dec_step: u32 },
+
+    /// Create named counter
+    /// 0. [signer, writable] - payer account
+    /// 1. [signer] - owner account
+    /// 2. [writable] - counter account, derived from owner and name
+    /// 3. [] - Rent sysvar (calculate rent for creating counter account)
+    /// 4. [] - System program (creating accounts, transfer lamports)
+    Create { name: String },
+
+    /// Set counter label
+    /// 0. [signer] - owner account
+    /// 1. [writable] - counter account
+    SetLabel { label: String },
+
+    /// Set period of automatic counter reset
+    /// 0. [signer] - admin account
+    /// 1. [writable] - settings account, PDA
+    /// 2. [] - roles account, PDA, required only for role holders other than `Settings.admin`
+    SetResetPeriod { period: ResetPeriod },
+
+    /// Freeze counter of any user
+    /// 0. [signer] - admin account
+    /// 1. [] - settings account, PDA
+    /// 2. [writable] - counter account
+    /// 3. [] - roles account, PDA, required only for role holders other than `Settings.admin`
+    Freeze,
+
+    /// Unfreeze counter of any user
+    /// 0. [signer] - admin account
+    /// 1. [] - settings account, PDA
+    /// 2. [writable] - counter account
+    /// 3. [] - roles account, PDA, required only for role holders other than `Settings.admin`
+    Thaw,
+
+    /// Reset counter of any user, frozen too
+    /// 0. [signer] - admin account
+    /// 1. [] - settings account, PDA
+    /// 2. [writable] - counter account
+    /// 3. [] - roles account, PDA, required only for role holders other than `Settings.admin`
+    AdminReset,
+
+    /// Set access mode of counter operations
+    /// 0. [signer] - admin account
+    /// 1. [writable] - settings account, PDA
+    /// 2. [] - roles account, PDA, required only for role holders other than `Settings.admin`
+    SetAccessMode { mode: AccessMode },
+
+    /// Add user to the first page of its bucket with a free place, creates the page when all pages are full.
+    /// Fails with `AccessPageFull` when all `MAX_ACCESS_PAGES` pages of the bucket are full
+    /// 0. [signer, writable] - admin account
+    /// 1. [] - settings account, PDA
+    /// 2. [writable] - first access page of the user bucket, PDA
+    /// 3. [] - Rent sysvar (calculate rent for creating access page account)
+    /// 4. [] - System program (creating accounts, transfer lamports)
+    /// 5. [] - roles account, PDA, required for role holders other than `Settings.admin` or when pages follow
+    /// 6.. [writable] - other access pages of the bucket in order, then the next page created when all are full
+    AddAccess { user: Pubkey },
+
+    /// Remove user from the access list
+    /// 0. [signer] - admin account
+    /// 1. [] - settings account, PDA
+    /// 2. [writable] - first access page of the user bucket, PDA
+    /// 3. [] - roles account, PDA, required for role holders other than `Settings.admin` or when pages follow
+    /// 4.. [writable] - other access pages of the bucket in order
+    RemoveAccess { user: Pubkey },
+
+    /// Grant role to user, creates the roles account when needed. `SuperAdmin` only
+    /// 0. [signer, writable] - admin account
+    /// 1. [] - settings account, PDA
+    /// 2. [writable] - roles account, PDA
+    /// 3. [] - Rent sysvar (calculate rent for creating roles account)
+    /// 4. [] - System program (creating accounts, transfer lamports)
+    GrantRole { user: Pubkey, role: Role },
+
+    /// Revoke role from user. `SuperAdmin` only
+    /// 0. [signer] - admin account
+    /// 1. [] - settings account, PDA
+    /// 2. [writable] - roles account, PDA
+    RevokeRole { user: Pubkey, role: Role },
+
+    /// Increment or decrement counter on behalf of its owner, any relayer pays the fee.
+    /// The previous instruction must be the Ed25519 program instruction verifying
+    /// `RelayedMessage` signed by the owner, see `relayed_message`
+    /// 0. [] - owner account
+    /// 1. [writable] - counter account, PDA
+    /// 2. [] - settings account, PDA
+    /// 3. [] - Instructions sysvar
+    /// 4. [] - first access page of the owner bucket, PDA, required only when access list is enabled
+    /// 5.. [] - other access pages of the bucket, every one in order for denylist, the one listing the owner for allowlist
+    Relayed { op: OperationKind, nonce: u64, expiry: i64 },
+
+    /// Register session key allowed to apply `ops` until `expiry`, registering it again updates the session.
+    /// `expiry` must be in the future and `ops` must not be empty
+    /// 0. [signer, writable] - owner account
+    /// 1. [writable] - session account, PDA
+    /// 2. [] - Rent sysvar (calculate rent for creating session account)
+    /// 3. [] - System program (creating accounts, transfer lamports)
+    CreateSession { session_key: Pubkey, expiry: i64, ops: Vec<OperationKind> },
+
+    /// Revoke session before its expiry, rent is returned to the owner
+    /// 0. [signer, writable] - owner account
+    /// 1. [writable] - session account, PDA
+    RevokeSession { session_key: Pubkey },
+
+    /// Increment or decrement counter signed by a session key of its owner
+    /// 0. [signer] - session key
+    /// 1. [] - owner account
+    /// 2. [writable] - counter account, PDA
+    /// 3. [] - settings account, PDA
+    /// 4. [] - session account, PDA
+    /// 5. [] - first access page of the owner bucket, PDA, required only when access list is enabled
+    /// 6.. [] - other access pages of the bucket, every one in order for denylist, the one listing the owner for allowlist
+    SessionOp { op: OperationKind },
+}
+
+/// Operation changing counter value
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Debug, Clone, Copy, PartialEq)]
+pub enum OperationKind {
+    Inc,
+    Dec,
+}
+
+impl OperationKind {
+    /// Bit of the operation in `Session.allowed_ops`
+    pub fn mask(&self) -> u8 {
+        1 << *self as u8
+    }
 }
 
+/// Message the owner signs to authorize a relayed operation, Borsh serialized
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Debug, PartialEq)]
+pub struct RelayedMessage {
+    /// Counter program
+    pub program_id: Pubkey,
+
+    /// Counter account
+    pub counter: Pubkey,
+
+    /// Operation to apply
+    pub op: OperationKind,
+
+    /// Must be equal to `Counter.relay_nonce`
+    pub nonce: u64,
+
+    /// Unix timestamp after which the operation is rejected
+    pub expiry: i64,
+}
+
+/// Builders taking a counter name fail when the name does not fit into the address seed,
+/// see `MAX_COUNTER_NAME_LEN`
 impl CounterInstruction {
-    pub fn inc_instr(user: Pubkey) -> Instruction {
-        Self::operation_instr(user, &CounterInstruction::Inc)
+    pub fn inc_instr(user: Pubkey, name: &str) -> Result<Instruction, PubkeyError> {
+        Self::operation_instr(user, name, &CounterInstruction::Inc)
     }
 
-    pub fn dec_instr(user: Pubkey) -> Instruction {
-        Self::operation_instr(user, &CounterInstruction::Dec)
+    pub fn dec_instr(user: Pubkey, name: &str) -> Result<Instruction, PubkeyError> {
+        Self::operation_instr(user, name, &CounterInstruction::Dec)
     }
 
-    fn operation_instr(user: Pubkey, instr: &CounterInstruction) -> Instruction {
-        let counter_pk = Counter::generate_counter_pk(&user).unwrap();
+    fn operation_instr(user: Pubkey, name: &str, instr: &CounterInstruction) -> Result<Instruction, PubkeyError> {
+        let counter_pk = Counter::generate_counter_pk(&user, name)?;
+        let (settings_pk, _) = Settings::get_settings_pk_with_bump();
+        let (access_page_pk, _) = AccessPage::get_access_page_pk_with_bump(AccessPage::bucket(&user), 0);
+        Ok(Self::operation_instr_with_keys(user, counter_pk, settings_pk, Some(access_page_pk), instr))
+    }
+
+    /// Build `Inc`, `Dec` or `Reset` for already known account keys
+    pub(crate) fn operation_instr_with_keys(
+        user: Pubkey,
+        counter_pk: Pubkey,
+        settings_pk: Pubkey,
+        access_page_pk: Option<Pubkey>,
+        instr: &CounterInstruction,
+    ) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(user, true),
+            AccountMeta::new(counter_pk, false),
+            AccountMeta::new_readonly(settings_pk, false),
+        ];
+        if let Some(access_page_pk) = access_page_pk {
+            accounts.push(AccountMeta::new_readonly(access_page_pk, false));
+        }
+        Instruction::new_with_borsh(id(), &instr, accounts)
+    }
+
+    pub fn reset_instr(user: Pubkey, name: &str) -> Result<Instruction, PubkeyError> {
+        Self::operation_instr(user, name, &CounterInstruction::Reset)
+    }
+
+    pub fn upd_sett_instr(
+        current_admin: Pubkey,
+        new_admin: Pubkey,
+        inc_step: u32,
+        dec_step: u32,
+    ) -> Instruction {
         let (settings_pk, _) = Settings::get_settings_pk_with_bump();
         Instruction::new_with_borsh(
             id(),
-            &instr,
+            &CounterInstruction::UpdSett { admin: new_admin, inc_step, dec_step },
+            vec![
+                AccountMeta::new(current_admin, true),
+                AccountMeta::new(settings_pk, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                Self::roles_meta(),
+            ],
+        )
+    }
+
+    pub fn set_label_instr(user: Pubkey, name: &str, label: &str) -> Result<Instruction, PubkeyError> {
+        let counter_pk = Counter::generate_counter_pk(&user, name)?;
+        Ok(Instruction::new_with_borsh(
+            id(),
+            &CounterInstruction::SetLabel { label: label.to_string() },
             vec![
                 AccountMeta::new_readonly(user, true),
                 AccountMeta::new(counter_pk, false),
+            ],
+        ))
+    }
+
+    pub fn set_reset_period_instr(admin: Pubkey, period: ResetPeriod) -> Instruction {
+        let (settings_pk, _) = Settings::get_settings_pk_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &CounterInstruction::SetResetPeriod { period },
+            vec![
+                AccountMeta::new_readonly(admin, true),
+                AccountMeta::new(settings_pk, false),
+                Self::roles_meta(),
+            ],
+        )
+    }
+
+    pub fn freeze_instr(admin: Pubkey, user: Pubkey, name: &str) -> Result<Instruction, PubkeyError> {
+        Self::moderation_instr(admin, user, name, &CounterInstruction::Freeze)
+    }
+
+    pub fn thaw_instr(admin: Pubkey, user: Pubkey, name: &str) -> Result<Instruction, PubkeyError> {
+        Self::moderation_instr(admin, user, name, &CounterInstruction::Thaw)
+    }
+
+    pub fn admin_reset_instr(admin: Pubkey, user: Pubkey, name: &str) -> Result<Instruction, PubkeyError> {
+        Self::moderation_instr(admin, user, name, &CounterInstruction::AdminReset)
+    }
+
+    pub fn set_access_mode_instr(admin: Pubkey, mode: AccessMode) -> Instruction {
+        let (settings_pk, _) = Settings::get_settings_pk_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &CounterInstruction::SetAccessMode { mode },
+            vec![
+                AccountMeta::new_readonly(admin, true),
+                AccountMeta::new(settings_pk, false),
+                Self::roles_meta(),
+            ],
+        )
+    }
+
+    /// `pages` is `AccessPage.pages` of the first page of the user bucket, 0 before the bucket has pages.
+    /// The next page is passed too, in case all pages are full
+    pub fn add_access_instr(admin: Pubkey, user: Pubkey, pages: u8) -> Instruction {
+        let (settings_pk, _) = Settings::get_settings_pk_with_bump();
+        let (access_page_pk, _) = AccessPage::get_access_page_pk_with_bump(AccessPage::bucket(&user), 0);
+        let mut accounts = vec![
+            AccountMeta::new(admin, true),
+            AccountMeta::new_readonly(settings_pk, false),
+            AccountMeta::new(access_page_pk, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            Self::roles_meta(),
+        ];
+        accounts.extend(Self::access_page_metas(&user, 1..=pages.max(1).min(MAX_ACCESS_PAGES - 1), true));
+        Instruction::new_with_borsh(id(), &CounterInstruction::AddAccess { user }, accounts)
+    }
+
+    /// `pages` is `AccessPage.pages` of the first page of the user bucket
+    pub fn remove_access_instr(admin: Pubkey, user: Pubkey, pages: u8) -> Instruction {
+        let (settings_pk, _) = Settings::get_settings_pk_with_bump();
+        let (access_page_pk, _) = AccessPage::get_access_page_pk_with_bump(AccessPage::bucket(&user), 0);
+        let mut accounts = vec![
+            AccountMeta::new_readonly(admin, true),
+            AccountMeta::new_readonly(settings_pk, false),
+            AccountMeta::new(access_page_pk, false),
+            Self::roles_meta(),
+        ];
+        accounts.extend(Self::access_page_metas(&user, 1..pages, true));
+        Instruction::new_with_borsh(id(), &CounterInstruction::RemoveAccess { user }, accounts)
+    }
+
+    /// Operation instruction built for the first access page only, with the other `1..pages` pages
+    /// of the owner bucket appended. `pages` is `AccessPage.pages` of the first page
+    pub fn with_access_pages(mut instr: Instruction, owner: &Pubkey, pages: u8) -> Instruction {
+        instr.accounts.extend(Self::access_page_metas(owner, 1..pages, false));
+        instr
+    }
+
+    fn access_page_metas(
+        user: &Pubkey,
+        indexes: impl Iterator<Item = u8>,
+        is_writable: bool,
+    ) -> impl Iterator<Item = AccountMeta> {
+        let bucket = AccessPage::bucket(user);
+        indexes.map(move |index| {
+            let (access_page_pk, _) = AccessPage::get_access_page_pk_with_bump(bucket, index);
+            AccountMeta { pubkey: access_page_pk, is_signer: false, is_writable }
+        })
+    }
+
+    pub fn grant_role_instr(admin: Pubkey, user: Pubkey, role: Role) -> Instruction {
+        let (settings_pk, _) = Settings::get_settings_pk_with_bump();
+        let (roles_pk, _) = Roles::get_roles_pk_with_bump();
+        Instruction::new_with_borsh(
+            id(),
+            &CounterInstruction::GrantRole { user, role },
+            vec![
+                AccountMeta::new(admin, true),
                 AccountMeta::new_readonly(settings_pk, false),
+                AccountMeta::new(roles_pk, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
             ],
         )
     }
 
-    pub fn reset_instr(user: Pubkey) -> Instruction {
-        let counter_pk = Counter::generate_counter_pk(&user).unwrap();
+    pub fn revoke_role_instr(admin: Pubkey, user: Pubkey, role: Role) -> Instruction {
+        let (settings_pk, _) = Settings::get_settings_pk_with_bump();
+        let (roles_pk, _) = Roles::get_roles_pk_with_bump();
         Instruction::new_with_borsh(
             id(),
-            &CounterInstruction::Reset,
+            &CounterInstruction::RevokeRole { user, role },
             vec![
-                AccountMeta::new_readonly(user, true),
-                AccountMeta::new(counter_pk, false),
+                AccountMeta::new_readonly(admin, true),
+                AccountMeta::new_readonly(settings_pk, false),
+                AccountMeta::new(roles_pk, false),
             ],
         )
     }
 
-    pub fn upd_sett_instr(
-        current_admin: Pubkey,
-        new_admin: Pubkey,
-        inc_step: u32,
-        dec_step: u32,
+    /// Relayed operation on the counter of `user`, the transaction must contain
+    /// the Ed25519 program instruction verifying `relayed_message` right before it
+    pub fn relayed_instr(
+        user: Pubkey,
+        name: &str,
+        op: OperationKind,
+        nonce: u64,
+        expiry: i64,
+    ) -> Result<Instruction, PubkeyError> {
+        let counter_pk = Counter::generate_counter_pk(&user, name)?;
+        let (settings_pk, _) = Settings::get_settings_pk_with_bump();
+        let (access_page_pk, _) = AccessPage::get_access_page_pk_with_bump(AccessPage::bucket(&user), 0);
+        Ok(Instruction::new_with_borsh(
+            id(),
+            &CounterInstruction::Relayed { op, nonce, expiry },
+            vec![
+                AccountMeta::new_readonly(user, false),
+                AccountMeta::new(counter_pk, false),
+                AccountMeta::new_readonly(settings_pk, false),
+                AccountMeta::new_readonly(sysvar::instructions::id(), false),
+                AccountMeta::new_readonly(access_page_pk, false),
+            ],
+        ))
+    }
+
+    /// Message the owner signs for `relayed_instr`
+    pub fn relayed_message(
+        user: Pubkey,
+        name: &str,
+        op: OperationKind,
+        nonce: u64,
+        expiry: i64,
+    ) -> Result<Vec<u8>, PubkeyError> {
+        let counter = Counter::generate_counter_pk(&user, name)?;
+        // Serialization into a vector does not fail
+        Ok(RelayedMessage { program_id: id(), counter, op, nonce, expiry }.try_to_vec().unwrap())
+    }
+
+    pub fn create_session_instr(
+        owner: Pubkey,
+        session_key: Pubkey,
+        expiry: i64,
+        ops: &[OperationKind],
     ) -> Instruction {
+        let (session_pk, _) = Session::get_session_pk_with_bump(&owner, &session_key);
+        Instruction::new_with_borsh(
+            id(),
+            &CounterInstruction::CreateSession { session_key, expiry, ops: ops.to_vec() },
+            vec![
+                AccountMeta::new(owner, true),
+                AccountMeta::new(session_pk, false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    }
+
+    pub fn revoke_session_instr(owner: Pubkey, session_key: Pubkey) -> Instruction {
+        let (session_pk, _) = Session::get_session_pk_with_bump(&owner, &session_key);
+        Instruction::new_with_borsh(
+            id(),
+            &CounterInstruction::RevokeSession { session_key },
+            vec![
+                AccountMeta::new(owner, true),
+                AccountMeta::new(session_pk, false),
+            ],
+        )
+    }
+
+    /// Operation on the counter of `owner` signed by `session_key`
+    pub fn session_op_instr(
+        session_key: Pubkey,
+        owner: Pubkey,
+        name: &str,
+        op: OperationKind,
+    ) -> Result<Instruction, PubkeyError> {
+        let counter_pk = Counter::generate_counter_pk(&owner, name)?;
+        let (settings_pk, _) = Settings::get_settings_pk_with_bump();
+        let (session_pk, _) = Session::get_session_pk_with_bump(&owner, &session_key);
+        let (access_page_pk, _) = AccessPage::get_access_page_pk_with_bump(AccessPage::bucket(&owner), 0);
+        Ok(Instruction::new_with_borsh(
+            id(),
+            &CounterInstruction::SessionOp { op },
+            vec![
+                AccountMeta::new_readonly(session_key, true),
+                AccountMeta::new_readonly(owner, false),
+                AccountMeta::new(counter_pk, false),
+                AccountMeta::new_readonly(settings_pk, false),
+                AccountMeta::new_readonly(session_pk, false),
+                AccountMeta::new_readonly(access_page_pk, false),
+            ],
+        ))
+    }
+
+    /// Roles account is passed to every admin instruction, so role holders can sign them
+    fn roles_meta() -> AccountMeta {
+        AccountMeta::new_readonly(Roles::get_roles_pk_with_bump().0, false)
+    }
+
+    fn moderation_instr(
+        admin: Pubkey,
+        user: Pubkey,
+        name: &str,
+        instr: &CounterInstruction,
+    ) -> Result<Instruction, PubkeyError> {
+        let counter_pk = Counter::generate_counter_pk(&user, name)?;
         let (settings_pk, _) = Settings::get_settings_pk_with_bump();
+        Ok(Instruction::new_with_borsh(
+            id(),
+            &instr,
+            vec![
+                AccountMeta::new_readonly(admin, true),
+                AccountMeta::new_readonly(settings_pk, false),
+                AccountMeta::new(counter_pk, false),
+                Self::roles_meta(),
+            ],
+        ))
+    }
+
+    pub fn create_instr(payer: Pubkey, user: Pubkey, name: &str) -> Result<Instruction, PubkeyError> {
+        let counter_pk = Counter::generate_counter_pk(&user, name)?;
+        Ok(Self::create_instr_with_keys(payer, user, counter_pk, name))
+    }
+
+    /// Build `Create` for already known account keys
+    pub(crate) fn create_instr_with_keys(
+        payer: Pubkey,
+        user: Pubkey,
+        counter_pk: Pubkey,
+        name: &str,
+    ) -> Instruction {
         Instruction::new_with_borsh(
             id(),
-            &CounterInstruction::UpdSett { admin: new_admin, inc_step, dec_step },
+            &CounterInstruction::Create { name: name.to_string() },
             vec![
-                AccountMeta::new(current_admin, true),
-                AccountMeta::new(settings_pk, false),
+                AccountMeta::new(payer, true),
+                AccountMeta::new_readonly(user, true),
+                AccountMeta::new(counter_pk, false),
                 AccountMeta::new_readonly(sysvar::rent::id(), false),
                 AccountMeta::new_readonly(system_program::id(), false),
             ],
@@ -88,12 +524,61 @@ impl CounterInstruction {
     }
 }
 
+/// Ed25519 program instruction verifying `signature` of `message` by `signer`, the one placed before
+/// `Relayed`. Laid out as `new_ed25519_instruction` of the SDK: one signature with the public key
+/// and the message in the instruction itself
+pub fn ed25519_instruction(signer: &Pubkey, signature: &[u8], message: &[u8]) -> Instruction {
+    let (public_key_offset, signature_offset, message_offset) = (16_u16, 48_u16, 112_u16);
+    let mut data = vec![1, 0];
+    for offset in [
+        signature_offset,
+        u16::MAX,
+        public_key_offset,
+        u16::MAX,
+        message_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    Instruction::new_with_bytes(ed25519_program::id(), &data, vec![])
+}
+
+/// Ed25519 program instruction `data` at `index` verifies a signature of `message` by `signer`.
+/// Public key and message must be stored in the instruction itself
+pub(crate) fn ed25519_verifies(data: &[u8], index: u16, signer: &Pubkey, message: &[u8]) -> bool {
+    let read_u16 = |offset: usize| -> Option<usize> {
+        let bytes = data.get(offset..offset + 2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+    };
+    let in_place = |offset: usize| {
+        read_u16(offset).is_some_and(|ix| ix == u16::MAX as usize || ix == index as usize)
+    };
+    let slice = |offset: usize, len: usize| data.get(offset..offset.checked_add(len)?);
+    // Offsets of the first signature: signature, its instruction, public key, its instruction,
+    // message, message size and its instruction, each is u16
+    let check = || -> Option<bool> {
+        if *data.first()? == 0 {
+            return Some(false);
+        }
+        let public_key = slice(read_u16(6)?, 32)?;
+        let signed = slice(read_u16(10)?, read_u16(12)?)?;
+        Some(in_place(8) && in_place(14) && public_key == signer.as_ref() && signed == message)
+    };
+    check().unwrap_or(false)
+}
+
 #[cfg(test)]
 mod counter_instruction_test {
     use borsh::BorshSerialize;
     use borsh::BorshDeserialize;
-    use crate::instruction::CounterInstruction;
-    use solana_program::pubkey::Pubkey;
+    use crate::instruction::{ed25519_instruction, ed25519_verifies, CounterInstruction, OperationKind, RelayedMessage};
+    use crate::state::{AccessMode, AccessPage, ResetPeriod, Role};
+    use solana_program::instruction::Instruction;
+    use solana_program::pubkey::{Pubkey, PubkeyError};
     use std::str::FromStr;
 
     #[test]
@@ -167,4 +652,201 @@ mod counter_instruction_test {
 
         assert_eq!(upd_instr, instr)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn when_serialization_create_expect_ok() {
+        let create_instr = CounterInstruction::Create { name: "abc".to_string() };
+        let binary_instr = [4, 3, 0, 0, 0, 97, 98, 99];
+
+        assert_eq!(create_instr.try_to_vec().unwrap(), binary_instr)
+    }
+
+    #[test]
+    fn when_deserialization_create_expect_ok() {
+        let binary_instr = [4, 3, 0, 0, 0, 97, 98, 99];
+        let instr = CounterInstruction::try_from_slice(&binary_instr).unwrap();
+
+        assert_eq!(instr, CounterInstruction::Create { name: "abc".to_string() })
+    }
+
+    #[test]
+    fn when_serialization_set_label_expect_ok() {
+        let set_label_instr = CounterInstruction::SetLabel { label: "abc".to_string() };
+        let binary_instr = [5, 3, 0, 0, 0, 97, 98, 99];
+
+        assert_eq!(set_label_instr.try_to_vec().unwrap(), binary_instr)
+    }
+
+    #[test]
+    fn when_deserialization_set_label_expect_ok() {
+        let binary_instr = [5, 3, 0, 0, 0, 97, 98, 99];
+        let instr = CounterInstruction::try_from_slice(&binary_instr).unwrap();
+
+        assert_eq!(instr, CounterInstruction::SetLabel { label: "abc".to_string() })
+    }
+
+    #[test]
+    fn when_serialization_set_reset_period_expect_ok() {
+        let set_period_instr = CounterInstruction::SetResetPeriod { period: ResetPeriod::Epochs(1) };
+        let binary_instr = [6, 3, 1, 0, 0, 0, 0, 0, 0, 0];
+
+        assert_eq!(set_period_instr.try_to_vec().unwrap(), binary_instr)
+    }
+
+    #[test]
+    fn when_deserialization_set_reset_period_expect_ok() {
+        let binary_instr = [6, 3, 1, 0, 0, 0, 0, 0, 0, 0];
+        let instr = CounterInstruction::try_from_slice(&binary_instr).unwrap();
+
+        assert_eq!(instr, CounterInstruction::SetResetPeriod { period: ResetPeriod::Epochs(1) })
+    }
+
+    #[test]
+    fn when_serialization_moderation_expect_ok() {
+        assert_eq!(CounterInstruction::Freeze.try_to_vec().unwrap(), [7]);
+        assert_eq!(CounterInstruction::Thaw.try_to_vec().unwrap(), [8]);
+        assert_eq!(CounterInstruction::AdminReset.try_to_vec().unwrap(), [9]);
+    }
+
+    #[test]
+    fn when_deserialization_moderation_expect_ok() {
+        assert_eq!(CounterInstruction::try_from_slice(&[7]).unwrap(), CounterInstruction::Freeze);
+        assert_eq!(CounterInstruction::try_from_slice(&[8]).unwrap(), CounterInstruction::Thaw);
+        assert_eq!(CounterInstruction::try_from_slice(&[9]).unwrap(), CounterInstruction::AdminReset);
+    }
+
+    #[test]
+    fn when_serialization_access_expect_ok() {
+        let user = Pubkey::new_from_array([3_u8; 32]);
+        let mut binary_add = vec![11];
+        binary_add.extend_from_slice(&[3_u8; 32]);
+        let mut binary_remove = vec![12];
+        binary_remove.extend_from_slice(&[3_u8; 32]);
+
+        assert_eq!(CounterInstruction::SetAccessMode { mode: AccessMode::Allowlist }.try_to_vec().unwrap(), [10, 1]);
+        assert_eq!(CounterInstruction::AddAccess { user }.try_to_vec().unwrap(), binary_add);
+        assert_eq!(CounterInstruction::RemoveAccess { user }.try_to_vec().unwrap(), binary_remove);
+    }
+
+    #[test]
+    fn when_inc_instr_expect_access_page_account() {
+        let user = Pubkey::new_from_array([3_u8; 32]);
+        let instr = CounterInstruction::inc_instr(user, "").unwrap();
+
+        assert_eq!(instr.accounts.len(), 4);
+        assert_eq!(instr.accounts[3].pubkey, AccessPage::get_access_page_pk_with_bump(3, 0).0);
+    }
+
+    #[test]
+    fn when_with_access_pages_expect_other_pages_appended() {
+        let user = Pubkey::new_from_array([3_u8; 32]);
+        let instr = CounterInstruction::with_access_pages(CounterInstruction::inc_instr(user, "").unwrap(), &user, 3);
+
+        assert_eq!(instr.accounts.len(), 6);
+        assert_eq!(instr.accounts[4].pubkey, AccessPage::get_access_page_pk_with_bump(3, 1).0);
+        assert_eq!(instr.accounts[5].pubkey, AccessPage::get_access_page_pk_with_bump(3, 2).0);
+        assert_eq!(instr.accounts[5].is_writable, false);
+    }
+
+    #[test]
+    fn when_add_access_instr_expect_next_page_appended() {
+        let user = Pubkey::new_from_array([3_u8; 32]);
+        let pages = |instr: Instruction| instr.accounts[6..].iter().map(|meta| meta.pubkey).collect::<Vec<_>>();
+        let page = |index| AccessPage::get_access_page_pk_with_bump(3, index).0;
+
+        assert_eq!(pages(CounterInstruction::add_access_instr(user, user, 0)), [page(1)]);
+        assert_eq!(pages(CounterInstruction::add_access_instr(user, user, 2)), [page(1), page(2)]);
+        assert_eq!(pages(CounterInstruction::add_access_instr(user, user, crate::MAX_ACCESS_PAGES)).len(), 15);
+        assert_eq!(CounterInstruction::remove_access_instr(user, user, 2).accounts[4].pubkey, page(1));
+    }
+
+    #[test]
+    fn when_name_too_long_expect_err() {
+        let user = Pubkey::new_from_array([3_u8; 32]);
+        let name = "a".repeat(crate::MAX_COUNTER_NAME_LEN + 1);
+
+        assert_eq!(CounterInstruction::create_instr(user, user, &name), Err(PubkeyError::MaxSeedLengthExceeded));
+        assert_eq!(CounterInstruction::inc_instr(user, &name), Err(PubkeyError::MaxSeedLengthExceeded));
+        assert_eq!(CounterInstruction::freeze_instr(user, user, &name), Err(PubkeyError::MaxSeedLengthExceeded));
+        assert!(CounterInstruction::relayed_message(user, &name, OperationKind::Inc, 0, 0).is_err());
+        assert!(CounterInstruction::inc_instr(user, &"a".repeat(crate::MAX_COUNTER_NAME_LEN)).is_ok());
+    }
+
+    #[test]
+    fn when_serialization_role_expect_ok() {
+        let user = Pubkey::new_from_array([3_u8; 32]);
+        let mut binary_grant = vec![13];
+        binary_grant.extend_from_slice(&[3_u8; 32]);
+        binary_grant.push(2);
+        let mut binary_revoke = vec![14];
+        binary_revoke.extend_from_slice(&[3_u8; 32]);
+        binary_revoke.push(1);
+
+        assert_eq!(CounterInstruction::GrantRole { user, role: Role::Moderator }.try_to_vec().unwrap(), binary_grant);
+        assert_eq!(CounterInstruction::RevokeRole { user, role: Role::SettingsManager }.try_to_vec().unwrap(), binary_revoke);
+    }
+
+    #[test]
+    fn when_serialization_relayed_expect_ok() {
+        let relayed_instr = CounterInstruction::Relayed { op: OperationKind::Dec, nonce: 2, expiry: 3 };
+        let binary_instr = [15, 1, 2, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0];
+
+        assert_eq!(relayed_instr.try_to_vec().unwrap(), binary_instr)
+    }
+
+    #[test]
+    fn when_relayed_message_expect_counter_and_op() {
+        let user = Pubkey::new_from_array([3_u8; 32]);
+        let message = CounterInstruction::relayed_message(user, "", OperationKind::Inc, 7, 1637000000).unwrap();
+        let message = RelayedMessage::try_from_slice(&message).unwrap();
+
+        assert_eq!(message.program_id, crate::id());
+        assert_eq!(message.counter, crate::state::Counter::generate_counter_pk(&user, "").unwrap());
+        assert_eq!((message.op, message.nonce, message.expiry), (OperationKind::Inc, 7, 1637000000));
+    }
+
+    /// Data of `ed25519_instruction` with the signature, public key and message in the instruction `ix`
+    fn ed25519_data(signer: &Pubkey, message: &[u8], ix: u16) -> Vec<u8> {
+        let mut data = ed25519_instruction(signer, &[0; 64], message).data;
+        for offset in [4, 8, 14] {
+            data[offset..offset + 2].copy_from_slice(&ix.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn when_ed25519_verifies_signer_message_expect_true() {
+        let signer = Pubkey::new_from_array([3_u8; 32]);
+
+        assert_eq!(ed25519_verifies(&ed25519_data(&signer, b"msg", u16::MAX), 0, &signer, b"msg"), true);
+        assert_eq!(ed25519_verifies(&ed25519_data(&signer, b"msg", 2), 2, &signer, b"msg"), true);
+    }
+
+    #[test]
+    fn when_ed25519_verifies_other_expect_false() {
+        let signer = Pubkey::new_from_array([3_u8; 32]);
+        let other = Pubkey::new_from_array([4_u8; 32]);
+
+        assert_eq!(ed25519_verifies(&ed25519_data(&other, b"msg", u16::MAX), 0, &signer, b"msg"), false);
+        assert_eq!(ed25519_verifies(&ed25519_data(&signer, b"other", u16::MAX), 0, &signer, b"msg"), false);
+        assert_eq!(ed25519_verifies(&ed25519_data(&signer, b"msg", 1), 0, &signer, b"msg"), false);
+        assert_eq!(ed25519_verifies(&ed25519_data(&signer, b"msg", u16::MAX)[..20], 0, &signer, b"msg"), false);
+        assert_eq!(ed25519_verifies(&[], 0, &signer, b"msg"), false);
+    }
+
+    #[test]
+    fn when_serialization_session_expect_ok() {
+        let session_key = Pubkey::new_from_array([3_u8; 32]);
+        let mut binary_create = vec![16];
+        binary_create.extend_from_slice(&[3_u8; 32]);
+        binary_create.extend_from_slice(&[100, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 1]);
+        let create_instr = CounterInstruction::CreateSession {
+            session_key,
+            expiry: 100,
+            ops: vec![OperationKind::Inc, OperationKind::Dec],
+        };
+
+        assert_eq!(create_instr.try_to_vec().unwrap(), binary_create);
+        assert_eq!(CounterInstruction::SessionOp { op: OperationKind::Dec }.try_to_vec().unwrap(), [18, 1]);
+    }
+}
@@ -1,12 +1,43 @@
+#[macro_use]
+mod log;
+
 pub mod error;
 pub mod processor;
 pub mod instruction;
 pub mod state;
+pub mod pod;
 
 #[cfg(not(feature = "no-entrypoint"))]
 pub mod entrypoint;
 
+#[cfg(feature = "no-entrypoint")]
+pub mod cpi;
+
 pub const COUNTER_SEED: &str = "counter";
 pub const SETTINGS_SEED: &str = "settings";
+pub const ACCESS_SEED: &str = "access";
+pub const ROLES_SEED: &str = "roles";
+pub const SESSION_SEED: &str = "session";
+
+/// Number of user keys in one page of the access list
+pub const ACCESS_PAGE_CAPACITY: usize = 32;
+
+/// Number of pages in one bucket of the access list. Denylist checks every page of the bucket,
+/// so all of them must fit into one transaction
+pub const MAX_ACCESS_PAGES: u8 = 16;
+
+/// Number of keys holding roles
+pub const MAX_ROLE_MEMBERS: usize = 16;
+
+/// Counter name is appended to `COUNTER_SEED`, the whole seed is limited by `MAX_SEED_LEN`
+pub const MAX_COUNTER_NAME_LEN: usize = solana_program::pubkey::MAX_SEED_LEN - COUNTER_SEED.len();
+
+/// Maximum length of a counter label in bytes
+pub const MAX_COUNTER_LABEL_LEN: usize = 32;
+
+solana_program::declare_id!("7eWFSioVjHdJjbobEZu6hn5QLhmjWSv7qLMyCuzamYCG");
 
-solana_program::declare_id!("7eWFSioVjHdJjbobEZu6hn5QLhmjWSv7qLMyCuzamYCG");
\ No newline at end of file
+/// Native program verifying Ed25519 signatures, authorizes relayed operations
+pub mod ed25519_program {
+    solana_program::declare_id!("Ed25519SigVerify111111111111111111111111111");
+}
\ No newline at end of file
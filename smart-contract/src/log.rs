@@ -0,0 +1,19 @@
+//! Logging costs compute units, so it is compiled in only by cargo features:
+//! `log-errors` prints failed instructions, `log-verbose` also traces every handler.
+//! The default build is quiet
+
+/// Trace of instruction processing, logged with the `log-verbose` feature
+macro_rules! log_verbose {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log-verbose")]
+        solana_program::msg!($($arg)+);
+    }};
+}
+
+/// Error of a failed instruction, logged with the `log-errors` feature
+macro_rules! log_error {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log-errors")]
+        solana_program::msg!($($arg)+);
+    }};
+}
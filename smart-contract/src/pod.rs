@@ -0,0 +1,89 @@
+use bytemuck::{Pod, Zeroable};
+
+macro_rules! pod_int {
+    ($name:ident, $int:ty, $size:expr) => {
+        /// Little-endian integer with alignment 1, the same bytes as its Borsh serialization
+        #[repr(transparent)]
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+        pub struct $name(pub [u8; $size]);
+
+        impl From<$int> for $name {
+            fn from(value: $int) -> Self {
+                Self(value.to_le_bytes())
+            }
+        }
+
+        impl From<$name> for $int {
+            fn from(value: $name) -> Self {
+                <$int>::from_le_bytes(value.0)
+            }
+        }
+    };
+}
+
+pod_int!(PodI64, i64, 8);
+pod_int!(PodU64, u64, 8);
+pod_int!(PodU32, u32, 4);
+
+/// Read UTF-8 string of `len` bytes stored in a zero padded buffer
+pub fn str_from_bytes(bytes: &[u8], len: u8) -> &str {
+    bytes
+        .get(..len as usize)
+        .and_then(|s| std::str::from_utf8(s).ok())
+        .unwrap_or("")
+}
+
+/// Store string in a zero padded buffer, the string must fit into the buffer
+pub fn str_to_bytes<const N: usize>(s: &str) -> ([u8; N], u8) {
+    let mut bytes = [0; N];
+    bytes[..s.len()].copy_from_slice(s.as_bytes());
+    (bytes, s.len() as u8)
+}
+
+/// Deserialize account data with Borsh as the program did before zero-copy accounts,
+/// the `borsh-accounts` build does it on every load to meter what zero-copy saves
+#[cfg(feature = "borsh-accounts")]
+pub fn borsh_read<T: borsh::BorshDeserialize>(data: &[u8]) -> Result<T, std::io::Error> {
+    T::try_from_slice(data)
+}
+
+/// Deserialize account data with Borsh and serialize it back, see `borsh_read`
+#[cfg(feature = "borsh-accounts")]
+pub fn borsh_round_trip<T>(data: &mut [u8]) -> Result<(), std::io::Error>
+where
+    T: borsh::BorshSerialize + borsh::BorshDeserialize,
+{
+    borsh_read::<T>(data)?.serialize(&mut &mut data[..])
+}
+
+#[cfg(test)]
+mod pod_test {
+    use crate::pod::{str_from_bytes, str_to_bytes, PodI64, PodU32, PodU64};
+
+    #[test]
+    fn when_pod_int_expect_le_bytes() {
+        assert_eq!(PodI64::from(-777).0, (-777_i64).to_le_bytes());
+        assert_eq!(PodU64::from(777).0, 777_u64.to_le_bytes());
+        assert_eq!(PodU32::from(777).0, 777_u32.to_le_bytes());
+    }
+
+    #[test]
+    fn when_pod_int_round_trip_expect_same_value() {
+        assert_eq!(i64::from(PodI64::from(i64::MIN)), i64::MIN);
+        assert_eq!(u64::from(PodU64::from(u64::MAX)), u64::MAX);
+        assert_eq!(u32::from(PodU32::from(u32::MAX)), u32::MAX);
+    }
+
+    #[test]
+    fn when_str_round_trip_expect_same_str() {
+        let (bytes, len) = str_to_bytes::<8>("abc");
+
+        assert_eq!(bytes, [97, 98, 99, 0, 0, 0, 0, 0]);
+        assert_eq!(str_from_bytes(&bytes, len), "abc");
+    }
+
+    #[test]
+    fn when_str_len_out_of_buffer_expect_empty() {
+        assert_eq!(str_from_bytes(&[97, 98, 99], 4), "");
+    }
+}
@@ -1,19 +1,21 @@
 use solana_program::pubkey::Pubkey;
 use solana_program::account_info::AccountInfo;
 use solana_program::entrypoint::ProgramResult;
-use solana_program::msg;
+use solana_program::program_error::ProgramError;
 use borsh::BorshSerialize;
 use borsh::BorshDeserialize;
 use solana_program::account_info::next_account_info;
-use solana_program::program_error::ProgramError;
 use solana_program::rent::Rent;
+use solana_program::clock::Clock;
 use solana_program::sysvar::Sysvar;
-use solana_program::program::invoke_signed;
+use solana_program::program::{invoke, invoke_signed};
 use solana_program::system_instruction;
-use crate::instruction::CounterInstruction;
-use crate::state::{Counter, Settings};
+use solana_program::sysvar;
+use solana_program::sysvar::instructions;
+use crate::instruction::{ed25519_verifies, CounterInstruction, OperationKind, RelayedMessage};
+use crate::state::{AccessMode, AccessPage, Counter, PodAccessPage, PodCounter, PodRoles, PodSession, PodSettings, ResetPeriod, Role, Roles, Session, Settings};
 use crate::error::CounterError;
-use crate::id;
+use crate::{ed25519_program, id, MAX_ACCESS_PAGES};
 
 pub struct Processor;
 
@@ -24,19 +26,39 @@ impl Processor {
         accounts: &[AccountInfo],
         raw_data: &[u8],
     ) -> ProgramResult {
-        msg!("Processor::process: {:?}", raw_data);
-        let instruction = CounterInstruction::try_from_slice(raw_data)?;
+        log_verbose!("Processor::process: {:?}", raw_data);
+        let instruction = CounterInstruction::try_from_slice(raw_data)
+            .map_err(|_| CounterError::InvalidInstruction)?;
         match instruction {
-            CounterInstruction::Inc => Self::process_operation(accounts, instruction),
-            CounterInstruction::Dec => Self::process_operation(accounts, instruction),
+            CounterInstruction::Inc => Self::process_operation(accounts, OperationKind::Inc),
+            CounterInstruction::Dec => Self::process_operation(accounts, OperationKind::Dec),
             CounterInstruction::Reset => Self::process_reset(accounts),
             CounterInstruction::UpdSett { admin, inc_step, dec_step } =>
-                Self::process_upd_sett(accounts, admin, inc_step, dec_step)
+                Self::process_upd_sett(accounts, admin, inc_step, dec_step),
+            CounterInstruction::Create { name } => Self::process_create(accounts, name),
+            CounterInstruction::SetLabel { label } => Self::process_set_label(accounts, label),
+            CounterInstruction::SetResetPeriod { period } =>
+                Self::process_set_reset_period(accounts, period),
+            CounterInstruction::Freeze => Self::process_moderation(accounts, instruction),
+            CounterInstruction::Thaw => Self::process_moderation(accounts, instruction),
+            CounterInstruction::AdminReset => Self::process_moderation(accounts, instruction),
+            CounterInstruction::SetAccessMode { mode } => Self::process_set_access_mode(accounts, mode),
+            CounterInstruction::AddAccess { user } => Self::process_add_access(accounts, user),
+            CounterInstruction::RemoveAccess { user } => Self::process_remove_access(accounts, user),
+            CounterInstruction::GrantRole { user, role } => Self::process_grant_role(accounts, user, role),
+            CounterInstruction::RevokeRole { user, role } => Self::process_revoke_role(accounts, user, role),
+            CounterInstruction::Relayed { op, nonce, expiry } =>
+                Self::process_relayed(accounts, op, nonce, expiry),
+            CounterInstruction::CreateSession { session_key, expiry, ops } =>
+                Self::process_create_session(accounts, session_key, expiry, ops),
+            CounterInstruction::RevokeSession { session_key } =>
+                Self::process_revoke_session(accounts, session_key),
+            CounterInstruction::SessionOp { op } => Self::process_session_op(accounts, op),
         }
     }
 
-    fn process_operation(accounts: &[AccountInfo], inst: CounterInstruction) -> ProgramResult {
-        msg!("Processor:process_operation inst={:?}", inst);
+    fn process_operation(accounts: &[AccountInfo], op: OperationKind) -> ProgramResult {
+        log_verbose!("Processor:process_operation op={:?}", op);
 
         let acc_iter = &mut accounts.iter();
         let user_acc = next_account_info(acc_iter)?;
@@ -45,52 +67,360 @@ impl Processor {
 
         // precondition checks
         if !user_acc.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+            return Err(CounterError::UserSignatureRequired.into());
         }
-        if !counter_acc.is_writable
-            && !Counter::check_counter_pk(user_acc.key, counter_acc.key) {
-            return Err(CounterError::WrongCounterPDA.into());
+
+        Self::apply_operation(user_acc, counter_acc, settings_acc, acc_iter.as_slice(), op, None)?;
+
+        log_verbose!("Processor:process_operation done op={:?}", op);
+        Ok(())
+    }
+
+    fn process_relayed(
+        accounts: &[AccountInfo],
+        op: OperationKind,
+        nonce: u64,
+        expiry: i64,
+    ) -> ProgramResult {
+        log_verbose!("Processor:process_relayed op={:?} nonce={} expiry={}", op, nonce, expiry);
+
+        let acc_iter = &mut accounts.iter();
+        let user_acc = next_account_info(acc_iter)?;
+        let counter_acc = next_account_info(acc_iter)?;
+        let settings_acc = next_account_info(acc_iter)?;
+        let instructions_acc = next_account_info(acc_iter)?;
+
+        // precondition checks
+        if Clock::get()?.unix_timestamp > expiry {
+            return Err(CounterError::RelayExpired.into());
+        }
+        let message = RelayedMessage { program_id: id(), counter: *counter_acc.key, op, nonce, expiry };
+        Self::check_ed25519_signature(instructions_acc, user_acc.key, &message.try_to_vec()?)?;
+
+        Self::apply_operation(user_acc, counter_acc, settings_acc, acc_iter.as_slice(), op, Some(nonce))?;
+
+        log_verbose!("Processor:process_relayed done");
+        Ok(())
+    }
+
+    fn process_session_op(accounts: &[AccountInfo], op: OperationKind) -> ProgramResult {
+        log_verbose!("Processor:process_session_op op={:?}", op);
+
+        let acc_iter = &mut accounts.iter();
+        let session_key_acc = next_account_info(acc_iter)?;
+        let user_acc = next_account_info(acc_iter)?;
+        let counter_acc = next_account_info(acc_iter)?;
+        let settings_acc = next_account_info(acc_iter)?;
+        let session_acc = next_account_info(acc_iter)?;
+
+        // precondition checks
+        if !session_key_acc.is_signer {
+            return Err(CounterError::SessionKeySignatureRequired.into());
+        }
+        if !Session::check_session_pk(user_acc.key, session_key_acc.key, session_acc.key) {
+            return Err(CounterError::WrongSessionPDA.into());
+        }
+        // Revoked session account is deleted
+        if session_acc.data_is_empty() {
+            return Err(CounterError::SessionExpired.into());
         }
+        {
+            let session_data = session_acc.try_borrow_data()?;
+            let session = PodSession::load(&session_data)?;
+            if Clock::get()?.unix_timestamp > session.expiry() {
+                return Err(CounterError::SessionExpired.into());
+            }
+            if !session.allows(op) {
+                return Err(CounterError::SessionOpNotAllowed.into());
+            }
+        }
+
+        Self::apply_operation(user_acc, counter_acc, settings_acc, acc_iter.as_slice(), op, None)?;
+
+        log_verbose!("Processor:process_session_op done");
+        Ok(())
+    }
+
+    /// Apply `op` for the counter owner, authorized either by its signature or by a relayed message
+    /// with `relay_nonce`
+    fn apply_operation(
+        user_acc: &AccountInfo,
+        counter_acc: &AccountInfo,
+        settings_acc: &AccountInfo,
+        access_page_accs: &[AccountInfo],
+        op: OperationKind,
+        relay_nonce: Option<u64>,
+    ) -> ProgramResult {
         if !Settings::check_settings_pk(settings_acc.key) {
-            return Err(CounterError::WrongCounterPDA.into());
+            return Err(CounterError::WrongSettingsPDA.into());
         }
 
-        let settings: Settings = Settings::try_from_slice(&settings_acc.data.borrow())?;
-        let mut counter: Counter = Counter::try_from_slice(&counter_acc.data.borrow())?;
+        let settings_data = settings_acc.try_borrow_data()?;
+        let settings = PodSettings::load(&settings_data)?;
+        Self::check_access(user_acc, access_page_accs, settings.access_mode()?)?;
+        let mut counter_data = counter_acc.try_borrow_mut_data()?;
+        let counter = PodCounter::load_mut(&mut counter_data)?;
+        Self::check_counter(user_acc, counter_acc, counter)?;
+        if let Some(relay_nonce) = relay_nonce {
+            if counter.relay_nonce() != relay_nonce {
+                return Err(CounterError::WrongRelayNonce.into());
+            }
+            counter.advance_relay_nonce();
+        }
 
-        match inst {
-            CounterInstruction::Inc => counter.value += settings.inc_step as i64,
-            CounterInstruction::Dec => counter.value -= settings.dec_step as i64,
-            _ => panic!("Processor:process_operation incorrect inst={:?}", inst)
+        let clock = Clock::get()?;
+        if let Some(period_id) = settings.reset_period()?.period_id(&clock) {
+            counter.roll_period(period_id);
         }
 
-        counter.serialize(&mut &mut counter_acc.data.borrow_mut()[..])?;
-        msg!("Processor:process_operation done inst={:?}", inst);
+        let value = match op {
+            OperationKind::Inc => counter.value().checked_add(settings.inc_step() as i64),
+            OperationKind::Dec => counter.value().checked_sub(settings.dec_step() as i64),
+        };
+        counter.set_value(value.ok_or(CounterError::Overflow)?);
+        counter.touch(clock.unix_timestamp);
+        counter.count_operation();
+        Ok(())
+    }
+
+    /// Instruction before the current one must be the Ed25519 program instruction verifying
+    /// `message` signed by `signer`. The signature itself is verified by the Ed25519 program
+    fn check_ed25519_signature(
+        instructions_acc: &AccountInfo,
+        signer: &Pubkey,
+        message: &[u8],
+    ) -> ProgramResult {
+        if !sysvar::instructions::check_id(instructions_acc.key) {
+            return Err(CounterError::WrongInstructionsSysvar.into());
+        }
+        let current = instructions::load_current_index_checked(instructions_acc)?;
+        let ed25519_index = current.checked_sub(1).ok_or(CounterError::MissingRelaySignature)?;
+        let ed25519_instr = instructions::load_instruction_at_checked(ed25519_index as usize, instructions_acc)?;
+        if ed25519_instr.program_id != ed25519_program::id()
+            || !ed25519_verifies(&ed25519_instr.data, ed25519_index, signer, message) {
+            return Err(CounterError::MissingRelaySignature.into());
+        }
         Ok(())
     }
 
     fn process_reset(accounts: &[AccountInfo]) -> ProgramResult {
-        msg!("Processor:process_reset");
+        log_verbose!("Processor:process_reset");
+
+        let acc_iter = &mut accounts.iter();
+        let user_acc = next_account_info(acc_iter)?;
+        let counter_acc = next_account_info(acc_iter)?;
+        let settings_acc = next_account_info(acc_iter)?;
+
+        // precondition checks
+        if !user_acc.is_signer {
+            return Err(CounterError::UserSignatureRequired.into());
+        }
+        if !Settings::check_settings_pk(settings_acc.key) {
+            return Err(CounterError::WrongSettingsPDA.into());
+        }
+        {
+            let settings_data = settings_acc.try_borrow_data()?;
+            let settings = PodSettings::load(&settings_data)?;
+            Self::check_access(user_acc, acc_iter.as_slice(), settings.access_mode()?)?;
+        }
+
+        let mut counter_data = counter_acc.try_borrow_mut_data()?;
+        let counter = PodCounter::load_mut(&mut counter_data)?;
+        Self::check_counter(user_acc, counter_acc, counter)?;
+
+        counter.set_value(0);
+        counter.touch(Clock::get()?.unix_timestamp);
+        counter.count_operation();
+
+        log_verbose!("Processor:process_reset done");
+        Ok(())
+    }
+
+    /// Counter must belong to the user, be writable and not frozen
+    fn check_counter(
+        user_acc: &AccountInfo,
+        counter_acc: &AccountInfo,
+        counter: &PodCounter,
+    ) -> ProgramResult {
+        if counter.owner != user_acc.key.to_bytes() {
+            return Err(CounterError::WrongCounterOwner.into());
+        }
+        Self::check_counter_pk(counter_acc, counter)?;
+        if counter.is_frozen() {
+            return Err(CounterError::Frozen.into());
+        }
+        Ok(())
+    }
+
+    /// Counter must be writable and live at the address derived from its owner and name
+    fn check_counter_pk(counter_acc: &AccountInfo, counter: &PodCounter) -> ProgramResult {
+        if !counter_acc.is_writable {
+            return Err(CounterError::CounterNotWritable.into());
+        }
+        if !Counter::check_counter_pk(&counter.owner(), counter.name(), counter_acc.key) {
+            return Err(CounterError::WrongCounterPDA.into());
+        }
+        Ok(())
+    }
+
+    /// User must be listed in an access page of its bucket for allowlist and must not be listed in any for denylist.
+    /// `access_page_accs` start with the first page of the bucket, which is empty when not created yet.
+    /// Denylist needs every other page of the bucket in order, allowlist any of them listing the user
+    fn check_access(
+        user_acc: &AccountInfo,
+        access_page_accs: &[AccountInfo],
+        access_mode: AccessMode,
+    ) -> ProgramResult {
+        if access_mode == AccessMode::Off {
+            return Ok(());
+        }
+        let (first_page_acc, other_page_accs) =
+            access_page_accs.split_first().ok_or(CounterError::AccessPageRequired)?;
+        if !AccessPage::check_access_page_pk(user_acc.key, 0, first_page_acc.key) {
+            return Err(CounterError::WrongAccessPagePDA.into());
+        }
+        let (mut listed, pages) = if first_page_acc.data_is_empty() {
+            (false, 0)
+        } else {
+            let access_page_data = first_page_acc.try_borrow_data()?;
+            let access_page = PodAccessPage::load(&access_page_data)?;
+            (access_page.contains(user_acc.key), access_page.pages)
+        };
+        let denylist = access_mode == AccessMode::Denylist;
+        if denylist && other_page_accs.len() != pages.saturating_sub(1) as usize {
+            return Err(CounterError::AccessPagesMissing.into());
+        }
+        for (position, access_page_acc) in other_page_accs.iter().enumerate() {
+            let access_page_data = access_page_acc.try_borrow_data()?;
+            let access_page = PodAccessPage::load(&access_page_data)?;
+            if !AccessPage::check_access_page_pk(user_acc.key, access_page.index, access_page_acc.key)
+                || (denylist && access_page.index as usize != position + 1) {
+                return Err(CounterError::WrongAccessPagePDA.into());
+            }
+            listed |= access_page.contains(user_acc.key);
+        }
+        let allowed = match access_mode {
+            AccessMode::Off => true,
+            AccessMode::Allowlist => listed,
+            AccessMode::Denylist => !listed,
+        };
+        if !allowed {
+            return Err(CounterError::AccessDenied.into());
+        }
+        Ok(())
+    }
+
+    /// Signer must be the admin from settings or hold the role in the roles account
+    fn check_role(
+        signer_acc: &AccountInfo,
+        settings_acc: &AccountInfo,
+        roles_acc: Option<&AccountInfo>,
+        role: Role,
+    ) -> ProgramResult {
+        if !signer_acc.is_signer {
+            return Err(CounterError::AdminRequired.into());
+        }
+        if !Settings::check_settings_pk(settings_acc.key) {
+            return Err(CounterError::WrongSettingsPDA.into());
+        }
+        // Roles account is checked even for the admin, who may write to it next
+        if let Some(roles_acc) = roles_acc {
+            if !Roles::check_roles_pk(roles_acc.key) {
+                return Err(CounterError::WrongRolesPDA.into());
+            }
+        }
+        let settings_data = settings_acc.try_borrow_data()?;
+        let settings = PodSettings::load(&settings_data)?;
+        if settings.admin == signer_acc.key.to_bytes() {
+            return Ok(());
+        }
+        if let Some(roles_acc) = roles_acc {
+            if !roles_acc.data_is_empty() {
+                let roles_data = roles_acc.try_borrow_data()?;
+                if PodRoles::load(&roles_data)?.has_role(signer_acc.key, role) {
+                    return Ok(());
+                }
+            }
+        }
+        Err(CounterError::AdminRequired.into())
+    }
+
+    fn process_create(accounts: &[AccountInfo], name: String) -> ProgramResult {
+        log_verbose!("Processor:process_create name={:?}", name);
 
         let acc_iter = &mut accounts.iter();
+        let payer_acc = next_account_info(acc_iter)?;
         let user_acc = next_account_info(acc_iter)?;
         let counter_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let sys_acc = next_account_info(acc_iter)?;
 
         // precondition checks
+        if !payer_acc.is_signer {
+            return Err(CounterError::PayerSignatureRequired.into());
+        }
         if !user_acc.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+            return Err(CounterError::UserSignatureRequired.into());
+        }
+        if !Counter::check_name(&name) {
+            return Err(CounterError::CounterNameTooLong.into());
         }
-        if !counter_acc.is_writable
-            && !Counter::check_counter_pk(user_acc.key, counter_acc.key) {
+        if !Counter::check_counter_pk(user_acc.key, &name, counter_acc.key) {
             return Err(CounterError::WrongCounterPDA.into());
         }
+        if !counter_acc.data_is_empty() {
+            return Err(CounterError::CounterAlreadyExists.into());
+        }
+
+        let counter = Counter::new(*user_acc.key, &name, Clock::get()?.unix_timestamp);
 
-        let mut counter: Counter = Counter::try_from_slice(&counter_acc.data.borrow())?;
+        let space = Counter::LEN;
+        let rent = Rent::from_account_info(rent_acc)?;
+        let rent_value = rent.minimum_balance(space);
 
-        counter.value = 0;
+        let create_counter_acc_instr = system_instruction::create_account_with_seed(
+            payer_acc.key,
+            counter_acc.key,
+            user_acc.key,
+            &Counter::seed(&name),
+            rent_value,
+            space as u64,
+            &id(),
+        );
+
+        invoke(
+            &create_counter_acc_instr,
+            &[payer_acc.clone(), counter_acc.clone(), user_acc.clone(), sys_acc.clone()],
+        )?;
 
         counter.serialize(&mut &mut counter_acc.data.borrow_mut()[..])?;
-        msg!("Processor:process_reset done");
+        log_verbose!("Processor:process_create done");
+        Ok(())
+    }
+
+    fn process_set_label(accounts: &[AccountInfo], label: String) -> ProgramResult {
+        log_verbose!("Processor:process_set_label label={:?}", label);
+
+        let acc_iter = &mut accounts.iter();
+        let user_acc = next_account_info(acc_iter)?;
+        let counter_acc = next_account_info(acc_iter)?;
+
+        // precondition checks
+        if !user_acc.is_signer {
+            return Err(CounterError::UserSignatureRequired.into());
+        }
+        if !Counter::check_label(&label) {
+            return Err(CounterError::CounterLabelTooLong.into());
+        }
+
+        let mut counter_data = counter_acc.try_borrow_mut_data()?;
+        let counter = PodCounter::load_mut(&mut counter_data)?;
+        Self::check_counter(user_acc, counter_acc, counter)?;
+
+        counter.set_label(&label);
+        counter.touch(Clock::get()?.unix_timestamp);
+
+        log_verbose!("Processor:process_set_label done");
         Ok(())
     }
 
@@ -100,22 +430,23 @@ impl Processor {
         inc_step: u32,
         dec_step: u32,
     ) -> ProgramResult {
-        msg!("Processor:process_upd_sett");
+        log_verbose!("Processor:process_upd_sett");
 
         let acc_iter = &mut accounts.iter();
         let admin_acc = next_account_info(acc_iter)?;
         let settings_acc = next_account_info(acc_iter)?;
         let rent_acc = next_account_info(acc_iter)?;
         let sys_acc = next_account_info(acc_iter)?;
+        let roles_acc = acc_iter.next();
 
         if !admin_acc.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+            return Err(CounterError::AdminRequired.into());
         }
         if !admin_acc.is_writable {
-            return Err(CounterError::AdminRequired.into());
+            return Err(CounterError::AdminNotWritable.into());
         }
         if !Settings::check_settings_pk(settings_acc.key) {
-            return Err(ProgramError::InvalidArgument);
+            return Err(CounterError::WrongSettingsPDA.into());
         }
 
         if settings_acc.data_is_empty() {
@@ -124,22 +455,430 @@ impl Processor {
                 settings_acc,
                 sys_acc,
                 rent_acc,
-                inc_step,
-                dec_step
             )?;
         }
 
-        let mut settings: Settings = Settings::try_from_slice(&settings_acc.data.borrow())?;
-        if settings.admin != *admin_acc.key && settings.admin != Pubkey::new(&[0_u8; 32]) {
-            return Err(CounterError::AdminRequired.into());
+        // Anybody initializes settings, then transfer of admin requires SuperAdmin
+        let current_admin = PodSettings::load(&settings_acc.try_borrow_data()?)?.admin;
+        if current_admin != [0_u8; 32] {
+            let role = if current_admin == admin.to_bytes() {
+                Role::SettingsManager
+            } else {
+                Role::SuperAdmin
+            };
+            Self::check_role(admin_acc, settings_acc, roles_acc, role)?;
+        }
+
+        let mut settings_data = settings_acc.try_borrow_mut_data()?;
+        let settings = PodSettings::load_mut(&mut settings_data)?;
+
+        settings.admin = admin.to_bytes();
+        settings.inc_step = inc_step.into();
+        settings.dec_step = dec_step.into();
+
+        log_verbose!("Processor:process_upd_sett done");
+        Ok(())
+    }
+
+    fn process_set_reset_period(accounts: &[AccountInfo], period: ResetPeriod) -> ProgramResult {
+        log_verbose!("Processor:process_set_reset_period period={:?}", period);
+
+        let acc_iter = &mut accounts.iter();
+        let admin_acc = next_account_info(acc_iter)?;
+        let settings_acc = next_account_info(acc_iter)?;
+        let roles_acc = acc_iter.next();
+
+        // precondition checks
+        Self::check_role(admin_acc, settings_acc, roles_acc, Role::SettingsManager)?;
+        if !period.is_valid() {
+            return Err(CounterError::WrongResetPeriod.into());
+        }
+
+        let mut settings_data = settings_acc.try_borrow_mut_data()?;
+        let settings = PodSettings::load_mut(&mut settings_data)?;
+
+        settings.set_reset_period(period);
+
+        log_verbose!("Processor:process_set_reset_period done");
+        Ok(())
+    }
+
+    fn process_moderation(accounts: &[AccountInfo], inst: CounterInstruction) -> ProgramResult {
+        log_verbose!("Processor:process_moderation inst={:?}", inst);
+
+        let acc_iter = &mut accounts.iter();
+        let admin_acc = next_account_info(acc_iter)?;
+        let settings_acc = next_account_info(acc_iter)?;
+        let counter_acc = next_account_info(acc_iter)?;
+        let roles_acc = acc_iter.next();
+
+        // precondition checks
+        Self::check_role(admin_acc, settings_acc, roles_acc, Role::Moderator)?;
+
+        let mut counter_data = counter_acc.try_borrow_mut_data()?;
+        let counter = PodCounter::load_mut(&mut counter_data)?;
+        Self::check_counter_pk(counter_acc, counter)?;
+
+        match inst {
+            CounterInstruction::Freeze => counter.set_frozen(true),
+            CounterInstruction::Thaw => counter.set_frozen(false),
+            CounterInstruction::AdminReset => counter.set_value(0),
+            _ => return Err(CounterError::InvalidInstruction.into()),
+        }
+        counter.touch(Clock::get()?.unix_timestamp);
+
+        log_verbose!("Processor:process_moderation done inst={:?}", inst);
+        Ok(())
+    }
+
+    fn process_set_access_mode(accounts: &[AccountInfo], mode: AccessMode) -> ProgramResult {
+        log_verbose!("Processor:process_set_access_mode mode={:?}", mode);
+
+        let acc_iter = &mut accounts.iter();
+        let admin_acc = next_account_info(acc_iter)?;
+        let settings_acc = next_account_info(acc_iter)?;
+        let roles_acc = acc_iter.next();
+
+        // precondition checks
+        Self::check_role(admin_acc, settings_acc, roles_acc, Role::SettingsManager)?;
+
+        let mut settings_data = settings_acc.try_borrow_mut_data()?;
+        let settings = PodSettings::load_mut(&mut settings_data)?;
+
+        settings.set_access_mode(mode);
+
+        log_verbose!("Processor:process_set_access_mode done");
+        Ok(())
+    }
+
+    fn process_add_access(accounts: &[AccountInfo], user: Pubkey) -> ProgramResult {
+        log_verbose!("Processor:process_add_access user={:?}", user);
+
+        let acc_iter = &mut accounts.iter();
+        let admin_acc = next_account_info(acc_iter)?;
+        let settings_acc = next_account_info(acc_iter)?;
+        let first_page_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let sys_acc = next_account_info(acc_iter)?;
+        let roles_acc = acc_iter.next();
+
+        // precondition checks
+        Self::check_role(admin_acc, settings_acc, roles_acc, Role::Moderator)?;
+        if !AccessPage::check_access_page_pk(&user, 0, first_page_acc.key) {
+            return Err(CounterError::WrongAccessPagePDA.into());
+        }
+
+        let bucket = AccessPage::bucket(&user);
+        if first_page_acc.data_is_empty() {
+            Self::create_access_page_account(admin_acc, first_page_acc, sys_acc, rent_acc, bucket, 0)?;
+        }
+        let pages = PodAccessPage::load(&first_page_acc.try_borrow_data()?)?.pages;
+        let (page_accs, next_page_acc) = Self::access_pages(&user, first_page_acc, acc_iter.as_slice(), pages)?;
+
+        // Listed key is not added twice, it goes to the first page with a free place
+        let mut free_page_acc = None;
+        for page_acc in &page_accs {
+            let access_page_data = page_acc.try_borrow_data()?;
+            let access_page = PodAccessPage::load(&access_page_data)?;
+            if access_page.contains(&user) {
+                log_verbose!("Processor:process_add_access done, already listed");
+                return Ok(());
+            }
+            if free_page_acc.is_none() && !access_page.is_full() {
+                free_page_acc = Some(*page_acc);
+            }
+        }
+        let page_acc = match free_page_acc {
+            Some(page_acc) => page_acc,
+            None => {
+                if pages >= MAX_ACCESS_PAGES {
+                    return Err(CounterError::AccessPageFull.into());
+                }
+                let next_page_acc = next_page_acc.ok_or(CounterError::AccessPagesMissing)?;
+                if !AccessPage::check_access_page_pk(&user, pages, next_page_acc.key) {
+                    return Err(CounterError::WrongAccessPagePDA.into());
+                }
+                Self::create_access_page_account(admin_acc, next_page_acc, sys_acc, rent_acc, bucket, pages)?;
+                PodAccessPage::load_mut(&mut first_page_acc.try_borrow_mut_data()?)?.pages = pages + 1;
+                next_page_acc
+            }
+        };
+
+        let mut access_page_data = page_acc.try_borrow_mut_data()?;
+        PodAccessPage::load_mut(&mut access_page_data)?.add(&user);
+
+        log_verbose!("Processor:process_add_access done");
+        Ok(())
+    }
+
+    fn process_remove_access(accounts: &[AccountInfo], user: Pubkey) -> ProgramResult {
+        log_verbose!("Processor:process_remove_access user={:?}", user);
+
+        let acc_iter = &mut accounts.iter();
+        let admin_acc = next_account_info(acc_iter)?;
+        let settings_acc = next_account_info(acc_iter)?;
+        let first_page_acc = next_account_info(acc_iter)?;
+        let roles_acc = acc_iter.next();
+
+        // precondition checks
+        Self::check_role(admin_acc, settings_acc, roles_acc, Role::Moderator)?;
+        if !AccessPage::check_access_page_pk(&user, 0, first_page_acc.key) {
+            return Err(CounterError::WrongAccessPagePDA.into());
+        }
+
+        let pages = PodAccessPage::load(&first_page_acc.try_borrow_data()?)?.pages;
+        let (page_accs, _) = Self::access_pages(&user, first_page_acc, acc_iter.as_slice(), pages)?;
+        for page_acc in page_accs {
+            let mut access_page_data = page_acc.try_borrow_mut_data()?;
+            PodAccessPage::load_mut(&mut access_page_data)?.remove(&user);
+        }
+
+        log_verbose!("Processor:process_remove_access done");
+        Ok(())
+    }
+
+    /// All `pages` pages of the user bucket: the first page and the other pages in order from `other_accs`,
+    /// followed by the account after them if any
+    fn access_pages<'a, 'b>(
+        user: &Pubkey,
+        first_page_acc: &'a AccountInfo<'b>,
+        other_accs: &'a [AccountInfo<'b>],
+        pages: u8,
+    ) -> Result<(Vec<&'a AccountInfo<'b>>, Option<&'a AccountInfo<'b>>), ProgramError> {
+        let others = (pages as usize).saturating_sub(1);
+        if other_accs.len() < others {
+            return Err(CounterError::AccessPagesMissing.into());
+        }
+        let mut page_accs = vec![first_page_acc];
+        for (index, page_acc) in (1..pages).zip(&other_accs[..others]) {
+            if !AccessPage::check_access_page_pk(user, index, page_acc.key) {
+                return Err(CounterError::WrongAccessPagePDA.into());
+            }
+            page_accs.push(page_acc);
+        }
+        Ok((page_accs, other_accs.get(others)))
+    }
+
+    fn process_create_session(
+        accounts: &[AccountInfo],
+        session_key: Pubkey,
+        expiry: i64,
+        ops: Vec<OperationKind>,
+    ) -> ProgramResult {
+        log_verbose!("Processor:process_create_session session_key={:?} expiry={}", session_key, expiry);
+
+        let acc_iter = &mut accounts.iter();
+        let user_acc = next_account_info(acc_iter)?;
+        let session_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let sys_acc = next_account_info(acc_iter)?;
+
+        // precondition checks
+        if !user_acc.is_signer {
+            return Err(CounterError::UserSignatureRequired.into());
+        }
+        if !Session::check_session_pk(user_acc.key, &session_key, session_acc.key) {
+            return Err(CounterError::WrongSessionPDA.into());
+        }
+        if ops.is_empty() {
+            return Err(CounterError::EmptySessionOps.into());
+        }
+        if expiry <= Clock::get()?.unix_timestamp {
+            return Err(CounterError::SessionExpiryNotInFuture.into());
+        }
+
+        if session_acc.data_is_empty() {
+            Self::create_session_account(user_acc, session_acc, sys_acc, rent_acc, &session_key)?;
+        }
+
+        let mut session_data = session_acc.try_borrow_mut_data()?;
+        let session = PodSession::load_mut(&mut session_data)?;
+
+        session.owner = user_acc.key.to_bytes();
+        session.session_key = session_key.to_bytes();
+        session.expiry = expiry.into();
+        session.allowed_ops = Session::pack_ops(&ops);
+
+        log_verbose!("Processor:process_create_session done");
+        Ok(())
+    }
+
+    fn process_revoke_session(accounts: &[AccountInfo], session_key: Pubkey) -> ProgramResult {
+        log_verbose!("Processor:process_revoke_session session_key={:?}", session_key);
+
+        let acc_iter = &mut accounts.iter();
+        let user_acc = next_account_info(acc_iter)?;
+        let session_acc = next_account_info(acc_iter)?;
+
+        // precondition checks
+        if !user_acc.is_signer {
+            return Err(CounterError::UserSignatureRequired.into());
+        }
+        if !Session::check_session_pk(user_acc.key, &session_key, session_acc.key) {
+            return Err(CounterError::WrongSessionPDA.into());
+        }
+
+        // Account without lamports is deleted at the end of the transaction
+        let user_lamports = user_acc.lamports().checked_add(session_acc.lamports()).ok_or(CounterError::LamportsOverflow)?;
+        **session_acc.try_borrow_mut_lamports()? = 0;
+        **user_acc.try_borrow_mut_lamports()? = user_lamports;
+        session_acc.try_borrow_mut_data()?.fill(0);
+
+        log_verbose!("Processor:process_revoke_session done");
+        Ok(())
+    }
+
+    fn create_session_account<'a>(
+        user_acc: &AccountInfo<'a>,
+        session_acc: &AccountInfo<'a>,
+        sys_acc: &AccountInfo<'a>,
+        rent_acc: &AccountInfo<'a>,
+        session_key: &Pubkey,
+    ) -> ProgramResult {
+        log_verbose!("Creating session account");
+        let space = Session::LEN;
+        let rent = Rent::from_account_info(rent_acc)?;
+        let rent_value = rent.minimum_balance(space);
+        let (session_pk, bump) = Session::get_session_pk_with_bump(user_acc.key, session_key);
+        let bump_ref = &[bump];
+        let signer_seeds: &[&[_]] = &Session::create_signer_seed(user_acc.key, session_key, bump_ref);
+
+        let create_session_acc_instr = system_instruction::create_account(
+            user_acc.key,
+            &session_pk,
+            rent_value,
+            space as u64,
+            &id(),
+        );
+
+        invoke_signed(
+            &create_session_acc_instr,
+            &[user_acc.clone(), session_acc.clone(), sys_acc.clone()],
+            &[signer_seeds],
+        )?;
+        log_verbose!("Creating session account done");
+        Ok(())
+    }
+
+    fn process_grant_role(accounts: &[AccountInfo], user: Pubkey, role: Role) -> ProgramResult {
+        log_verbose!("Processor:process_grant_role user={:?} role={:?}", user, role);
+
+        let acc_iter = &mut accounts.iter();
+        let admin_acc = next_account_info(acc_iter)?;
+        let settings_acc = next_account_info(acc_iter)?;
+        let roles_acc = next_account_info(acc_iter)?;
+        let rent_acc = next_account_info(acc_iter)?;
+        let sys_acc = next_account_info(acc_iter)?;
+
+        // precondition checks
+        Self::check_role(admin_acc, settings_acc, Some(roles_acc), Role::SuperAdmin)?;
+
+        if roles_acc.data_is_empty() {
+            Self::create_roles_account(admin_acc, roles_acc, sys_acc, rent_acc)?;
         }
 
-        settings.admin = admin;
-        settings.inc_step = inc_step;
-        settings.dec_step = dec_step;
+        let mut roles_data = roles_acc.try_borrow_mut_data()?;
+        let roles = PodRoles::load_mut(&mut roles_data)?;
+        if !roles.grant(&user, role) {
+            return Err(CounterError::RolesFull.into());
+        }
 
-        settings.serialize(&mut &mut settings_acc.data.borrow_mut()[..])?;
-        msg!("Processor:process_upd_sett done");
+        log_verbose!("Processor:process_grant_role done");
+        Ok(())
+    }
+
+    fn process_revoke_role(accounts: &[AccountInfo], user: Pubkey, role: Role) -> ProgramResult {
+        log_verbose!("Processor:process_revoke_role user={:?} role={:?}", user, role);
+
+        let acc_iter = &mut accounts.iter();
+        let admin_acc = next_account_info(acc_iter)?;
+        let settings_acc = next_account_info(acc_iter)?;
+        let roles_acc = next_account_info(acc_iter)?;
+
+        // precondition checks
+        Self::check_role(admin_acc, settings_acc, Some(roles_acc), Role::SuperAdmin)?;
+
+        let mut roles_data = roles_acc.try_borrow_mut_data()?;
+        let roles = PodRoles::load_mut(&mut roles_data)?;
+
+        roles.revoke(&user, role);
+
+        log_verbose!("Processor:process_revoke_role done");
+        Ok(())
+    }
+
+    fn create_roles_account<'a>(
+        admin_acc: &AccountInfo<'a>,
+        roles_acc: &AccountInfo<'a>,
+        sys_acc: &AccountInfo<'a>,
+        rent_acc: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        log_verbose!("Creating roles account");
+        let space = Roles::LEN;
+        let rent = Rent::from_account_info(rent_acc)?;
+        let rent_value = rent.minimum_balance(space);
+        let (roles_pk, bump) = Roles::get_roles_pk_with_bump();
+        let bump_ref = &[bump];
+        let signer_seeds: &[&[_]] = &Roles::create_signer_seed(bump_ref);
+
+        let create_roles_acc_instr = system_instruction::create_account(
+            admin_acc.key,
+            &roles_pk,
+            rent_value,
+            space as u64,
+            &id(),
+        );
+
+        invoke_signed(
+            &create_roles_acc_instr,
+            &[admin_acc.clone(), roles_acc.clone(), sys_acc.clone()],
+            &[signer_seeds],
+        )?;
+        log_verbose!("Creating roles account done");
+        Ok(())
+    }
+
+    fn create_access_page_account<'a>(
+        admin_acc: &AccountInfo<'a>,
+        access_page_acc: &AccountInfo<'a>,
+        sys_acc: &AccountInfo<'a>,
+        rent_acc: &AccountInfo<'a>,
+        bucket: u8,
+        index: u8,
+    ) -> ProgramResult {
+        log_verbose!("Creating access page account bucket={} index={}", bucket, index);
+        let space = AccessPage::LEN;
+        let rent = Rent::from_account_info(rent_acc)?;
+        let rent_value = rent.minimum_balance(space);
+        let (access_page_pk, bump) = AccessPage::get_access_page_pk_with_bump(bucket, index);
+        let bucket_ref = &[bucket];
+        let index_ref = &[index];
+        let bump_ref = &[bump];
+        let signer_seeds: &[&[_]] = &AccessPage::create_signer_seed(bucket_ref, index_ref, bump_ref);
+
+        let create_access_page_acc_instr = system_instruction::create_account(
+            admin_acc.key,
+            &access_page_pk,
+            rent_value,
+            space as u64,
+            &id(),
+        );
+
+        invoke_signed(
+            &create_access_page_acc_instr,
+            &[admin_acc.clone(), access_page_acc.clone(), sys_acc.clone()],
+            &[signer_seeds],
+        )?;
+
+        let mut access_page_data = access_page_acc.try_borrow_mut_data()?;
+        let access_page = PodAccessPage::load_mut(&mut access_page_data)?;
+        access_page.bucket = bucket;
+        access_page.index = index;
+        // Only the first page counts the pages of the bucket
+        if index == 0 {
+            access_page.pages = 1;
+        }
+        log_verbose!("Creating access page account done");
         Ok(())
     }
 
@@ -148,13 +887,9 @@ impl Processor {
         settings_acc: &AccountInfo<'a>,
         sys_acc: &AccountInfo<'a>,
         rent_acc: &AccountInfo<'a>,
-        inc_step: u32,
-        dec_step: u32,
     ) -> ProgramResult {
-        msg!("Creating settings account");
-        let settings = Settings { admin: admin_acc.key.clone(), inc_step, dec_step };
-
-        let space = settings.try_to_vec()?.len();
+        log_verbose!("Creating settings account");
+        let space = Settings::LEN;
         let rent = Rent::from_account_info(rent_acc)?;
         let rent_value = rent.minimum_balance(space);
         let (settings_pk, bump) = Settings::get_settings_pk_with_bump();
@@ -174,7 +909,7 @@ impl Processor {
             &[admin_acc.clone(), settings_acc.clone(), sys_acc.clone()],
             &[signer_seeds],
         )?;
-        msg!("Creating settings account done");
+        log_verbose!("Creating settings account done");
         Ok(())
     }
 }
@@ -1,23 +1,110 @@
 use solana_program::pubkey::{Pubkey, PubkeyError};
+use solana_program::program_error::ProgramError;
+use solana_program::clock::Clock;
+use solana_program::hash::hashv;
 use borsh::BorshSerialize;
 use borsh::BorshDeserialize;
+use borsh::BorshSchema;
+use bytemuck::{Pod, Zeroable};
 use crate::id;
-use crate::{COUNTER_SEED, SETTINGS_SEED};
+use crate::pod::{str_from_bytes, str_to_bytes, PodI64, PodU32, PodU64};
+use crate::{ACCESS_PAGE_CAPACITY, ACCESS_SEED, COUNTER_SEED, MAX_COUNTER_LABEL_LEN, MAX_COUNTER_NAME_LEN, MAX_ROLE_MEMBERS, ROLES_SEED, SESSION_SEED, SETTINGS_SEED};
+use crate::instruction::OperationKind;
+use crate::error::CounterError;
 
-/// New counter for every user
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+/// Named counter, a user can have any number of them.
+/// Borsh layout is fixed size and equal to `PodCounter`
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Debug, PartialEq)]
 pub struct Counter {
     /// Value of a counter
     pub value: i64,
+
+    /// Owner of a counter
+    pub owner: Pubkey,
+
+    /// Unix timestamp of counter creation
+    pub created_at: i64,
+
+    /// Unix timestamp of the last counter update
+    pub updated_at: i64,
+
+    /// Number of `Inc`, `Dec` and `Reset` applied to a counter, labels and moderation are not counted
+    pub op_count: u64,
+
+    /// Id of the reset period of the last operation, see `ResetPeriod`
+    pub period_id: u64,
+
+    /// Nonce the next relayed operation must be signed with, see `CounterInstruction::Relayed`
+    pub relay_nonce: u64,
+
+    /// Frozen by admin, the owner can not change a frozen counter
+    pub frozen: bool,
+
+    /// Length of `label` in bytes
+    pub label_len: u8,
+
+    /// UTF-8 label, zero padded. Fixed size allows changing the label in place
+    pub label: [u8; MAX_COUNTER_LABEL_LEN],
+
+    /// Length of `name` in bytes
+    pub name_len: u8,
+
+    /// Name of a counter, zero padded, part of the address seed. Empty for the default counter
+    pub name: [u8; MAX_COUNTER_NAME_LEN],
 }
 
 impl Counter {
-    pub fn generate_counter_pk(user: &Pubkey) -> Result<Pubkey, PubkeyError> {
-        Pubkey::create_with_seed(user, COUNTER_SEED, &id())
+    /// Size of a counter account
+    pub const LEN: usize = std::mem::size_of::<PodCounter>();
+
+    /// Offset of `owner` in serialized counter, used for filtering counters by owner
+    pub const OWNER_OFFSET: usize = 8;
+
+    /// Name must be checked with `check_name` before
+    pub fn new(owner: Pubkey, name: &str, now: i64) -> Self {
+        let (name, name_len) = str_to_bytes(name);
+        Counter {
+            value: 0,
+            owner,
+            created_at: now,
+            updated_at: now,
+            op_count: 0,
+            period_id: 0,
+            relay_nonce: 0,
+            frozen: false,
+            label_len: 0,
+            label: [0; MAX_COUNTER_LABEL_LEN],
+            name_len,
+            name,
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        str_from_bytes(&self.label, self.label_len)
+    }
+
+    pub fn name(&self) -> &str {
+        str_from_bytes(&self.name, self.name_len)
+    }
+
+    pub fn check_label(label: &str) -> bool {
+        label.len() <= MAX_COUNTER_LABEL_LEN
+    }
+
+    pub fn seed(name: &str) -> String {
+        format!("{}{}", COUNTER_SEED, name)
+    }
+
+    pub fn check_name(name: &str) -> bool {
+        name.len() <= MAX_COUNTER_NAME_LEN
     }
 
-    pub fn check_counter_pk(user: &Pubkey, transaction: &Pubkey) -> bool {
-        let counter = Self::generate_counter_pk(user);
+    pub fn generate_counter_pk(user: &Pubkey, name: &str) -> Result<Pubkey, PubkeyError> {
+        Pubkey::create_with_seed(user, &Self::seed(name), &id())
+    }
+
+    pub fn check_counter_pk(user: &Pubkey, name: &str, transaction: &Pubkey) -> bool {
+        let counter = Self::generate_counter_pk(user, name);
         if let Ok(pk) = counter {
             transaction.to_bytes() == pk.to_bytes()
         } else {
@@ -26,20 +113,127 @@ impl Counter {
     }
 }
 
+/// Zero-copy layout of `Counter`, accessed in place in account data
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct PodCounter {
+    pub value: PodI64,
+    pub owner: [u8; 32],
+    pub created_at: PodI64,
+    pub updated_at: PodI64,
+    pub op_count: PodU64,
+    pub period_id: PodU64,
+    pub relay_nonce: PodU64,
+    pub frozen: u8,
+    pub label_len: u8,
+    pub label: [u8; MAX_COUNTER_LABEL_LEN],
+    pub name_len: u8,
+    pub name: [u8; MAX_COUNTER_NAME_LEN],
+}
+
+impl PodCounter {
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        #[cfg(feature = "borsh-accounts")]
+        crate::pod::borsh_read::<Counter>(data).map_err(|_| CounterError::InvalidCounterData)?;
+        bytemuck::try_from_bytes(data).map_err(|_| CounterError::InvalidCounterData.into())
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        #[cfg(feature = "borsh-accounts")]
+        crate::pod::borsh_round_trip::<Counter>(data).map_err(|_| CounterError::InvalidCounterData)?;
+        bytemuck::try_from_bytes_mut(data).map_err(|_| CounterError::InvalidCounterData.into())
+    }
+
+    pub fn value(&self) -> i64 {
+        self.value.into()
+    }
+
+    pub fn set_value(&mut self, value: i64) {
+        self.value = value.into();
+    }
+
+    pub fn owner(&self) -> Pubkey {
+        Pubkey::new_from_array(self.owner)
+    }
+
+    pub fn name(&self) -> &str {
+        str_from_bytes(&self.name, self.name_len)
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen != 0
+    }
+
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen as u8;
+    }
+
+    pub fn relay_nonce(&self) -> u64 {
+        self.relay_nonce.into()
+    }
+
+    /// Relayed operation with the current nonce is accepted, the next one must use the next nonce
+    pub fn advance_relay_nonce(&mut self) {
+        self.relay_nonce = self.relay_nonce().wrapping_add(1).into();
+    }
+
+    /// Reset value if `period_id` starts a new reset period
+    pub fn roll_period(&mut self, period_id: u64) {
+        if u64::from(self.period_id) != period_id {
+            self.period_id = period_id.into();
+            self.set_value(0);
+        }
+    }
+
+    /// Counter was updated at `now`
+    pub fn touch(&mut self, now: i64) {
+        self.updated_at = now.into();
+    }
+
+    /// Register an applied `Inc`, `Dec` or `Reset`
+    pub fn count_operation(&mut self) {
+        self.op_count = u64::from(self.op_count).saturating_add(1).into();
+    }
+
+    /// Label must be checked with `Counter::check_label` before
+    pub fn set_label(&mut self, label: &str) {
+        let (label, label_len) = str_to_bytes(label);
+        self.label = label;
+        self.label_len = label_len;
+    }
+}
+
 #[cfg(test)]
 mod counter_test {
     use borsh::BorshSerialize;
     use borsh::BorshDeserialize;
-    use crate::state::Counter;
+    use crate::state::{Counter, PodCounter};
     use solana_program::pubkey::Pubkey;
     use std::str::FromStr;
 
-    const COUNTER: Counter = Counter { value: -777 };
-    const BINARY_COUNTER: [u8; 8] = [247, 252, 255, 255, 255, 255, 255, 255];
+    const PK: Pubkey = Pubkey::new_from_array([3_u8; 32]);
+    const BINARY_COUNTER: [u8; 140] = [247, 252, 255, 255, 255, 255, 255, 255, 3, 3, 3, 3, 3, 3, 3,
+        3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 64, 163, 146, 97,
+        0, 0, 0, 0, 164, 163, 146, 97, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0,
+        5, 0, 0, 0, 0, 0, 0, 0, 0, 3, 108, 98, 108, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    fn counter() -> Counter {
+        let mut counter = Counter::new(PK, "abc", 1637000000);
+        counter.value = -777;
+        counter.updated_at = 1637000100;
+        counter.op_count = 3;
+        counter.period_id = 2;
+        counter.relay_nonce = 5;
+        counter.label_len = 3;
+        counter.label[..3].copy_from_slice(b"lbl");
+        counter
+    }
 
     #[test]
     fn when_serialization_counter_expect_ok() {
-        let serialized_counter = COUNTER.try_to_vec().unwrap();
+        let serialized_counter = counter().try_to_vec().unwrap();
 
         assert_eq!(serialized_counter, BINARY_COUNTER)
     }
@@ -48,25 +242,127 @@ mod counter_test {
     fn when_deserialization_counter_expect_ok() {
         let deserialized_counter = Counter::try_from_slice(&BINARY_COUNTER).unwrap();
 
-        assert_eq!(deserialized_counter, COUNTER)
+        assert_eq!(deserialized_counter, counter())
+    }
+
+    #[test]
+    fn when_owner_offset_expect_owner_bytes() {
+        let serialized_counter = counter().try_to_vec().unwrap();
+        let owner_bytes = &serialized_counter[Counter::OWNER_OFFSET..Counter::OWNER_OFFSET + 32];
+
+        assert_eq!(owner_bytes, PK.as_ref())
+    }
+
+    #[test]
+    fn when_new_counter_expect_empty_metadata() {
+        let counter = Counter::new(PK, "abc", 1637000000);
+
+        assert_eq!(counter.value, 0);
+        assert_eq!(counter.name(), "abc");
+        assert_eq!(counter.created_at, 1637000000);
+        assert_eq!(counter.updated_at, 1637000000);
+        assert_eq!(counter.op_count, 0);
+        assert_eq!(counter.label(), "");
+    }
+
+    #[test]
+    fn when_check_label_expect_max_len() {
+        assert_eq!(Counter::check_label(&"a".repeat(32)), true);
+        assert_eq!(Counter::check_label(&"a".repeat(33)), false);
+    }
+
+    #[test]
+    fn when_load_pod_counter_expect_same_fields() {
+        let pod_counter = PodCounter::load(&BINARY_COUNTER).unwrap();
+
+        assert_eq!(pod_counter.value(), -777);
+        assert_eq!(pod_counter.owner(), PK);
+        assert_eq!(pod_counter.name(), "abc");
+        assert_eq!(Counter::LEN, BINARY_COUNTER.len());
+    }
+
+    #[test]
+    fn when_set_frozen_expect_borsh_frozen() {
+        let mut data = BINARY_COUNTER;
+        let pod_counter = PodCounter::load_mut(&mut data).unwrap();
+        assert_eq!(pod_counter.is_frozen(), false);
+
+        pod_counter.set_frozen(true);
+        assert_eq!(pod_counter.is_frozen(), true);
+        assert_eq!(Counter::try_from_slice(&data).unwrap().frozen, true);
+    }
+
+    #[test]
+    fn when_roll_same_period_expect_value_kept() {
+        let mut data = BINARY_COUNTER;
+        let pod_counter = PodCounter::load_mut(&mut data).unwrap();
+        pod_counter.roll_period(2);
+
+        assert_eq!(pod_counter.value(), -777);
+    }
+
+    #[test]
+    fn when_roll_new_period_expect_value_reset() {
+        let mut data = BINARY_COUNTER;
+        let pod_counter = PodCounter::load_mut(&mut data).unwrap();
+        pod_counter.roll_period(3);
+
+        assert_eq!(pod_counter.value(), 0);
+        assert_eq!(u64::from(pod_counter.period_id), 3);
+    }
+
+    #[test]
+    fn when_load_pod_counter_wrong_size_expect_err() {
+        let check = PodCounter::load(&BINARY_COUNTER[1..]).is_err();
+
+        assert_eq!(check, true)
+    }
+
+    #[test]
+    fn when_pod_counter_changed_expect_borsh_counter_changed() {
+        let mut data = BINARY_COUNTER;
+        let pod_counter = PodCounter::load_mut(&mut data).unwrap();
+        pod_counter.set_value(5);
+        pod_counter.set_label("счетчик");
+        pod_counter.touch(1637000200);
+        pod_counter.count_operation();
+
+        let counter = Counter::try_from_slice(&data).unwrap();
+
+        assert_eq!(counter.value, 5);
+        assert_eq!(counter.label(), "счетчик");
+        assert_eq!(counter.created_at, 1637000000);
+        assert_eq!(counter.updated_at, 1637000200);
+        assert_eq!(counter.op_count, 4);
     }
 
     #[test]
     fn when_generate_counter_pk_expect_equals() {
         let user_pk = Pubkey::from_str("4UPHhQxnJrsmLE5w1qLencgCCttYiPswdaRRpQ9xwG5Z").unwrap();
-        let generated_pk = Counter::generate_counter_pk(&user_pk).unwrap();
+        let generated_pk = Counter::generate_counter_pk(&user_pk, "").unwrap();
 
         let counter_pk = Pubkey::from_str("Ffav6rApgVYVogddJrLsccYwveUZCS8KJoM5TLW8T6CH").unwrap();
 
         assert_eq!(generated_pk, counter_pk)
     }
 
+    #[test]
+    fn when_generate_named_counter_pk_expect_different() {
+        let user_pk = Pubkey::from_str("4UPHhQxnJrsmLE5w1qLencgCCttYiPswdaRRpQ9xwG5Z").unwrap();
+        let default_pk = Counter::generate_counter_pk(&user_pk, "").unwrap();
+        let first_pk = Counter::generate_counter_pk(&user_pk, "first").unwrap();
+        let second_pk = Counter::generate_counter_pk(&user_pk, "second").unwrap();
+
+        assert_ne!(first_pk, default_pk);
+        assert_ne!(first_pk, second_pk);
+    }
+
     #[test]
     fn when_check_counter_pk_expect_transaction_pk_true() {
         let user_pk = Pubkey::from_str("4UPHhQxnJrsmLE5w1qLencgCCttYiPswdaRRpQ9xwG5Z").unwrap();
         let counter_pk = Pubkey::from_str("Ffav6rApgVYVogddJrLsccYwveUZCS8KJoM5TLW8T6CH").unwrap();
 
-        let check = Counter::check_counter_pk(&user_pk, &counter_pk);
+        let check = Counter::check_counter_pk(&user_pk, "", &counter_pk);
 
         assert_eq!(check, true)
     }
@@ -76,14 +372,32 @@ mod counter_test {
         let user_pk = Pubkey::from_str("4UPHhQxnJrsmLE5w1qLencgCCttYiPswdaRRpQ9xwG5Z").unwrap();
         let wrong_counter_pk = Pubkey::from_str("2wY7hT8TJhFpQqQJ5PGSed76vEgGNeQ11y1PvPsLUcS4").unwrap(); // admin pk
 
-        let check = Counter::check_counter_pk(&user_pk, &wrong_counter_pk);
+        let check = Counter::check_counter_pk(&user_pk, "", &wrong_counter_pk);
+
+        assert_eq!(check, false)
+    }
+
+    #[test]
+    fn when_check_counter_pk_with_other_name_expect_false() {
+        let user_pk = Pubkey::from_str("4UPHhQxnJrsmLE5w1qLencgCCttYiPswdaRRpQ9xwG5Z").unwrap();
+        let counter_pk = Pubkey::from_str("Ffav6rApgVYVogddJrLsccYwveUZCS8KJoM5TLW8T6CH").unwrap();
+
+        let check = Counter::check_counter_pk(&user_pk, "first", &counter_pk);
 
         assert_eq!(check, false)
     }
+
+    #[test]
+    fn when_check_name_expect_max_len() {
+        assert_eq!(Counter::check_name(""), true);
+        assert_eq!(Counter::check_name(&"a".repeat(25)), true);
+        assert_eq!(Counter::check_name(&"a".repeat(26)), false);
+    }
 }
 
-/// Settings for every counter
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+/// Settings for every counter.
+/// Borsh layout is equal to `PodSettings`
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Debug, PartialEq)]
 pub struct Settings {
     /// Account admin
     pub admin: Pubkey,
@@ -93,9 +407,28 @@ pub struct Settings {
 
     /// Decrement step
     pub dec_step: u32,
+
+    /// Kind of `ResetPeriod`, 0 when counters are never reset
+    pub reset_period_kind: u8,
+
+    /// Length of `ResetPeriod`
+    pub reset_period: u64,
+
+    /// `AccessMode` of counter operations
+    pub access_mode: u8,
 }
 
 impl Settings {
+    /// Size of a settings account
+    pub const LEN: usize = std::mem::size_of::<PodSettings>();
+
+    pub fn reset_period(&self) -> Option<ResetPeriod> {
+        ResetPeriod::unpack(self.reset_period_kind, self.reset_period)
+    }
+
+    pub fn access_mode(&self) -> Option<AccessMode> {
+        AccessMode::unpack(self.access_mode)
+    }
 
     pub fn create_signer_seed(bump: &[u8]) -> [&[u8]; 2] {
         [SETTINGS_SEED.as_bytes(), bump]
@@ -111,18 +444,237 @@ impl Settings {
     }
 }
 
+/// Zero-copy layout of `Settings`, accessed in place in account data
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct PodSettings {
+    pub admin: [u8; 32],
+    pub inc_step: PodU32,
+    pub dec_step: PodU32,
+    pub reset_period_kind: u8,
+    pub reset_period: PodU64,
+    pub access_mode: u8,
+}
+
+impl PodSettings {
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        #[cfg(feature = "borsh-accounts")]
+        crate::pod::borsh_read::<Settings>(data).map_err(|_| CounterError::InvalidSettingsData)?;
+        bytemuck::try_from_bytes(data).map_err(|_| CounterError::InvalidSettingsData.into())
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        #[cfg(feature = "borsh-accounts")]
+        crate::pod::borsh_round_trip::<Settings>(data).map_err(|_| CounterError::InvalidSettingsData)?;
+        bytemuck::try_from_bytes_mut(data).map_err(|_| CounterError::InvalidSettingsData.into())
+    }
+
+    pub fn admin(&self) -> Pubkey {
+        Pubkey::new_from_array(self.admin)
+    }
+
+    pub fn inc_step(&self) -> u32 {
+        self.inc_step.into()
+    }
+
+    pub fn dec_step(&self) -> u32 {
+        self.dec_step.into()
+    }
+
+    pub fn reset_period(&self) -> Result<ResetPeriod, ProgramError> {
+        ResetPeriod::unpack(self.reset_period_kind, self.reset_period.into())
+            .ok_or_else(|| CounterError::InvalidSettingsData.into())
+    }
+
+    pub fn set_reset_period(&mut self, period: ResetPeriod) {
+        let (kind, length) = period.pack();
+        self.reset_period_kind = kind;
+        self.reset_period = length.into();
+    }
+
+    pub fn access_mode(&self) -> Result<AccessMode, ProgramError> {
+        AccessMode::unpack(self.access_mode).ok_or_else(|| CounterError::InvalidSettingsData.into())
+    }
+
+    pub fn set_access_mode(&mut self, mode: AccessMode) {
+        self.access_mode = mode as u8;
+    }
+}
+
+/// Period after which counter values are reset to zero.
+/// Counters are reset lazily by the first operation in a new period
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Clone, Copy, Debug, PartialEq)]
+pub enum ResetPeriod {
+    /// Counters are never reset automatically
+    Never,
+    /// Every given number of slots
+    Slots(u64),
+    /// Every given number of seconds of Unix time
+    UnixTime(u64),
+    /// Every given number of epochs
+    Epochs(u64),
+}
+
+impl ResetPeriod {
+    /// Period length must be positive
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            ResetPeriod::Never => true,
+            ResetPeriod::Slots(length)
+            | ResetPeriod::UnixTime(length)
+            | ResetPeriod::Epochs(length) => length > 0,
+        }
+    }
+
+    /// Id of the period `clock` falls into, `None` when counters are never reset. Period must be valid.
+    /// The kind and length are hashed into the id, so a changed period always starts a new one
+    pub fn period_id(&self, clock: &Clock) -> Option<u64> {
+        let index = match *self {
+            ResetPeriod::Never => return None,
+            ResetPeriod::Slots(length) => clock.slot / length,
+            ResetPeriod::UnixTime(length) => clock.unix_timestamp.max(0) as u64 / length,
+            ResetPeriod::Epochs(length) => clock.epoch / length,
+        };
+        let (kind, length) = self.pack();
+        let hash = hashv(&[&[kind], &length.to_le_bytes(), &index.to_le_bytes()]);
+        let mut id = [0; 8];
+        id.copy_from_slice(&hash.as_ref()[..8]);
+        Some(u64::from_le_bytes(id))
+    }
+
+    pub fn pack(&self) -> (u8, u64) {
+        match *self {
+            ResetPeriod::Never => (0, 0),
+            ResetPeriod::Slots(length) => (1, length),
+            ResetPeriod::UnixTime(length) => (2, length),
+            ResetPeriod::Epochs(length) => (3, length),
+        }
+    }
+
+    /// `None` for unknown kind or invalid period
+    pub fn unpack(kind: u8, length: u64) -> Option<Self> {
+        let period = match kind {
+            0 => ResetPeriod::Never,
+            1 => ResetPeriod::Slots(length),
+            2 => ResetPeriod::UnixTime(length),
+            3 => ResetPeriod::Epochs(length),
+            _ => return None,
+        };
+        Some(period).filter(ResetPeriod::is_valid)
+    }
+}
+
+/// Who may apply counter operations
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Clone, Copy, Debug, PartialEq)]
+pub enum AccessMode {
+    /// Everybody
+    Off,
+    /// Only users listed in access pages
+    Allowlist,
+    /// Everybody except users listed in access pages
+    Denylist,
+}
+
+impl AccessMode {
+    /// `None` for unknown mode
+    pub fn unpack(mode: u8) -> Option<Self> {
+        match mode {
+            0 => Some(AccessMode::Off),
+            1 => Some(AccessMode::Allowlist),
+            2 => Some(AccessMode::Denylist),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod reset_period_test {
+    use borsh::BorshSerialize;
+    use solana_program::clock::Clock;
+    use crate::state::ResetPeriod;
+
+    fn clock() -> Clock {
+        Clock { slot: 250, epoch: 7, unix_timestamp: 1637000000, ..Clock::default() }
+    }
+
+    #[test]
+    fn when_never_expect_no_period_id() {
+        assert_eq!(ResetPeriod::Never.period_id(&clock()), None);
+    }
+
+    #[test]
+    fn when_same_period_expect_same_id() {
+        let next_slot = Clock { slot: 299, ..clock() };
+        let next_period_slot = Clock { slot: 300, ..clock() };
+
+        assert_eq!(ResetPeriod::Slots(100).period_id(&next_slot), ResetPeriod::Slots(100).period_id(&clock()));
+        assert_ne!(ResetPeriod::Slots(100).period_id(&next_period_slot), ResetPeriod::Slots(100).period_id(&clock()));
+    }
+
+    #[test]
+    fn when_kind_or_length_changed_expect_other_id() {
+        // Slot 250, epoch 7 and the clock time are in the third period of every one of them
+        let ids = [
+            ResetPeriod::Slots(100).period_id(&clock()),
+            ResetPeriod::Slots(120).period_id(&clock()),
+            ResetPeriod::Epochs(3).period_id(&clock()),
+            ResetPeriod::UnixTime(818500000).period_id(&clock()),
+        ];
+
+        for (index, id) in ids.iter().enumerate() {
+            assert!(id.is_some());
+            assert!(ids[index + 1..].iter().all(|other| other != id), "{:?}", ids);
+        }
+    }
+
+    #[test]
+    fn when_pack_unpack_expect_same_period() {
+        for period in [
+            ResetPeriod::Never,
+            ResetPeriod::Slots(1),
+            ResetPeriod::UnixTime(86400),
+            ResetPeriod::Epochs(3),
+        ] {
+            let (kind, length) = period.pack();
+
+            assert_eq!(ResetPeriod::unpack(kind, length), Some(period));
+        }
+    }
+
+    #[test]
+    fn when_unpack_invalid_expect_none() {
+        assert_eq!(ResetPeriod::unpack(1, 0), None);
+        assert_eq!(ResetPeriod::unpack(4, 1), None);
+    }
+
+    #[test]
+    fn when_serialization_reset_period_expect_ok() {
+        let binary_period = [1, 100, 0, 0, 0, 0, 0, 0, 0];
+
+        assert_eq!(ResetPeriod::Slots(100).try_to_vec().unwrap(), binary_period)
+    }
+}
+
 #[cfg(test)]
 mod settings_test {
     use borsh::BorshSerialize;
     use borsh::BorshDeserialize;
     use solana_program::pubkey::Pubkey;
-    use crate::state::Settings;
+    use crate::state::{AccessMode, PodSettings, ResetPeriod, Settings};
     use std::str::FromStr;
 
     const PK: Pubkey = Pubkey::new_from_array([3_u8; 32]);
-    const SETTINGS: Settings = Settings { admin: PK, inc_step: 1, dec_step: 10 };
-    const BINARY_SETTINGS: [u8; 40] = [3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-        3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 1, 0, 0, 0, 10, 0, 0, 0];
+    const SETTINGS: Settings = Settings {
+        admin: PK,
+        inc_step: 1,
+        dec_step: 10,
+        reset_period_kind: 1,
+        reset_period: 100,
+        access_mode: 2,
+    };
+    const BINARY_SETTINGS: [u8; 50] = [3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+        3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 1, 0, 0, 0, 10, 0, 0, 0, 1, 100, 0, 0, 0, 0, 0, 0, 0,
+        2];
 
     #[test]
     fn when_serialization_settings_expect_ok() {
@@ -138,6 +690,18 @@ mod settings_test {
         assert_eq!(deserialized_settings, SETTINGS)
     }
 
+    #[test]
+    fn when_load_pod_settings_expect_same_fields() {
+        let pod_settings = PodSettings::load(&BINARY_SETTINGS).unwrap();
+
+        assert_eq!(pod_settings.admin(), PK);
+        assert_eq!(pod_settings.inc_step(), 1);
+        assert_eq!(pod_settings.dec_step(), 10);
+        assert_eq!(pod_settings.reset_period().unwrap(), ResetPeriod::Slots(100));
+        assert_eq!(pod_settings.access_mode().unwrap(), AccessMode::Denylist);
+        assert_eq!(Settings::LEN, BINARY_SETTINGS.len());
+    }
+
     #[test]
     fn when_get_settings_pk_expect_key() {
         let (generated_pk, bump) = Settings::get_settings_pk_with_bump();
@@ -162,4 +726,516 @@ mod settings_test {
 
         assert_eq!(check, false)
     }
-}
\ No newline at end of file
+}
+
+/// Page of the access list, PDA for a bucket of user keys and the page index.
+/// A user belongs to the bucket of the first byte of its key and is listed in one page of the bucket.
+/// The first page is created with the bucket, the next page when all pages of the bucket are full,
+/// up to `MAX_ACCESS_PAGES`. Borsh layout is equal to `PodAccessPage`
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Debug, PartialEq)]
+pub struct AccessPage {
+    /// Bucket of the page
+    pub bucket: u8,
+
+    /// Index of the page in the bucket, the first page is 0
+    pub index: u8,
+
+    /// Number of created pages of the bucket, kept in the first page only
+    pub pages: u8,
+
+    /// Number of listed keys
+    pub len: u8,
+
+    /// Listed keys, only the first `len` are used
+    pub keys: [Pubkey; ACCESS_PAGE_CAPACITY],
+}
+
+impl AccessPage {
+    /// Size of an access page account
+    pub const LEN: usize = std::mem::size_of::<PodAccessPage>();
+
+    pub fn bucket(user: &Pubkey) -> u8 {
+        user.as_ref()[0]
+    }
+
+    pub fn keys(&self) -> &[Pubkey] {
+        &self.keys[..(self.len as usize).min(ACCESS_PAGE_CAPACITY)]
+    }
+
+    pub fn create_signer_seed<'a>(bucket: &'a [u8], index: &'a [u8], bump: &'a [u8]) -> [&'a [u8]; 4] {
+        [ACCESS_SEED.as_bytes(), bucket, index, bump]
+    }
+
+    pub fn get_access_page_pk_with_bump(bucket: u8, index: u8) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[ACCESS_SEED.as_bytes(), &[bucket], &[index]], &id())
+    }
+
+    /// Access page must be the page `index` of the user bucket
+    pub fn check_access_page_pk(user: &Pubkey, index: u8, access_page: &Pubkey) -> bool {
+        let (pk, _) = Self::get_access_page_pk_with_bump(Self::bucket(user), index);
+        pk.to_bytes() == access_page.to_bytes()
+    }
+}
+
+/// Zero-copy layout of `AccessPage`, accessed in place in account data
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct PodAccessPage {
+    pub bucket: u8,
+    pub index: u8,
+    pub pages: u8,
+    pub len: u8,
+    pub keys: [[u8; 32]; ACCESS_PAGE_CAPACITY],
+}
+
+impl PodAccessPage {
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        bytemuck::try_from_bytes(data).map_err(|_| CounterError::InvalidAccessPageData.into())
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        bytemuck::try_from_bytes_mut(data).map_err(|_| CounterError::InvalidAccessPageData.into())
+    }
+
+    fn listed(&self) -> &[[u8; 32]] {
+        &self.keys[..(self.len as usize).min(ACCESS_PAGE_CAPACITY)]
+    }
+
+    pub fn contains(&self, user: &Pubkey) -> bool {
+        self.listed().contains(&user.to_bytes())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len as usize >= ACCESS_PAGE_CAPACITY
+    }
+
+    /// Add a key, page must not be full. Listed key is not added twice
+    pub fn add(&mut self, user: &Pubkey) {
+        if !self.contains(user) {
+            self.keys[self.len as usize] = user.to_bytes();
+            self.len += 1;
+        }
+    }
+
+    /// Remove a key, the last key takes its place
+    pub fn remove(&mut self, user: &Pubkey) {
+        let listed = self.listed();
+        if let Some(index) = listed.iter().position(|key| *key == user.to_bytes()) {
+            let last = listed.len() - 1;
+            self.keys[index] = self.keys[last];
+            self.keys[last] = [0; 32];
+            self.len = last as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod access_page_test {
+    use borsh::BorshDeserialize;
+    use solana_program::pubkey::Pubkey;
+    use crate::state::{AccessPage, PodAccessPage};
+    use crate::ACCESS_PAGE_CAPACITY;
+
+    fn user(bucket: u8, index: u8) -> Pubkey {
+        let mut key = [index; 32];
+        key[0] = bucket;
+        Pubkey::new_from_array(key)
+    }
+
+    #[test]
+    fn when_add_expect_borsh_keys() {
+        let mut data = [0_u8; AccessPage::LEN];
+        let page = PodAccessPage::load_mut(&mut data).unwrap();
+        page.add(&user(1, 1));
+        page.add(&user(1, 2));
+        page.add(&user(1, 1));
+
+        let access_page = AccessPage::try_from_slice(&data).unwrap();
+
+        assert_eq!(access_page.keys(), [user(1, 1), user(1, 2)]);
+    }
+
+    #[test]
+    fn when_fill_page_expect_full_at_capacity() {
+        let mut data = [0_u8; AccessPage::LEN];
+        let page = PodAccessPage::load_mut(&mut data).unwrap();
+        for index in 0..ACCESS_PAGE_CAPACITY as u8 {
+            assert_eq!(page.is_full(), false);
+            page.add(&user(1, index));
+        }
+
+        assert_eq!(page.is_full(), true);
+        assert_eq!(page.contains(&user(1, 0)), true);
+        assert_eq!(page.contains(&user(1, ACCESS_PAGE_CAPACITY as u8 - 1)), true);
+    }
+
+    #[test]
+    fn when_remove_expect_last_key_moved() {
+        let mut data = [0_u8; AccessPage::LEN];
+        let page = PodAccessPage::load_mut(&mut data).unwrap();
+        page.add(&user(1, 1));
+        page.add(&user(1, 2));
+        page.add(&user(1, 3));
+        page.remove(&user(1, 1));
+        page.remove(&user(1, 7));
+
+        let access_page = AccessPage::try_from_slice(&data).unwrap();
+
+        assert_eq!(access_page.keys(), [user(1, 3), user(1, 2)]);
+        assert_eq!(access_page.keys[2], Pubkey::default());
+    }
+
+    #[test]
+    fn when_remove_with_corrupted_len_expect_no_panic() {
+        let mut data = [0_u8; AccessPage::LEN];
+        let page = PodAccessPage::load_mut(&mut data).unwrap();
+        page.add(&user(1, 1));
+        page.len = u8::MAX;
+        page.remove(&user(1, 1));
+
+        assert_eq!(page.len as usize, ACCESS_PAGE_CAPACITY - 1);
+        assert_eq!(page.contains(&user(1, 1)), false);
+    }
+
+    #[test]
+    fn when_bucket_expect_first_key_byte() {
+        assert_eq!(AccessPage::bucket(&user(7, 1)), 7);
+        assert_eq!(
+            AccessPage::check_access_page_pk(
+                &user(7, 1),
+                0,
+                &AccessPage::get_access_page_pk_with_bump(7, 0).0,
+            ),
+            true,
+        );
+        assert_eq!(
+            AccessPage::check_access_page_pk(
+                &user(7, 1),
+                0,
+                &AccessPage::get_access_page_pk_with_bump(8, 0).0,
+            ),
+            false,
+        );
+    }
+
+    #[test]
+    fn when_page_index_differs_expect_other_pk() {
+        let second_page_pk = AccessPage::get_access_page_pk_with_bump(7, 1).0;
+
+        assert_ne!(second_page_pk, AccessPage::get_access_page_pk_with_bump(7, 0).0);
+        assert_eq!(AccessPage::check_access_page_pk(&user(7, 1), 1, &second_page_pk), true);
+        assert_eq!(AccessPage::check_access_page_pk(&user(7, 1), 0, &second_page_pk), false);
+    }
+}
+
+/// Privilege of an admin instruction.
+/// `Settings.admin` holds every role, other keys get roles in the roles account
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Clone, Copy, Debug, PartialEq)]
+pub enum Role {
+    /// Every role, grants and revokes roles and transfers `Settings.admin`
+    SuperAdmin,
+    /// Operation steps, reset period and access mode
+    SettingsManager,
+    /// Freeze, thaw and reset of user counters, access list
+    Moderator,
+}
+
+impl Role {
+    /// Bit of the role in `Roles.roles`
+    pub fn mask(&self) -> u8 {
+        1 << *self as u8
+    }
+}
+
+/// Keys holding roles, PDA.
+/// Borsh layout is equal to `PodRoles`
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Debug, PartialEq)]
+pub struct Roles {
+    /// Number of members
+    pub len: u8,
+
+    /// Member keys, only the first `len` are used
+    pub keys: [Pubkey; MAX_ROLE_MEMBERS],
+
+    /// Role bits of the member with the same index
+    pub roles: [u8; MAX_ROLE_MEMBERS],
+}
+
+impl Roles {
+    /// Size of a roles account
+    pub const LEN: usize = std::mem::size_of::<PodRoles>();
+
+    /// Roles of the member, empty when it is not a member
+    pub fn roles_of(&self, user: &Pubkey) -> Vec<Role> {
+        let len = (self.len as usize).min(MAX_ROLE_MEMBERS);
+        let mask = self.keys[..len]
+            .iter()
+            .position(|key| key == user)
+            .map_or(0, |index| self.roles[index]);
+        [Role::SuperAdmin, Role::SettingsManager, Role::Moderator]
+            .iter()
+            .copied()
+            .filter(|role| mask & role.mask() != 0)
+            .collect()
+    }
+
+    pub fn create_signer_seed(bump: &[u8]) -> [&[u8]; 2] {
+        [ROLES_SEED.as_bytes(), bump]
+    }
+
+    pub fn get_roles_pk_with_bump() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[ROLES_SEED.as_bytes()], &id())
+    }
+
+    pub fn check_roles_pk(roles: &Pubkey) -> bool {
+        let (pk, _) = Self::get_roles_pk_with_bump();
+        pk.to_bytes() == roles.to_bytes()
+    }
+}
+
+/// Zero-copy layout of `Roles`, accessed in place in account data
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct PodRoles {
+    pub len: u8,
+    pub keys: [[u8; 32]; MAX_ROLE_MEMBERS],
+    pub roles: [u8; MAX_ROLE_MEMBERS],
+}
+
+impl PodRoles {
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        bytemuck::try_from_bytes(data).map_err(|_| CounterError::InvalidRolesData.into())
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        bytemuck::try_from_bytes_mut(data).map_err(|_| CounterError::InvalidRolesData.into())
+    }
+
+    fn position(&self, user: &Pubkey) -> Option<usize> {
+        let len = (self.len as usize).min(MAX_ROLE_MEMBERS);
+        self.keys[..len].iter().position(|key| *key == user.to_bytes())
+    }
+
+    /// `SuperAdmin` holds every role
+    pub fn has_role(&self, user: &Pubkey, role: Role) -> bool {
+        let mask = role.mask() | Role::SuperAdmin.mask();
+        self.position(user).is_some_and(|index| self.roles[index] & mask != 0)
+    }
+
+    /// `false` when the user is not a member and there is no place for a new member
+    pub fn grant(&mut self, user: &Pubkey, role: Role) -> bool {
+        let index = match self.position(user) {
+            Some(index) => index,
+            None if (self.len as usize) < MAX_ROLE_MEMBERS => {
+                let index = self.len as usize;
+                self.keys[index] = user.to_bytes();
+                self.roles[index] = 0;
+                self.len += 1;
+                index
+            }
+            None => return false,
+        };
+        self.roles[index] |= role.mask();
+        true
+    }
+
+    /// Member without roles is removed, the last member takes its place
+    pub fn revoke(&mut self, user: &Pubkey, role: Role) {
+        if let Some(index) = self.position(user) {
+            self.roles[index] &= !role.mask();
+            if self.roles[index] == 0 {
+                let last = (self.len as usize).min(MAX_ROLE_MEMBERS) - 1;
+                self.keys[index] = self.keys[last];
+                self.roles[index] = self.roles[last];
+                self.keys[last] = [0; 32];
+                self.roles[last] = 0;
+                self.len = last as u8;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod roles_test {
+    use borsh::BorshDeserialize;
+    use solana_program::pubkey::Pubkey;
+    use crate::state::{PodRoles, Role, Roles};
+    use crate::MAX_ROLE_MEMBERS;
+
+    fn user(index: u8) -> Pubkey {
+        Pubkey::new_from_array([index; 32])
+    }
+
+    #[test]
+    fn when_grant_expect_borsh_roles() {
+        let mut data = [0_u8; Roles::LEN];
+        let roles = PodRoles::load_mut(&mut data).unwrap();
+        roles.grant(&user(1), Role::Moderator);
+        roles.grant(&user(1), Role::SettingsManager);
+        roles.grant(&user(2), Role::SettingsManager);
+
+        let roles = Roles::try_from_slice(&data).unwrap();
+
+        assert_eq!(roles.len, 2);
+        assert_eq!(roles.roles_of(&user(1)), [Role::SettingsManager, Role::Moderator]);
+        assert_eq!(roles.roles_of(&user(2)), [Role::SettingsManager]);
+        assert_eq!(roles.roles_of(&user(3)), []);
+    }
+
+    #[test]
+    fn when_has_role_expect_super_admin_has_every_role() {
+        let mut data = [0_u8; Roles::LEN];
+        let roles = PodRoles::load_mut(&mut data).unwrap();
+        roles.grant(&user(1), Role::Moderator);
+        roles.grant(&user(2), Role::SuperAdmin);
+
+        assert_eq!(roles.has_role(&user(1), Role::Moderator), true);
+        assert_eq!(roles.has_role(&user(1), Role::SettingsManager), false);
+        assert_eq!(roles.has_role(&user(2), Role::SettingsManager), true);
+        assert_eq!(roles.has_role(&user(3), Role::Moderator), false);
+    }
+
+    #[test]
+    fn when_revoke_last_role_expect_member_removed() {
+        let mut data = [0_u8; Roles::LEN];
+        let roles = PodRoles::load_mut(&mut data).unwrap();
+        roles.grant(&user(1), Role::Moderator);
+        roles.grant(&user(2), Role::Moderator);
+        roles.grant(&user(2), Role::SettingsManager);
+        roles.revoke(&user(2), Role::Moderator);
+        roles.revoke(&user(1), Role::Moderator);
+
+        let roles = Roles::try_from_slice(&data).unwrap();
+
+        assert_eq!(roles.len, 1);
+        assert_eq!(roles.keys[0], user(2));
+        assert_eq!(roles.roles_of(&user(2)), [Role::SettingsManager]);
+    }
+
+    #[test]
+    fn when_revoke_with_corrupted_len_expect_no_panic() {
+        let mut data = [0_u8; Roles::LEN];
+        let roles = PodRoles::load_mut(&mut data).unwrap();
+        roles.grant(&user(1), Role::Moderator);
+        roles.len = u8::MAX;
+        roles.revoke(&user(1), Role::Moderator);
+
+        assert_eq!(roles.len as usize, MAX_ROLE_MEMBERS - 1);
+        assert_eq!(roles.has_role(&user(1), Role::Moderator), false);
+    }
+
+    #[test]
+    fn when_grant_to_full_roles_expect_false() {
+        let mut data = [0_u8; Roles::LEN];
+        let roles = PodRoles::load_mut(&mut data).unwrap();
+        for index in 0..MAX_ROLE_MEMBERS as u8 {
+            assert_eq!(roles.grant(&user(index), Role::Moderator), true);
+        }
+
+        assert_eq!(roles.grant(&user(MAX_ROLE_MEMBERS as u8), Role::Moderator), false);
+        assert_eq!(roles.grant(&user(0), Role::SettingsManager), true);
+    }
+}
+
+/// Short-lived key allowed to apply operations to counters of the owner, PDA.
+/// Borsh layout is equal to `PodSession`
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Debug, PartialEq)]
+pub struct Session {
+    /// Owner of counters
+    pub owner: Pubkey,
+
+    /// Key signing operations instead of the owner
+    pub session_key: Pubkey,
+
+    /// Unix timestamp after which the session is rejected
+    pub expiry: i64,
+
+    /// Bits of allowed `OperationKind`s
+    pub allowed_ops: u8,
+}
+
+impl Session {
+    /// Size of a session account
+    pub const LEN: usize = std::mem::size_of::<PodSession>();
+
+    pub fn create_signer_seed<'a>(owner: &'a Pubkey, session_key: &'a Pubkey, bump: &'a [u8]) -> [&'a [u8]; 4] {
+        [SESSION_SEED.as_bytes(), owner.as_ref(), session_key.as_ref(), bump]
+    }
+
+    pub fn get_session_pk_with_bump(owner: &Pubkey, session_key: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[SESSION_SEED.as_bytes(), owner.as_ref(), session_key.as_ref()], &id())
+    }
+
+    pub fn check_session_pk(owner: &Pubkey, session_key: &Pubkey, session: &Pubkey) -> bool {
+        let (pk, _) = Self::get_session_pk_with_bump(owner, session_key);
+        pk.to_bytes() == session.to_bytes()
+    }
+
+    pub fn pack_ops(ops: &[OperationKind]) -> u8 {
+        ops.iter().fold(0, |mask, op| mask | op.mask())
+    }
+}
+
+/// Zero-copy layout of `Session`, accessed in place in account data
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct PodSession {
+    pub owner: [u8; 32],
+    pub session_key: [u8; 32],
+    pub expiry: PodI64,
+    pub allowed_ops: u8,
+}
+
+impl PodSession {
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        bytemuck::try_from_bytes(data).map_err(|_| CounterError::InvalidSessionData.into())
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        bytemuck::try_from_bytes_mut(data).map_err(|_| CounterError::InvalidSessionData.into())
+    }
+
+    pub fn expiry(&self) -> i64 {
+        self.expiry.into()
+    }
+
+    pub fn allows(&self, op: OperationKind) -> bool {
+        self.allowed_ops & op.mask() != 0
+    }
+}
+
+#[cfg(test)]
+mod session_test {
+    use borsh::BorshSerialize;
+    use solana_program::pubkey::Pubkey;
+    use crate::instruction::OperationKind;
+    use crate::state::{PodSession, Session};
+
+    const OWNER: Pubkey = Pubkey::new_from_array([3_u8; 32]);
+    const SESSION_KEY: Pubkey = Pubkey::new_from_array([4_u8; 32]);
+
+    #[test]
+    fn when_load_pod_session_expect_same_fields() {
+        let session = Session {
+            owner: OWNER,
+            session_key: SESSION_KEY,
+            expiry: 1637000000,
+            allowed_ops: Session::pack_ops(&[OperationKind::Inc]),
+        };
+        let data = session.try_to_vec().unwrap();
+        let pod_session = PodSession::load(&data).unwrap();
+
+        assert_eq!(Session::LEN, data.len());
+        assert_eq!(pod_session.expiry(), 1637000000);
+        assert_eq!(pod_session.allows(OperationKind::Inc), true);
+        assert_eq!(pod_session.allows(OperationKind::Dec), false);
+    }
+
+    #[test]
+    fn when_session_pk_expect_unique_for_owner_and_key() {
+        let (session_pk, _) = Session::get_session_pk_with_bump(&OWNER, &SESSION_KEY);
+
+        assert_eq!(Session::check_session_pk(&OWNER, &SESSION_KEY, &session_pk), true);
+        assert_eq!(Session::check_session_pk(&SESSION_KEY, &OWNER, &session_pk), false);
+    }
+}
@@ -0,0 +1,214 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use solana_program::pubkey::Pubkey;
+use solana_program_test::ProgramTestContext;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use counter::instruction::CounterInstruction;
+use counter::state::{AccessMode, AccessPage, Settings};
+use counter::error::CounterError;
+use counter::ACCESS_PAGE_CAPACITY;
+use borsh::BorshDeserialize;
+use common::{custom_err, get_counter, program_test, send, Env};
+
+async fn get_access_page(ctx: &mut ProgramTestContext, bucket: u8, index: u8) -> AccessPage {
+    let access_page_acc = ctx
+        .banks_client
+        .get_account(AccessPage::get_access_page_pk_with_bump(bucket, index).0)
+        .await
+        .unwrap()
+        .unwrap();
+    AccessPage::try_from_slice(&access_page_acc.data).unwrap()
+}
+
+/// Key falling into the given bucket of the access list
+fn bucket_key(bucket: u8, index: u8) -> Pubkey {
+    let mut key = [index; 32];
+    key[0] = bucket;
+    Pubkey::new_from_array(key)
+}
+
+/// Signer falling into the given bucket of the access list
+fn bucket_keypair(bucket: u8) -> Keypair {
+    loop {
+        let keypair = Keypair::new();
+        if AccessPage::bucket(&keypair.pubkey()) == bucket {
+            return keypair;
+        }
+    }
+}
+
+#[tokio::test]
+async fn allowlist() {
+    let env = Env::new().await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+
+    send(&mut ctx, &[CounterInstruction::set_access_mode_instr(admin.pubkey(), AccessMode::Allowlist)], &admin, &[])
+        .await
+        .unwrap();
+    let err = send(&mut ctx, &[CounterInstruction::inc_instr(user.pubkey(), "").unwrap()], &user, &[]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::AccessDenied));
+    let err = send(&mut ctx, &[CounterInstruction::reset_instr(user.pubkey(), "").unwrap()], &user, &[]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::AccessDenied));
+
+    send(&mut ctx, &[CounterInstruction::add_access_instr(admin.pubkey(), user.pubkey(), 1)], &admin, &[])
+        .await
+        .unwrap();
+    send(&mut ctx, &[CounterInstruction::inc_instr(user.pubkey(), "").unwrap()], &admin, &[&user]).await.unwrap();
+    assert_eq!(get_counter(&mut ctx, &user.pubkey(), "").await.value, 9);
+
+    send(&mut ctx, &[CounterInstruction::remove_access_instr(admin.pubkey(), user.pubkey(), 1)], &admin, &[])
+        .await
+        .unwrap();
+    let err = send(&mut ctx, &[CounterInstruction::dec_instr(user.pubkey(), "").unwrap()], &user, &[]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::AccessDenied));
+}
+
+#[tokio::test]
+async fn denylist() {
+    let env = Env::new().await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+
+    send(&mut ctx, &[CounterInstruction::set_access_mode_instr(admin.pubkey(), AccessMode::Denylist)], &admin, &[])
+        .await
+        .unwrap();
+    send(&mut ctx, &[CounterInstruction::inc_instr(user.pubkey(), "").unwrap()], &user, &[]).await.unwrap();
+
+    send(&mut ctx, &[CounterInstruction::add_access_instr(admin.pubkey(), user.pubkey(), 1)], &admin, &[])
+        .await
+        .unwrap();
+    let err = send(&mut ctx, &[CounterInstruction::dec_instr(user.pubkey(), "").unwrap()], &user, &[]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::AccessDenied));
+    let err = send(&mut ctx, &[CounterInstruction::reset_instr(user.pubkey(), "").unwrap()], &user, &[]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::AccessDenied));
+    assert_eq!(get_counter(&mut ctx, &user.pubkey(), "").await.value, 9);
+
+    send(&mut ctx, &[CounterInstruction::set_access_mode_instr(admin.pubkey(), AccessMode::Off)], &admin, &[])
+        .await
+        .unwrap();
+    send(&mut ctx, &[CounterInstruction::dec_instr(user.pubkey(), "").unwrap()], &admin, &[&user]).await.unwrap();
+    assert_eq!(get_counter(&mut ctx, &user.pubkey(), "").await.value, 4);
+}
+
+#[tokio::test]
+async fn access_page_full() {
+    let env = Env::start_with_user(program_test(), bucket_keypair(5)).await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+
+    for index in 0..ACCESS_PAGE_CAPACITY as u8 {
+        send(&mut ctx, &[CounterInstruction::add_access_instr(admin.pubkey(), bucket_key(5, index), 1)], &admin, &[])
+            .await
+            .unwrap();
+    }
+    let page = get_access_page(&mut ctx, 5, 0).await;
+    assert_eq!((page.bucket, page.index, page.pages), (5, 0, 1));
+    assert_eq!(page.keys().len(), ACCESS_PAGE_CAPACITY);
+
+    // Listed key is not added twice, so it fits into the full page
+    send(&mut ctx, &[CounterInstruction::add_access_instr(admin.pubkey(), bucket_key(5, 0), 1)], &user, &[&admin])
+        .await
+        .unwrap();
+    assert_eq!(get_access_page(&mut ctx, 5, 0).await.pages, 1);
+
+    // Next key of the bucket goes to the next page counted in the first one,
+    // while the pages of the other buckets are not even created
+    send(&mut ctx, &[CounterInstruction::add_access_instr(admin.pubkey(), user.pubkey(), 1)], &admin, &[])
+        .await
+        .unwrap();
+    assert_eq!(get_access_page(&mut ctx, 5, 0).await.pages, 2);
+    let next_page = get_access_page(&mut ctx, 5, 1).await;
+    assert_eq!((next_page.bucket, next_page.index), (5, 1));
+    assert_eq!(next_page.keys(), [user.pubkey()]);
+    let other_page = ctx.banks_client.get_account(AccessPage::get_access_page_pk_with_bump(6, 0).0).await.unwrap();
+    assert_eq!(other_page, None);
+
+    // Denylist checks every page of the bucket, so the key on the next page is not missed
+    send(&mut ctx, &[CounterInstruction::set_access_mode_instr(admin.pubkey(), AccessMode::Denylist)], &admin, &[])
+        .await
+        .unwrap();
+    let err = send(&mut ctx, &[CounterInstruction::inc_instr(user.pubkey(), "").unwrap()], &user, &[]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::AccessPagesMissing));
+    let inc_instr = CounterInstruction::inc_instr(user.pubkey(), "").unwrap();
+    let inc_instr = CounterInstruction::with_access_pages(inc_instr, &user.pubkey(), 2);
+    let err = send(&mut ctx, &[inc_instr.clone()], &user, &[]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::AccessDenied));
+
+    // Removed key frees a place on its page
+    send(&mut ctx, &[CounterInstruction::remove_access_instr(admin.pubkey(), user.pubkey(), 2)], &admin, &[])
+        .await
+        .unwrap();
+    assert!(get_access_page(&mut ctx, 5, 1).await.keys().is_empty());
+    send(&mut ctx, &[inc_instr], &admin, &[&user]).await.unwrap();
+    assert_eq!(get_counter(&mut ctx, &user.pubkey(), "").await.value, 9);
+
+    // Allowlist accepts the page listing the key
+    send(&mut ctx, &[CounterInstruction::add_access_instr(admin.pubkey(), user.pubkey(), 2)], &admin, &[])
+        .await
+        .unwrap();
+    assert_eq!(get_access_page(&mut ctx, 5, 1).await.keys(), [user.pubkey()]);
+    send(&mut ctx, &[CounterInstruction::set_access_mode_instr(admin.pubkey(), AccessMode::Allowlist)], &admin, &[])
+        .await
+        .unwrap();
+    let err = send(&mut ctx, &[CounterInstruction::dec_instr(user.pubkey(), "").unwrap()], &user, &[]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::AccessDenied));
+    let dec_instr = CounterInstruction::dec_instr(user.pubkey(), "").unwrap();
+    let dec_instr = CounterInstruction::with_access_pages(dec_instr, &user.pubkey(), 2);
+    send(&mut ctx, &[dec_instr], &user, &[]).await.unwrap();
+    assert_eq!(get_counter(&mut ctx, &user.pubkey(), "").await.value, 4);
+
+    // Place freed on the first page is taken before the next page
+    let overflow_key = bucket_key(5, ACCESS_PAGE_CAPACITY as u8);
+    send(&mut ctx, &[CounterInstruction::remove_access_instr(admin.pubkey(), bucket_key(5, 0), 2)], &admin, &[])
+        .await
+        .unwrap();
+    send(&mut ctx, &[CounterInstruction::add_access_instr(admin.pubkey(), overflow_key, 2)], &admin, &[])
+        .await
+        .unwrap();
+    let page = get_access_page(&mut ctx, 5, 0).await;
+    assert_eq!(page.pages, 2);
+    assert!(page.keys().contains(&overflow_key));
+    assert!(!page.keys().contains(&bucket_key(5, 0)));
+    assert_eq!(get_access_page(&mut ctx, 5, 1).await.keys(), [user.pubkey()]);
+}
+
+#[tokio::test]
+async fn wrong_access_page() {
+    let env = Env::new().await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+
+    send(&mut ctx, &[CounterInstruction::set_access_mode_instr(admin.pubkey(), AccessMode::Denylist)], &admin, &[])
+        .await
+        .unwrap();
+    let mut inc_instr = CounterInstruction::inc_instr(user.pubkey(), "").unwrap();
+    let other_bucket = AccessPage::bucket(&user.pubkey()).wrapping_add(1);
+    inc_instr.accounts[3].pubkey = AccessPage::get_access_page_pk_with_bump(other_bucket, 0).0;
+    let err = send(&mut ctx, &[inc_instr], &user, &[]).await.unwrap_err();
+
+    assert_eq!(err, custom_err(0, CounterError::WrongAccessPagePDA));
+}
+
+#[tokio::test]
+async fn access_not_admin() {
+    let env = Env::new().await;
+    let (user, mut ctx) = (env.user, env.ctx);
+
+    let set_mode_instr = CounterInstruction::set_access_mode_instr(user.pubkey(), AccessMode::Allowlist);
+    let err = send(&mut ctx, &[set_mode_instr], &user, &[]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::AdminRequired));
+
+    let err = send(&mut ctx, &[CounterInstruction::add_access_instr(user.pubkey(), user.pubkey(), 1)], &user, &[])
+        .await
+        .unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::AdminRequired));
+
+    let settings_acc = ctx
+        .banks_client
+        .get_account(Settings::get_settings_pk_with_bump().0)
+        .await
+        .unwrap()
+        .unwrap();
+    let settings = Settings::try_from_slice(&settings_acc.data).unwrap();
+    assert_eq!(settings.access_mode(), Some(AccessMode::Off));
+}
@@ -0,0 +1,155 @@
+#![allow(dead_code)]
+
+use std::borrow::Borrow;
+use solana_program::instruction::{Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use counter::instruction::CounterInstruction;
+use counter::state::{Counter, Settings};
+use counter::error::CounterError;
+use borsh::BorshDeserialize;
+use counter::id;
+use counter::processor::Processor;
+
+/// Native runs use the processor, so the tests also build with `no-entrypoint`
+pub fn program_test() -> ProgramTest {
+    ProgramTest::new("counter", id(), processor!(Processor::process))
+}
+
+pub struct Env {
+    pub ctx: ProgramTestContext,
+    pub admin: Keypair,
+    pub user: Keypair,
+}
+
+impl Env {
+    pub async fn new() -> Self {
+        Self::start(program_test()).await
+    }
+
+    /// Start with additional programs registered in `program_test`
+    pub async fn start(program_test: ProgramTest) -> Self {
+        Self::start_with_user(program_test, Keypair::new()).await
+    }
+
+    /// Start with a user known in advance, so `program_test` may hold accounts of it
+    pub async fn start_with_user(program_test: ProgramTest, user: Keypair) -> Self {
+        let mut ctx = program_test.start_with_context().await;
+
+        let admin = Keypair::new();
+
+
+        // Deposit SOL
+        let admin_deposit_instr = system_instruction::transfer(
+            &ctx.payer.pubkey(),
+            &admin.pubkey(),
+            5_000_000_000,
+        );
+        let user_deposit_instr = system_instruction::transfer(
+            &ctx.payer.pubkey(),
+            &user.pubkey(),
+            3_000_000_000,
+        );
+        let deposit_tx = Transaction::new_signed_with_payer(
+            &[admin_deposit_instr, user_deposit_instr],
+            Some(&ctx.payer.pubkey()),
+            &[&ctx.payer],
+            ctx.last_blockhash,
+        );
+        ctx.banks_client.process_transaction(deposit_tx).await.unwrap();
+
+
+        // Update settings
+        let upd_sett_instr = CounterInstruction::upd_sett_instr(
+            admin.pubkey(),
+            admin.pubkey(),
+            9,
+            5,
+        );
+        let update_settings_tx = Transaction::new_signed_with_payer(
+            &[upd_sett_instr],
+            Some(&admin.pubkey()),
+            &[&admin],
+            ctx.last_blockhash,
+        );
+        ctx.banks_client.process_transaction(update_settings_tx).await.unwrap();
+
+        // Check settings account
+        let settings_pk = Settings::get_settings_pk_with_bump().0;
+        let settings_acc = ctx.banks_client.get_account(settings_pk).await.unwrap().unwrap();
+        let deserialized_settings = Settings::try_from_slice(settings_acc.data.borrow()).unwrap();
+        let inited_settings = Settings {
+            admin: admin.pubkey(),
+            inc_step: 9,
+            dec_step: 5,
+            reset_period_kind: 0,
+            reset_period: 0,
+            access_mode: 0,
+        };
+        assert_eq!(deserialized_settings, inited_settings);
+
+
+        // Init counter account
+        create_counter(&mut ctx, &user, "").await;
+
+
+        // Check counter account
+        let counter = get_counter(&mut ctx, &user.pubkey(), "").await;
+        assert_eq!(counter.value, 0);
+        assert_eq!(counter.owner, user.pubkey());
+        assert_eq!(counter.name(), "");
+        assert_eq!(counter.op_count, 0);
+        assert_eq!(counter.created_at, counter.updated_at);
+
+        Env { ctx, admin, user }
+    }
+}
+
+pub async fn create_counter(ctx: &mut ProgramTestContext, user: &Keypair, name: &str) {
+    let create_counter_instr = CounterInstruction::create_instr(user.pubkey(), user.pubkey(), name).unwrap();
+    let create_counter_tx = Transaction::new_signed_with_payer(
+        &[create_counter_instr],
+        Some(&user.pubkey()),
+        &[user],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(create_counter_tx).await.unwrap();
+}
+
+pub async fn get_counter(ctx: &mut ProgramTestContext, user: &Pubkey, name: &str) -> Counter {
+    let counter_acc = ctx
+        .banks_client
+        .get_account(Counter::generate_counter_pk(user, name).unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    Counter::try_from_slice(&counter_acc.data.borrow()).unwrap()
+}
+
+/// Send `instrs` paid by `payer` and signed by `signers` as well.
+/// Another payer gives a repeated instruction a new signature
+pub async fn send(
+    ctx: &mut ProgramTestContext,
+    instrs: &[Instruction],
+    payer: &Keypair,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(
+        instrs,
+        Some(&payer.pubkey()),
+        &all_signers,
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap())
+}
+
+/// Error of the counter program failed in the instruction number `index`
+pub fn custom_err(index: u8, err: CounterError) -> TransactionError {
+    TransactionError::InstructionError(index, InstructionError::Custom(err as u32))
+}
@@ -0,0 +1,267 @@
+#![cfg(feature = "test-bpf")]
+
+//! Compute units are metered only for the BPF build of the program:
+//! `cargo test-bpf --test compute_units -- --nocapture`
+//! Running it with `--features log-errors` and `--features log-verbose` checks the budget of each logging
+//! configuration.
+//! Comparisons meter other builds of the program in the same run, they are built by the tests with
+//! `cargo build-bpf` into `target/features`: `borsh-accounts` deserializes accounts with Borsh as before
+//! zero-copy, `log-errors` and `log-verbose` are the logging configurations
+
+// Comparisons measure the quiet build against the builds with features, other builds only check their budget
+#![cfg_attr(feature = "log-errors", allow(dead_code, unused_imports))]
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use solana_program::bpf_loader;
+use solana_program::instruction::Instruction;
+use solana_program::instruction::InstructionError;
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::system_program;
+use solana_program_test::ProgramTest;
+use solana_sdk::account::Account;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use counter::error::CounterError;
+use counter::instruction::CounterInstruction;
+use counter::state::{Counter, Settings};
+use counter::id;
+use borsh::BorshSerialize;
+use common::{custom_err, program_test};
+
+/// Upper bound of compute units for a single counter operation of the quiet build.
+/// Errors are logged on failure only, so `log-errors` costs nothing for successful operations
+#[cfg(not(feature = "log-verbose"))]
+const MAX_OPERATION_UNITS: u64 = 15_000;
+
+/// Tracing of every handler costs compute units for each `msg!`
+#[cfg(feature = "log-verbose")]
+const MAX_OPERATION_UNITS: u64 = 20_000;
+
+/// Name of the logging configuration the program is built with
+const LOG_CONFIG: &str = if cfg!(feature = "log-verbose") {
+    "log-verbose"
+} else if cfg!(feature = "log-errors") {
+    "log-errors"
+} else {
+    "quiet"
+};
+
+/// Default compute budget of a transaction
+const MAX_TRANSACTION_UNITS: u64 = 200_000;
+
+/// Build of the program metered by a test
+enum Build {
+    /// `counter.so` built for the test run
+    Tested,
+    /// Program built with a feature by `Build::with_feature`
+    Featured(Vec<u8>),
+}
+
+impl Build {
+    /// Build the program with `feature`. Builds use their own target directory,
+    /// cargo keeps the one of the test run locked until the tests finish
+    fn with_feature(feature: &str) -> Build {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let target_dir = manifest_dir.join("target").join("features");
+        let out_dir = target_dir.join("deploy").join(feature);
+        let status = Command::new("cargo")
+            .args(&["build-bpf", "--features", feature, "--bpf-out-dir"])
+            .arg(&out_dir)
+            .current_dir(manifest_dir)
+            .env("CARGO_TARGET_DIR", &target_dir)
+            .status()
+            .expect("cargo build-bpf must be installed");
+        assert!(status.success(), "build with {} failed", feature);
+        Build::Featured(fs::read(out_dir.join("counter.so")).unwrap())
+    }
+
+    fn program_test(&self) -> ProgramTest {
+        let elf = match self {
+            Build::Tested => return program_test(),
+            Build::Featured(elf) => elf,
+        };
+        let mut program_test = ProgramTest::default();
+        program_test.add_account(
+            id(),
+            Account {
+                lamports: Rent::default().minimum_balance(elf.len()),
+                data: elf.clone(),
+                owner: bpf_loader::id(),
+                executable: true,
+                rent_epoch: 0,
+            },
+        );
+        program_test
+    }
+}
+
+/// Data of the settings and counter accounts of `user`
+fn accounts_data(user: &Pubkey, frozen: bool) -> (Vec<u8>, Vec<u8>) {
+    let settings = Settings {
+        admin: *user,
+        inc_step: 9,
+        dec_step: 5,
+        reset_period_kind: 0,
+        reset_period: 0,
+        access_mode: 0,
+    };
+    let mut counter = Counter::new(*user, "", 0);
+    counter.frozen = frozen;
+    (settings.try_to_vec().unwrap(), counter.try_to_vec().unwrap())
+}
+
+fn inc(user: Pubkey) -> Instruction {
+    CounterInstruction::inc_instr(user, "").unwrap()
+}
+
+fn dec(user: Pubkey) -> Instruction {
+    CounterInstruction::dec_instr(user, "").unwrap()
+}
+
+fn reset(user: Pubkey) -> Instruction {
+    CounterInstruction::reset_instr(user, "").unwrap()
+}
+
+fn set_label(user: Pubkey) -> Instruction {
+    CounterInstruction::set_label_instr(user, "", "label").unwrap()
+}
+
+/// Operation name and its instruction builder
+type Operation = (&'static str, fn(Pubkey) -> Instruction);
+
+fn operations() -> [Operation; 4] {
+    [("inc", inc), ("dec", dec), ("reset", reset), ("set_label", set_label)]
+}
+
+fn program_account(data: Vec<u8>) -> Account {
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Run a single operation of `build` with the given compute budget, on a frozen counter it fails with `Frozen`.
+/// Settings and counter accounts are preloaded, so only the operation itself is metered
+async fn run_operation(
+    build: &Build,
+    build_instr: fn(Pubkey) -> Instruction,
+    frozen: bool,
+    max_units: u64,
+) -> Result<(), TransactionError> {
+    let user = Keypair::new();
+    let (settings_data, counter_data) = accounts_data(&user.pubkey(), frozen);
+
+    let mut program_test = build.program_test();
+    program_test.set_bpf_compute_max_units(max_units);
+    program_test.add_account(
+        user.pubkey(),
+        Account::new(1_000_000_000, 0, &system_program::id()),
+    );
+    program_test.add_account(Settings::get_settings_pk_with_bump().0, program_account(settings_data));
+    program_test.add_account(
+        Counter::generate_counter_pk(&user.pubkey(), "").unwrap(),
+        program_account(counter_data),
+    );
+    let (mut banks_client, _, recent_blockhash) = program_test.start().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[build_instr(user.pubkey())],
+        Some(&user.pubkey()),
+        &[&user],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.map_err(|e| e.unwrap())
+}
+
+/// The program ran to the end, with or without its own error, so the compute budget was enough
+fn completed(result: &Result<(), TransactionError>) -> bool {
+    matches!(result, Ok(()) | Err(TransactionError::InstructionError(_, InstructionError::Custom(_))))
+}
+
+/// Minimal compute budget the operation of `build` completes with
+async fn measure(build: &Build, build_instr: fn(Pubkey) -> Instruction, frozen: bool) -> u64 {
+    let expected = if frozen { Err(custom_err(0, CounterError::Frozen)) } else { Ok(()) };
+    assert_eq!(run_operation(build, build_instr, frozen, MAX_TRANSACTION_UNITS).await, expected);
+
+    let (mut failed, mut succeeded) = (0, MAX_TRANSACTION_UNITS);
+    while succeeded - failed > 1 {
+        let units = (failed + succeeded) / 2;
+        if completed(&run_operation(build, build_instr, frozen, units).await) {
+            succeeded = units;
+        } else {
+            failed = units;
+        }
+    }
+    succeeded
+}
+
+#[tokio::test]
+async fn operations_fit_compute_budget() {
+    for (name, build_instr) in operations() {
+        assert!(
+            run_operation(&Build::Tested, build_instr, false, MAX_OPERATION_UNITS).await.is_ok(),
+            "{} exceeds {} compute units of {} build",
+            name,
+            MAX_OPERATION_UNITS,
+            LOG_CONFIG,
+        );
+    }
+}
+
+/// Zero-copy accounts must be cheaper than Borsh deserialization for every operation
+#[cfg(not(feature = "log-errors"))]
+#[tokio::test]
+async fn zero_copy_saves_compute_units() {
+    let borsh_build = Build::with_feature("borsh-accounts");
+    for (name, build_instr) in operations() {
+        let borsh = measure(&borsh_build, build_instr, false).await;
+        let zero_copy = measure(&Build::Tested, build_instr, false).await;
+        println!(
+            "{}: borsh {} zero-copy {} compute units, saved {} ({}%)",
+            name,
+            borsh,
+            zero_copy,
+            borsh.saturating_sub(zero_copy),
+            borsh.saturating_sub(zero_copy) * 100 / borsh,
+        );
+        assert!(zero_copy < borsh, "{} costs {} with zero-copy and {} with borsh", name, zero_copy, borsh);
+    }
+}
+
+/// Every logging configuration costs more than the previous one.
+/// Errors are logged on failure only, so `log-errors` is compared with the quiet build on a frozen counter
+#[cfg(not(feature = "log-errors"))]
+#[tokio::test]
+async fn logging_costs_compute_units() {
+    let log_errors_build = Build::with_feature("log-errors");
+    let log_verbose_build = Build::with_feature("log-verbose");
+    let mut cases: Vec<(Operation, bool)> = operations().iter().map(|&operation| (operation, false)).collect();
+    cases.push((("inc frozen", inc), true));
+    for ((name, build_instr), frozen) in cases {
+        let quiet = measure(&Build::Tested, build_instr, frozen).await;
+        let log_errors = measure(&log_errors_build, build_instr, frozen).await;
+        let log_verbose = measure(&log_verbose_build, build_instr, frozen).await;
+        println!("{}: quiet {} log-errors {} log-verbose {} compute units", name, quiet, log_errors, log_verbose);
+        if frozen {
+            assert!(quiet < log_errors, "{} costs {} quiet and {} with log-errors", name, quiet, log_errors);
+        } else {
+            assert!(quiet <= log_errors, "{} costs {} quiet and {} with log-errors", name, quiet, log_errors);
+        }
+        assert!(
+            log_errors < log_verbose,
+            "{} costs {} with log-errors and {} with log-verbose",
+            name,
+            log_errors,
+            log_verbose,
+        );
+    }
+}
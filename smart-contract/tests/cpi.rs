@@ -0,0 +1,167 @@
+#![cfg(feature = "test-bpf")]
+
+//! `counter::cpi` exists only with `no-entrypoint`, a BPF build of the program has no entrypoint with it,
+//! so the test runs the program natively: `cargo test --features test-bpf,no-entrypoint --test cpi`
+
+mod common;
+
+use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_program::sysvar;
+use solana_program_test::processor;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use counter::{cpi, id};
+use counter::state::{Counter, Settings};
+use common::{get_counter, program_test, send, Env};
+
+const CALLER_ID: Pubkey = Pubkey::new_from_array([7_u8; 32]);
+const CALLER_SEED: &[u8] = b"caller";
+
+/// Caller program instructions, the first byte of instruction data
+const CALLER_INC: u8 = 0;
+const CALLER_DEC: u8 = 1;
+const CALLER_INC_AS_PDA: u8 = 2;
+const CALLER_CREATE_PDA_COUNTER: u8 = 3;
+
+/// Tiny program calling the counter program
+fn caller_process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let (_, bump) = Pubkey::find_program_address(&[CALLER_SEED], program_id);
+    let bump_ref = &[bump];
+    let signer_seeds: &[&[u8]] = &[CALLER_SEED, bump_ref];
+
+    match data.first() {
+        Some(&CALLER_INC) => cpi::inc(operation_accounts(accounts)?, &[]),
+        Some(&CALLER_DEC) => cpi::dec(operation_accounts(accounts)?, &[]),
+        Some(&CALLER_INC_AS_PDA) => cpi::inc(operation_accounts(accounts)?, &[signer_seeds]),
+        Some(&CALLER_CREATE_PDA_COUNTER) => create_pda_counter(accounts, signer_seeds),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn operation_accounts<'a>(accounts: &[AccountInfo<'a>]) -> Result<cpi::Operation<'a>, ProgramError> {
+    let acc_iter = &mut accounts.iter();
+    Ok(cpi::Operation {
+        counter_program: next_account_info(acc_iter)?.clone(),
+        user: next_account_info(acc_iter)?.clone(),
+        counter: next_account_info(acc_iter)?.clone(),
+        settings: next_account_info(acc_iter)?.clone(),
+        access_page: next_account_info(acc_iter).ok().cloned(),
+    })
+}
+
+fn create_pda_counter(accounts: &[AccountInfo], signer_seeds: &[&[u8]]) -> ProgramResult {
+    let acc_iter = &mut accounts.iter();
+    let create_accounts = cpi::Create {
+        counter_program: next_account_info(acc_iter)?.clone(),
+        payer: next_account_info(acc_iter)?.clone(),
+        user: next_account_info(acc_iter)?.clone(),
+        counter: next_account_info(acc_iter)?.clone(),
+        rent: next_account_info(acc_iter)?.clone(),
+        system_program: next_account_info(acc_iter)?.clone(),
+    };
+    cpi::create(create_accounts, "", &[signer_seeds])
+}
+
+async fn start() -> Env {
+    let mut program_test = program_test();
+    program_test.add_program("caller", CALLER_ID, processor!(caller_process_instruction));
+    Env::start(program_test).await
+}
+
+fn operation_metas(program: Pubkey, user: Pubkey, user_is_signer: bool) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(program, false),
+        AccountMeta::new_readonly(user, user_is_signer),
+        AccountMeta::new(Counter::generate_counter_pk(&user, "").unwrap(), false),
+        AccountMeta::new_readonly(Settings::get_settings_pk_with_bump().0, false),
+    ]
+}
+
+#[tokio::test]
+async fn cpi_inc() {
+    let env = start().await;
+    let user = env.user;
+    let mut ctx = env.ctx;
+
+    let instr = Instruction::new_with_bytes(
+        CALLER_ID,
+        &[CALLER_INC],
+        operation_metas(id(), user.pubkey(), true),
+    );
+    send(&mut ctx, &[instr], &user, &[]).await.unwrap();
+
+    assert_eq!(get_counter(&mut ctx, &user.pubkey(), "").await.value, 9);
+}
+
+#[tokio::test]
+async fn cpi_dec() {
+    let env = start().await;
+    let user = env.user;
+    let mut ctx = env.ctx;
+
+    let instr = Instruction::new_with_bytes(
+        CALLER_ID,
+        &[CALLER_DEC],
+        operation_metas(id(), user.pubkey(), true),
+    );
+    send(&mut ctx, &[instr], &user, &[]).await.unwrap();
+
+    assert_eq!(get_counter(&mut ctx, &user.pubkey(), "").await.value, -5);
+}
+
+#[tokio::test]
+async fn cpi_inc_signed_by_pda() {
+    let env = start().await;
+    let user = env.user;
+    let mut ctx = env.ctx;
+    let (pda, _) = Pubkey::find_program_address(&[CALLER_SEED], &CALLER_ID);
+
+    let create_instr = Instruction::new_with_bytes(
+        CALLER_ID,
+        &[CALLER_CREATE_PDA_COUNTER],
+        vec![
+            AccountMeta::new_readonly(id(), false),
+            AccountMeta::new(user.pubkey(), true),
+            AccountMeta::new_readonly(pda, false),
+            AccountMeta::new(Counter::generate_counter_pk(&pda, "").unwrap(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    );
+    send(&mut ctx, &[create_instr], &user, &[]).await.unwrap();
+
+    let inc_instr = Instruction::new_with_bytes(
+        CALLER_ID,
+        &[CALLER_INC_AS_PDA],
+        operation_metas(id(), pda, false),
+    );
+    send(&mut ctx, &[inc_instr], &user, &[]).await.unwrap();
+
+    assert_eq!(get_counter(&mut ctx, &pda, "").await.value, 9);
+}
+
+#[tokio::test]
+async fn cpi_wrong_counter_program() {
+    let env = start().await;
+    let user = env.user;
+    let mut ctx = env.ctx;
+
+    let instr = Instruction::new_with_bytes(
+        CALLER_ID,
+        &[CALLER_INC],
+        operation_metas(system_program::id(), user.pubkey(), true),
+    );
+    let err = send(&mut ctx, &[instr], &user, &[]).await.unwrap_err();
+
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::IncorrectProgramId));
+    assert_eq!(get_counter(&mut ctx, &user.pubkey(), "").await.value, 0);
+}
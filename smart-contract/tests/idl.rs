@@ -0,0 +1,671 @@
+//! JSON IDL of the program at `idl/counter.json` is generated from the Rust definitions.
+//! Instruction discriminants and account metas are taken from the instruction builders,
+//! instruction arguments and types from their Borsh schema, error codes from `CounterError`,
+//! and every type is checked against its Borsh serialization.
+//! Every `CounterInstruction` variant must be listed, their number is taken from the Borsh schema.
+//! Regenerate the file after changing the program: `UPDATE_IDL=1 cargo test --test idl`
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use serde_json::{json, Value};
+use solana_program::instruction::{Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_program::{system_program, sysvar};
+use counter::instruction::{CounterInstruction, OperationKind, RelayedMessage};
+use counter::state::{AccessMode, AccessPage, Counter, ResetPeriod, Role, Roles, Session, Settings};
+use counter::error::CounterError;
+use counter::{id, ACCESS_PAGE_CAPACITY, ACCESS_SEED, COUNTER_SEED, MAX_ROLE_MEMBERS, ROLES_SEED, SESSION_SEED, SETTINGS_SEED};
+use borsh::schema::{Declaration, Definition, Fields};
+use borsh::{BorshSchema, BorshSerialize};
+
+const IDL_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/idl/counter.json");
+
+fn key(byte: u8) -> Pubkey {
+    Pubkey::new_from_array([byte; 32])
+}
+
+fn user() -> Pubkey {
+    key(1)
+}
+
+fn admin() -> Pubkey {
+    key(2)
+}
+
+fn session_key() -> Pubkey {
+    key(3)
+}
+
+/// Instruction described by its builder and names of its accounts, arguments are taken from the Borsh schema
+struct IdlInstruction {
+    instr: Instruction,
+    accounts: &'static [&'static str],
+    optional_accounts: usize,
+    /// Argument values of `instr`, checked against its Borsh data
+    sample: Value,
+}
+
+fn idl_instructions() -> Vec<IdlInstruction> {
+    // Other access pages of the bucket follow the first one, a single page stands for them
+    let operation_accounts: &[&str] = &["owner", "counter", "settings", "access_page", "access_pages"];
+    let with_access_pages = |instr| CounterInstruction::with_access_pages(instr, &user(), 2);
+    let moderation_accounts: &[&str] = &["admin", "settings", "counter", "roles"];
+    vec![
+        IdlInstruction {
+            instr: with_access_pages(CounterInstruction::inc_instr(user(), "").unwrap()),
+            accounts: operation_accounts,
+            optional_accounts: 2,
+            sample: json!({}),
+        },
+        IdlInstruction {
+            instr: with_access_pages(CounterInstruction::dec_instr(user(), "").unwrap()),
+            accounts: operation_accounts,
+            optional_accounts: 2,
+            sample: json!({}),
+        },
+        IdlInstruction {
+            instr: with_access_pages(CounterInstruction::reset_instr(user(), "").unwrap()),
+            accounts: operation_accounts,
+            optional_accounts: 2,
+            sample: json!({}),
+        },
+        IdlInstruction {
+            instr: CounterInstruction::upd_sett_instr(admin(), user(), 3, 4),
+            accounts: &["admin", "settings", "rent", "system_program", "roles"],
+            optional_accounts: 1,
+            sample: json!({"admin": user().to_string(), "inc_step": 3, "dec_step": 4}),
+        },
+        IdlInstruction {
+            instr: CounterInstruction::create_instr(admin(), user(), "name").unwrap(),
+            accounts: &["payer", "owner", "counter", "rent", "system_program"],
+            optional_accounts: 0,
+            sample: json!({"name": "name"}),
+        },
+        IdlInstruction {
+            instr: CounterInstruction::set_label_instr(user(), "", "label").unwrap(),
+            accounts: &["owner", "counter"],
+            optional_accounts: 0,
+            sample: json!({"label": "label"}),
+        },
+        IdlInstruction {
+            instr: CounterInstruction::set_reset_period_instr(admin(), ResetPeriod::Slots(10)),
+            accounts: &["admin", "settings", "roles"],
+            optional_accounts: 1,
+            sample: json!({"period": {"Slots": [10]}}),
+        },
+        IdlInstruction {
+            instr: CounterInstruction::freeze_instr(admin(), user(), "").unwrap(),
+            accounts: moderation_accounts,
+            optional_accounts: 1,
+            sample: json!({}),
+        },
+        IdlInstruction {
+            instr: CounterInstruction::thaw_instr(admin(), user(), "").unwrap(),
+            accounts: moderation_accounts,
+            optional_accounts: 1,
+            sample: json!({}),
+        },
+        IdlInstruction {
+            instr: CounterInstruction::admin_reset_instr(admin(), user(), "").unwrap(),
+            accounts: moderation_accounts,
+            optional_accounts: 1,
+            sample: json!({}),
+        },
+        IdlInstruction {
+            instr: CounterInstruction::set_access_mode_instr(admin(), AccessMode::Denylist),
+            accounts: &["admin", "settings", "roles"],
+            optional_accounts: 1,
+            sample: json!({"mode": "Denylist"}),
+        },
+        IdlInstruction {
+            instr: CounterInstruction::add_access_instr(admin(), user(), 1),
+            accounts: &["admin", "settings", "access_page", "rent", "system_program", "roles", "access_pages"],
+            optional_accounts: 2,
+            sample: json!({"user": user().to_string()}),
+        },
+        IdlInstruction {
+            instr: CounterInstruction::remove_access_instr(admin(), user(), 2),
+            accounts: &["admin", "settings", "access_page", "roles", "access_pages"],
+            optional_accounts: 2,
+            sample: json!({"user": user().to_string()}),
+        },
+        IdlInstruction {
+            instr: CounterInstruction::grant_role_instr(admin(), user(), Role::Moderator),
+            accounts: &["admin", "settings", "roles", "rent", "system_program"],
+            optional_accounts: 0,
+            sample: json!({"user": user().to_string(), "role": "Moderator"}),
+        },
+        IdlInstruction {
+            instr: CounterInstruction::revoke_role_instr(admin(), user(), Role::SettingsManager),
+            accounts: &["admin", "settings", "roles"],
+            optional_accounts: 0,
+            sample: json!({"user": user().to_string(), "role": "SettingsManager"}),
+        },
+        IdlInstruction {
+            instr: with_access_pages(CounterInstruction::relayed_instr(user(), "", OperationKind::Dec, 7, -8).unwrap()),
+            accounts: &["owner", "counter", "settings", "instructions", "access_page", "access_pages"],
+            optional_accounts: 2,
+            sample: json!({"op": "Dec", "nonce": 7, "expiry": -8}),
+        },
+        IdlInstruction {
+            instr: CounterInstruction::create_session_instr(
+                user(),
+                session_key(),
+                1000,
+                &[OperationKind::Inc, OperationKind::Dec],
+            ),
+            accounts: &["owner", "session", "rent", "system_program"],
+            optional_accounts: 0,
+            sample: json!({"session_key": session_key().to_string(), "expiry": 1000, "ops": ["Inc", "Dec"]}),
+        },
+        IdlInstruction {
+            instr: CounterInstruction::revoke_session_instr(user(), session_key()),
+            accounts: &["owner", "session"],
+            optional_accounts: 0,
+            sample: json!({"session_key": session_key().to_string()}),
+        },
+        IdlInstruction {
+            instr: with_access_pages(
+                CounterInstruction::session_op_instr(session_key(), user(), "", OperationKind::Inc).unwrap(),
+            ),
+            accounts: &["session_key", "owner", "counter", "settings", "session", "access_page", "access_pages"],
+            optional_accounts: 2,
+            sample: json!({"op": "Inc"}),
+        },
+    ]
+}
+
+/// IDL type of a Borsh schema declaration, types defined in the schema are referred by their name
+fn idl_type(declaration: &str, definitions: &HashMap<Declaration, Definition>) -> Value {
+    match definitions.get(declaration) {
+        _ if declaration == "Pubkey" => json!("publicKey"),
+        Some(Definition::Array { length, elements }) => json!({"array": [idl_type(elements, definitions), length]}),
+        Some(Definition::Sequence { elements }) => json!({"vec": idl_type(elements, definitions)}),
+        Some(_) => json!({"defined": declaration}),
+        None => json!(declaration),
+    }
+}
+
+/// Named fields are `[{name, type}]`, unnamed ones are `[type]`
+fn idl_fields(fields: &Fields, definitions: &HashMap<Declaration, Definition>) -> Value {
+    match fields {
+        Fields::NamedFields(fields) => fields
+            .iter()
+            .map(|(name, ty)| json!({"name": name, "type": idl_type(ty, definitions)}))
+            .collect(),
+        Fields::UnnamedFields(types) => types.iter().map(|ty| idl_type(ty, definitions)).collect(),
+        Fields::Empty => json!([]),
+    }
+}
+
+/// Struct with its fields or enum with its variants from the Borsh schema of `T`
+fn idl_definition<T: BorshSchema>() -> Value {
+    let container = T::schema_container();
+    let definitions = &container.definitions;
+    match &definitions[&container.declaration] {
+        Definition::Struct { fields } => json!({"kind": "struct", "fields": idl_fields(fields, definitions)}),
+        Definition::Enum { variants } => {
+            let variants: Vec<Value> = variants
+                .iter()
+                .map(|(name, declaration)| match &definitions[declaration] {
+                    Definition::Struct { fields: Fields::Empty } => json!({"name": name}),
+                    Definition::Struct { fields } => json!({"name": name, "fields": idl_fields(fields, definitions)}),
+                    definition => panic!("variant {} is {:?}", name, definition),
+                })
+                .collect();
+            json!({"kind": "enum", "variants": variants})
+        }
+        definition => panic!("{} is {:?}", container.declaration, definition),
+    }
+}
+
+fn idl_named_type<T: BorshSchema>() -> Value {
+    json!({"name": T::declaration(), "type": idl_definition::<T>()})
+}
+
+/// `CounterInstruction` variants with their arguments as fields, in the order of discriminants
+fn instruction_variants() -> Vec<Value> {
+    idl_definition::<CounterInstruction>()["variants"].as_array().unwrap().clone()
+}
+
+/// Name of the `CounterInstruction` variant encoded in `instr`
+fn instruction_name(instr: &Instruction) -> String {
+    instruction_variants()[instr.data[0] as usize]["name"].as_str().unwrap().to_string()
+}
+
+/// Accounts with a fixed address
+fn fixed_address(pubkey: &Pubkey) -> Option<Pubkey> {
+    let fixed = [
+        Settings::get_settings_pk_with_bump().0,
+        Roles::get_roles_pk_with_bump().0,
+        sysvar::rent::id(),
+        sysvar::instructions::id(),
+        system_program::id(),
+    ];
+    fixed.iter().find(|fixed| *fixed == pubkey).copied()
+}
+
+fn idl_types() -> Value {
+    json!([
+        idl_named_type::<OperationKind>(),
+        idl_named_type::<ResetPeriod>(),
+        idl_named_type::<AccessMode>(),
+        idl_named_type::<Role>(),
+        idl_named_type::<RelayedMessage>(),
+    ])
+}
+
+fn const_seed(value: &str) -> Value {
+    json!({"kind": "const", "value": value})
+}
+
+fn arg_seed(name: &str, ty: &str) -> Value {
+    json!({"kind": "arg", "name": name, "type": ty})
+}
+
+fn idl_accounts() -> Value {
+    json!([
+        {
+            "name": "Counter",
+            "size": Counter::LEN,
+            // Name bytes are appended to the seed as is, without a length prefix
+            "address": {"withSeed": {"base": "owner", "seed": format!("{}{{name}}", COUNTER_SEED)}},
+            "type": idl_definition::<Counter>(),
+        },
+        {
+            "name": "Settings",
+            "size": Settings::LEN,
+            "address": {"pda": [const_seed(SETTINGS_SEED)]},
+            "type": idl_definition::<Settings>(),
+        },
+        {
+            "name": "AccessPage",
+            "size": AccessPage::LEN,
+            "address": {"pda": [const_seed(ACCESS_SEED), arg_seed("bucket", "u8"), arg_seed("index", "u8")]},
+            "type": idl_definition::<AccessPage>(),
+        },
+        {
+            "name": "Roles",
+            "size": Roles::LEN,
+            "address": {"pda": [const_seed(ROLES_SEED)]},
+            "type": idl_definition::<Roles>(),
+        },
+        {
+            "name": "Session",
+            "size": Session::LEN,
+            "address": {"pda": [
+                const_seed(SESSION_SEED),
+                arg_seed("owner", "publicKey"),
+                arg_seed("session_key", "publicKey"),
+            ]},
+            "type": idl_definition::<Session>(),
+        },
+    ])
+}
+
+fn idl_errors() -> Value {
+    let mut errors = vec![];
+    let mut code = 0;
+    while let Some(err) = CounterError::from_instruction_error(&InstructionError::Custom(code)) {
+        errors.push(json!({"code": code, "name": format!("{:?}", err), "msg": err.to_string()}));
+        code += 1;
+    }
+    Value::Array(errors)
+}
+
+fn generate_idl() -> Value {
+    let instructions: Vec<Value> = idl_instructions()
+        .iter()
+        .map(|idl_instr| {
+            assert_eq!(
+                idl_instr.instr.accounts.len(),
+                idl_instr.accounts.len(),
+                "account names of {}",
+                instruction_name(&idl_instr.instr),
+            );
+            let required = idl_instr.accounts.len() - idl_instr.optional_accounts;
+            let accounts: Vec<Value> = idl_instr
+                .instr
+                .accounts
+                .iter()
+                .zip(idl_instr.accounts)
+                .enumerate()
+                .map(|(index, (meta, name))| {
+                    let mut account = json!({
+                        "name": name,
+                        "isMut": meta.is_writable,
+                        "isSigner": meta.is_signer,
+                        "optional": index >= required,
+                    });
+                    if let Some(address) = fixed_address(&meta.pubkey) {
+                        account["address"] = json!(address.to_string());
+                    }
+                    account
+                })
+                .collect();
+            let variant = &instruction_variants()[idl_instr.instr.data[0] as usize];
+            json!({
+                "name": variant["name"],
+                "discriminant": idl_instr.instr.data[0],
+                "accounts": accounts,
+                "args": variant.get("fields").cloned().unwrap_or_else(|| json!([])),
+            })
+        })
+        .collect();
+
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "name": "counter",
+        "programId": id().to_string(),
+        "instructions": instructions,
+        "accounts": idl_accounts(),
+        "types": idl_types(),
+        "errors": idl_errors(),
+    })
+}
+
+/// Borsh serialization of `value` of IDL type `ty`
+fn encode(ty: &Value, value: &Value, out: &mut Vec<u8>) {
+    match ty {
+        Value::String(name) => match name.as_str() {
+            "bool" => out.push(value.as_bool().unwrap() as u8),
+            "u8" => out.push(value.as_u64().unwrap() as u8),
+            "u32" => out.extend((value.as_u64().unwrap() as u32).to_le_bytes()),
+            "u64" => out.extend(value.as_u64().unwrap().to_le_bytes()),
+            "i64" => out.extend(value.as_i64().unwrap().to_le_bytes()),
+            "publicKey" => out.extend(Pubkey::from_str(value.as_str().unwrap()).unwrap().to_bytes()),
+            "string" => {
+                let s = value.as_str().unwrap();
+                out.extend((s.len() as u32).to_le_bytes());
+                out.extend(s.as_bytes());
+            }
+            _ => panic!("unknown type {}", name),
+        },
+        Value::Object(ty) => {
+            if let Some(elem_ty) = ty.get("vec") {
+                let values = value.as_array().unwrap();
+                out.extend((values.len() as u32).to_le_bytes());
+                values.iter().for_each(|value| encode(elem_ty, value, out));
+            } else if let Some(array) = ty.get("array") {
+                let values = value.as_array().unwrap();
+                assert_eq!(values.len() as u64, array[1].as_u64().unwrap());
+                values.iter().for_each(|value| encode(&array[0], value, out));
+            } else if let Some(name) = ty.get("defined") {
+                let types = idl_types();
+                let defined = types
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .find(|defined| &defined["name"] == name)
+                    .unwrap_or_else(|| panic!("unknown type {}", name));
+                encode_defined(&defined["type"], value, out);
+            } else {
+                panic!("unknown type {:?}", ty);
+            }
+        }
+        _ => panic!("unknown type {:?}", ty),
+    }
+}
+
+fn encode_defined(ty: &Value, value: &Value, out: &mut Vec<u8>) {
+    match ty["kind"].as_str().unwrap() {
+        "struct" => encode_fields(&ty["fields"], value, out),
+        "enum" => {
+            // Unit variant is its name, variant with fields is `{name: [fields]}`
+            let (name, fields) = match value {
+                Value::String(name) => (name.clone(), json!([])),
+                Value::Object(variant) => {
+                    let (name, fields) = variant.iter().next().unwrap();
+                    (name.clone(), fields.clone())
+                }
+                _ => panic!("wrong enum value {:?}", value),
+            };
+            let variants = ty["variants"].as_array().unwrap();
+            let index = variants.iter().position(|variant| variant["name"] == name).unwrap();
+            out.push(index as u8);
+            let field_types = variants[index]["fields"].as_array().cloned().unwrap_or_default();
+            assert_eq!(field_types.len(), fields.as_array().unwrap().len());
+            for (field_ty, field) in field_types.iter().zip(fields.as_array().unwrap()) {
+                encode(field_ty, field, out);
+            }
+        }
+        kind => panic!("unknown kind {}", kind),
+    }
+}
+
+/// Fields are `[{name, type}]`, `value` is an object with them
+fn encode_fields(fields: &Value, value: &Value, out: &mut Vec<u8>) {
+    let fields = fields.as_array().unwrap();
+    assert_eq!(fields.len(), value.as_object().unwrap().len());
+    for field in fields {
+        let name = field["name"].as_str().unwrap();
+        let field_value = value.get(name).unwrap_or_else(|| panic!("no value for {}", name));
+        encode(&field["type"], field_value, out);
+    }
+}
+
+/// Size of a fixed size IDL type
+fn size_of(ty: &Value) -> usize {
+    match ty.as_str() {
+        Some("bool") | Some("u8") => 1,
+        Some("u32") => 4,
+        Some("u64") | Some("i64") => 8,
+        Some("publicKey") => 32,
+        Some(name) => panic!("{} has no fixed size", name),
+        None => {
+            let array = &ty["array"];
+            size_of(&array[0]) * array[1].as_u64().unwrap() as usize
+        }
+    }
+}
+
+fn find<'a>(items: &'a Value, name: &str) -> &'a Value {
+    items
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item["name"] == name)
+        .unwrap_or_else(|| panic!("{} is not found", name))
+}
+
+fn pubkeys(keys: &[Pubkey]) -> Vec<String> {
+    keys.iter().map(Pubkey::to_string).collect()
+}
+
+#[test]
+fn idl_file_is_up_to_date() {
+    let generated = serde_json::to_string_pretty(&generate_idl()).unwrap() + "\n";
+    if std::env::var("UPDATE_IDL").is_ok() {
+        std::fs::create_dir_all(std::path::Path::new(IDL_PATH).parent().unwrap()).unwrap();
+        std::fs::write(IDL_PATH, &generated).unwrap();
+    }
+    let file = std::fs::read_to_string(IDL_PATH).unwrap();
+
+    assert!(
+        file == generated,
+        "{} is out of date, run `UPDATE_IDL=1 cargo test --test idl`",
+        IDL_PATH,
+    );
+}
+
+#[test]
+fn idl_instruction_args_match_borsh() {
+    let idl = generate_idl();
+    for idl_instr in idl_instructions() {
+        let name = instruction_name(&idl_instr.instr);
+        let instruction = find(&idl["instructions"], &name);
+        let mut data = vec![instruction["discriminant"].as_u64().unwrap() as u8];
+        encode_fields(&instruction["args"], &idl_instr.sample, &mut data);
+
+        assert_eq!(data, idl_instr.instr.data, "{}", name);
+    }
+}
+
+/// A new `CounterInstruction` variant fails this until it is listed in `idl_instructions`
+#[test]
+fn idl_lists_every_instruction() {
+    let idl = generate_idl();
+    let mut discriminants: Vec<u64> = idl["instructions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|instruction| instruction["discriminant"].as_u64().unwrap())
+        .collect();
+    discriminants.sort_unstable();
+
+    assert_eq!(discriminants, (0..instruction_variants().len() as u64).collect::<Vec<_>>());
+}
+
+#[test]
+fn idl_types_match_borsh() {
+    let types = idl_types();
+    let message = RelayedMessage { program_id: id(), counter: user(), op: OperationKind::Dec, nonce: 1, expiry: 2 };
+    let samples = [
+        ("RelayedMessage", json!({
+            "program_id": id().to_string(),
+            "counter": user().to_string(),
+            "op": "Dec",
+            "nonce": 1,
+            "expiry": 2,
+        }), message.try_to_vec().unwrap()),
+        ("ResetPeriod", json!("Never"), ResetPeriod::Never.try_to_vec().unwrap()),
+        ("ResetPeriod", json!({"UnixTime": [5]}), ResetPeriod::UnixTime(5).try_to_vec().unwrap()),
+        ("ResetPeriod", json!({"Epochs": [6]}), ResetPeriod::Epochs(6).try_to_vec().unwrap()),
+        ("AccessMode", json!("Allowlist"), AccessMode::Allowlist.try_to_vec().unwrap()),
+        ("Role", json!("SuperAdmin"), Role::SuperAdmin.try_to_vec().unwrap()),
+        ("Role", json!("Moderator"), Role::Moderator.try_to_vec().unwrap()),
+    ];
+    for (name, value, borsh) in samples.iter() {
+        let mut data = vec![];
+        encode_defined(&find(&types, name)["type"], value, &mut data);
+
+        assert_eq!(&data, borsh, "{}", name);
+    }
+}
+
+#[test]
+fn idl_accounts_match_borsh() {
+    let accounts = idl_accounts();
+
+    let mut counter = Counter::new(user(), "name", 1);
+    counter.value = -5;
+    counter.updated_at = 2;
+    counter.op_count = 3;
+    counter.period_id = 4;
+    counter.relay_nonce = 5;
+    counter.frozen = true;
+    counter.label_len = 1;
+    counter.label[0] = b'l';
+    let settings = Settings {
+        admin: admin(),
+        inc_step: 9,
+        dec_step: 5,
+        reset_period_kind: 2,
+        reset_period: 86400,
+        access_mode: 1,
+    };
+    let mut access_page =
+        AccessPage { bucket: 7, index: 0, pages: 2, len: 1, keys: [Pubkey::default(); ACCESS_PAGE_CAPACITY] };
+    access_page.keys[0] = key(7);
+    let mut roles = Roles { len: 1, keys: [Pubkey::default(); MAX_ROLE_MEMBERS], roles: [0; MAX_ROLE_MEMBERS] };
+    roles.keys[0] = admin();
+    roles.roles[0] = Role::Moderator.mask();
+    let session = Session { owner: user(), session_key: session_key(), expiry: 1000, allowed_ops: 3 };
+
+    let samples = [
+        ("Counter", json!({
+            "value": counter.value,
+            "owner": counter.owner.to_string(),
+            "created_at": counter.created_at,
+            "updated_at": counter.updated_at,
+            "op_count": counter.op_count,
+            "period_id": counter.period_id,
+            "relay_nonce": counter.relay_nonce,
+            "frozen": counter.frozen,
+            "label_len": counter.label_len,
+            "label": counter.label.to_vec(),
+            "name_len": counter.name_len,
+            "name": counter.name.to_vec(),
+        }), counter.try_to_vec().unwrap()),
+        ("Settings", json!({
+            "admin": settings.admin.to_string(),
+            "inc_step": settings.inc_step,
+            "dec_step": settings.dec_step,
+            "reset_period_kind": settings.reset_period_kind,
+            "reset_period": settings.reset_period,
+            "access_mode": settings.access_mode,
+        }), settings.try_to_vec().unwrap()),
+        ("AccessPage", json!({
+            "bucket": access_page.bucket,
+            "index": access_page.index,
+            "pages": access_page.pages,
+            "len": access_page.len,
+            "keys": pubkeys(&access_page.keys),
+        }), access_page.try_to_vec().unwrap()),
+        ("Roles", json!({
+            "len": roles.len,
+            "keys": pubkeys(&roles.keys),
+            "roles": roles.roles.to_vec(),
+        }), roles.try_to_vec().unwrap()),
+        ("Session", json!({
+            "owner": session.owner.to_string(),
+            "session_key": session.session_key.to_string(),
+            "expiry": session.expiry,
+            "allowed_ops": session.allowed_ops,
+        }), session.try_to_vec().unwrap()),
+    ];
+    for (name, value, borsh) in samples.iter() {
+        let account = find(&accounts, name);
+        let mut data = vec![];
+        encode_defined(&account["type"], value, &mut data);
+        let size: usize = account["type"]["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|field| size_of(&field["type"]))
+            .sum();
+
+        assert_eq!(&data, borsh, "{}", name);
+        assert_eq!(size as u64, account["size"].as_u64().unwrap(), "{}", name);
+    }
+}
+
+#[test]
+fn idl_account_addresses_match_pda() {
+    let accounts = idl_accounts();
+    let bucket = AccessPage::bucket(&user());
+    let args = json!({
+        "bucket": bucket,
+        "index": 1,
+        "owner": user().to_string(),
+        "session_key": session_key().to_string(),
+    });
+    let derive = |name: &str| -> Pubkey {
+        let seeds: Vec<Vec<u8>> = find(&accounts, name)["address"]["pda"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|seed| match seed["kind"].as_str().unwrap() {
+                "const" => seed["value"].as_str().unwrap().as_bytes().to_vec(),
+                _ => {
+                    let mut bytes = vec![];
+                    encode(&seed["type"], &args[seed["name"].as_str().unwrap()], &mut bytes);
+                    bytes
+                }
+            })
+            .collect();
+        let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+        Pubkey::find_program_address(&seeds, &id()).0
+    };
+
+    assert_eq!(derive("Settings"), Settings::get_settings_pk_with_bump().0);
+    assert_eq!(derive("AccessPage"), AccessPage::get_access_page_pk_with_bump(bucket, 1).0);
+    assert_eq!(derive("Roles"), Roles::get_roles_pk_with_bump().0);
+    assert_eq!(derive("Session"), Session::get_session_pk_with_bump(&user(), &session_key()).0);
+
+    let counter_seed = find(&accounts, "Counter")["address"]["withSeed"]["seed"].as_str().unwrap().replace("{name}", "name");
+    assert_eq!(
+        Pubkey::create_with_seed(&user(), &counter_seed, &id()).unwrap(),
+        Counter::generate_counter_pk(&user(), "name").unwrap(),
+    );
+}
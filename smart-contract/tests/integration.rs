@@ -1,111 +1,19 @@
 #![cfg(feature = "test-bpf")]
 
+mod common;
+
 use std::borrow::Borrow;
-use solana_program::system_instruction;
-use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_program::clock::Clock;
+use solana_program::instruction::{Instruction, InstructionError};
 use solana_sdk::signature::Keypair;
 use solana_sdk::signer::Signer;
-use solana_sdk::transaction::Transaction;
-use counter::COUNTER_SEED;
+use solana_sdk::transaction::{Transaction, TransactionError};
 use counter::instruction::CounterInstruction;
-use counter::state::{Counter, Settings};
+use counter::state::{Counter, ResetPeriod, Settings};
+use counter::error::CounterError;
 use borsh::BorshDeserialize;
-use borsh::BorshSerialize;
-use counter::id;
-use counter::entrypoint::process_instruction;
-use solana_program::pubkey::Pubkey;
-
-struct Env {
-    ctx: ProgramTestContext,
-    admin: Keypair,
-    user: Keypair,
-}
-
-impl Env {
-    async fn new() -> Self {
-        let counter_program = ProgramTest::new("counter", id(), processor!(process_instruction));
-        let mut ctx = counter_program.start_with_context().await;
-
-        let admin = Keypair::new();
-        let user = Keypair::new();
-
-
-        // Deposit SOL
-        let admin_deposit_instr = system_instruction::transfer(
-            &ctx.payer.pubkey(),
-            &admin.pubkey(),
-            5_000_000_000,
-        );
-        let user_deposit_instr = system_instruction::transfer(
-            &ctx.payer.pubkey(),
-            &user.pubkey(),
-            3_000_000_000,
-        );
-        let deposit_tx = Transaction::new_signed_with_payer(
-            &[admin_deposit_instr, user_deposit_instr],
-            Some(&ctx.payer.pubkey()),
-            &[&ctx.payer],
-            ctx.last_blockhash,
-        );
-        ctx.banks_client.process_transaction(deposit_tx).await.unwrap();
-
-
-        // Update settings
-        let upd_sett_instr = CounterInstruction::upd_sett_instr(
-            admin.pubkey(),
-            admin.pubkey(),
-            9,
-            5,
-        );
-        let update_settings_tx = Transaction::new_signed_with_payer(
-            &[upd_sett_instr],
-            Some(&admin.pubkey()),
-            &[&admin],
-            ctx.last_blockhash,
-        );
-        ctx.banks_client.process_transaction(update_settings_tx).await.unwrap();
-
-        // Check settings account
-        let settings_pk = Settings::get_settings_pk_with_bump().0;
-        let settings_acc = ctx.banks_client.get_account(settings_pk).await.unwrap().unwrap();
-        let deserialized_settings = Settings::try_from_slice(settings_acc.data.borrow()).unwrap();
-        let inited_settings = Settings { admin: admin.pubkey(), inc_step: 9, dec_step: 5 };
-        assert_eq!(deserialized_settings, inited_settings);
-
-
-        // Init counter account
-        let counter = Counter { value: 0 };
-        let space = counter.try_to_vec().unwrap().len();
-        let rent = ctx.banks_client.get_rent().await.unwrap();
-        let rent_value = rent.minimum_balance(space);
-
-        let create_counter_instr = system_instruction::create_account_with_seed(
-            &user.pubkey(),
-            &Counter::generate_counter_pk(&user.pubkey()).unwrap(),
-            &user.pubkey(),
-            COUNTER_SEED,
-            rent_value,
-            space as u64,
-            &id(),
-        );
-        let create_counter_tx = Transaction::new_signed_with_payer(
-            &[create_counter_instr],
-            Some(&user.pubkey()),
-            &[&user],
-            ctx.last_blockhash,
-        );
-        ctx.banks_client.process_transaction(create_counter_tx).await.unwrap();
-
-
-        // Check counter account
-        let counter_pk = Counter::generate_counter_pk(&user.pubkey()).unwrap();
-        let counter_acc = ctx.banks_client.get_account(counter_pk).await.unwrap().unwrap();
-        let deserialized_counter = Counter::try_from_slice(&counter_acc.data.borrow()).unwrap();
-        assert_eq!(deserialized_counter, counter);
-
-        Env { ctx, admin, user }
-    }
-}
+use common::{create_counter, get_counter, send, Env};
+use solana_program_test::ProgramTestContext;
 
 #[tokio::test]
 async fn inc() {
@@ -113,7 +21,7 @@ async fn inc() {
     let user = env.user;
     let mut ctx = env.ctx;
 
-    let inc_instr = CounterInstruction::inc_instr(user.pubkey());
+    let inc_instr = CounterInstruction::inc_instr(user.pubkey(), "").unwrap();
     let inc_tx = Transaction::new_signed_with_payer(
         &[inc_instr],
         Some(&user.pubkey()),
@@ -124,7 +32,7 @@ async fn inc() {
 
     let counter_acc = ctx
         .banks_client
-        .get_account(Counter::generate_counter_pk(&user.pubkey()).unwrap())
+        .get_account(Counter::generate_counter_pk(&user.pubkey(), "").unwrap())
         .await
         .unwrap()
         .unwrap();
@@ -139,7 +47,7 @@ async fn dec() {
     let user = env.user;
     let mut ctx = env.ctx;
 
-    let dec_instr = CounterInstruction::dec_instr(user.pubkey());
+    let dec_instr = CounterInstruction::dec_instr(user.pubkey(), "").unwrap();
     let dec_tx = Transaction::new_signed_with_payer(
         &[dec_instr],
         Some(&user.pubkey()),
@@ -150,7 +58,7 @@ async fn dec() {
 
     let counter_acc = ctx
         .banks_client
-        .get_account(Counter::generate_counter_pk(&user.pubkey()).unwrap())
+        .get_account(Counter::generate_counter_pk(&user.pubkey(), "").unwrap())
         .await
         .unwrap()
         .unwrap();
@@ -165,7 +73,7 @@ async fn reset() {
     let user = env.user;
     let mut ctx = env.ctx;
 
-    let reset_instr = CounterInstruction::reset_instr(user.pubkey());
+    let reset_instr = CounterInstruction::reset_instr(user.pubkey(), "").unwrap();
     let reset_tx = Transaction::new_signed_with_payer(
         &[reset_instr],
         Some(&user.pubkey()),
@@ -176,7 +84,7 @@ async fn reset() {
 
     let counter_acc = ctx
         .banks_client
-        .get_account(Counter::generate_counter_pk(&user.pubkey()).unwrap())
+        .get_account(Counter::generate_counter_pk(&user.pubkey(), "").unwrap())
         .await
         .unwrap()
         .unwrap();
@@ -211,3 +119,376 @@ async fn upd_sett() {
     assert_eq!(settings.inc_step, 1);
     assert_eq!(settings.dec_step, 2);
 }
+
+#[tokio::test]
+async fn named_counters() {
+    let env = Env::new().await;
+    let user = env.user;
+    let mut ctx = env.ctx;
+
+    create_counter(&mut ctx, &user, "first").await;
+    create_counter(&mut ctx, &user, "second").await;
+
+    let inc_tx = Transaction::new_signed_with_payer(
+        &[CounterInstruction::inc_instr(user.pubkey(), "first").unwrap()],
+        Some(&user.pubkey()),
+        &[&user],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(inc_tx).await.unwrap();
+
+    let dec_tx = Transaction::new_signed_with_payer(
+        &[CounterInstruction::dec_instr(user.pubkey(), "second").unwrap()],
+        Some(&user.pubkey()),
+        &[&user],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(dec_tx).await.unwrap();
+
+    let first = get_counter(&mut ctx, &user.pubkey(), "first").await;
+    let second = get_counter(&mut ctx, &user.pubkey(), "second").await;
+    let default = get_counter(&mut ctx, &user.pubkey(), "").await;
+
+    assert_eq!((first.value, first.owner, first.name()), (9, user.pubkey(), "first"));
+    assert_eq!((second.value, second.owner, second.name()), (-5, user.pubkey(), "second"));
+    assert_eq!(default.value, 0);
+}
+
+#[tokio::test]
+async fn create_with_too_long_name() {
+    let env = Env::new().await;
+    let user = env.user;
+    let mut ctx = env.ctx;
+
+    let name = "a".repeat(counter::MAX_COUNTER_NAME_LEN + 1);
+    let mut create_instr = CounterInstruction::create_instr(user.pubkey(), user.pubkey(), "").unwrap();
+    create_instr.data = borsh::BorshSerialize::try_to_vec(&CounterInstruction::Create { name }).unwrap();
+    let create_tx = Transaction::new_signed_with_payer(
+        &[create_instr],
+        Some(&user.pubkey()),
+        &[&user],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(create_tx).await.unwrap_err().unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::CounterNameTooLong as u32),
+        ),
+    );
+}
+
+#[tokio::test]
+async fn inc_other_user_counter() {
+    let env = Env::new().await;
+    let user = env.user;
+    let mut ctx = env.ctx;
+
+    let other = Keypair::new();
+    let mut inc_instr = CounterInstruction::inc_instr(other.pubkey(), "").unwrap();
+    inc_instr.accounts[1].pubkey = Counter::generate_counter_pk(&user.pubkey(), "").unwrap();
+    let inc_tx = Transaction::new_signed_with_payer(
+        &[inc_instr],
+        Some(&user.pubkey()),
+        &[&user, &other],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(inc_tx).await.unwrap_err().unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::WrongCounterOwner as u32),
+        ),
+    );
+    assert_eq!(get_counter(&mut ctx, &user.pubkey(), "").await.value, 0);
+}
+
+#[tokio::test]
+async fn inc_wrong_settings() {
+    let env = Env::new().await;
+    let user = env.user;
+    let mut ctx = env.ctx;
+
+    let mut inc_instr = CounterInstruction::inc_instr(user.pubkey(), "").unwrap();
+    inc_instr.accounts[2].pubkey = Keypair::new().pubkey();
+    let inc_tx = Transaction::new_signed_with_payer(
+        &[inc_instr],
+        Some(&user.pubkey()),
+        &[&user],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(inc_tx).await.unwrap_err().unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::WrongSettingsPDA as u32),
+        ),
+    );
+}
+
+#[tokio::test]
+async fn set_label() {
+    let env = Env::new().await;
+    let user = env.user;
+    let mut ctx = env.ctx;
+
+    let set_label_instr = CounterInstruction::set_label_instr(user.pubkey(), "", "моя метка").unwrap();
+    let set_label_tx = Transaction::new_signed_with_payer(
+        &[set_label_instr],
+        Some(&user.pubkey()),
+        &[&user],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(set_label_tx).await.unwrap();
+
+    let counter = get_counter(&mut ctx, &user.pubkey(), "").await;
+
+    assert_eq!(counter.label(), "моя метка");
+    assert_eq!(counter.op_count, 0);
+}
+
+#[tokio::test]
+async fn set_too_long_label() {
+    let env = Env::new().await;
+    let user = env.user;
+    let mut ctx = env.ctx;
+
+    let label = "a".repeat(counter::MAX_COUNTER_LABEL_LEN + 1);
+    let set_label_instr = CounterInstruction::set_label_instr(user.pubkey(), "", &label).unwrap();
+    let set_label_tx = Transaction::new_signed_with_payer(
+        &[set_label_instr],
+        Some(&user.pubkey()),
+        &[&user],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(set_label_tx).await.unwrap_err().unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::CounterLabelTooLong as u32),
+        ),
+    );
+}
+
+#[tokio::test]
+async fn operations_update_metadata() {
+    let env = Env::new().await;
+    let user = env.user;
+    let mut ctx = env.ctx;
+    let created = get_counter(&mut ctx, &user.pubkey(), "").await;
+
+    // The clock moves between the operations, every one of them updates `updated_at`.
+    // The clock of a warped bank moves with the slots since the epoch start, so the slots are inside one epoch
+    let operations = [
+        (3_000, CounterInstruction::inc_instr(user.pubkey(), "").unwrap(), 1),
+        (3_200, CounterInstruction::dec_instr(user.pubkey(), "").unwrap(), 2),
+        (3_400, CounterInstruction::reset_instr(user.pubkey(), "").unwrap(), 3),
+        (3_600, CounterInstruction::set_label_instr(user.pubkey(), "", "label").unwrap(), 3),
+    ];
+    let mut updated_at = created.updated_at;
+    for (slot, instr, op_count) in operations {
+        let counter = apply_at(&mut ctx, &user, instr, Some(slot)).await;
+
+        assert_eq!(counter.op_count, op_count);
+        assert_eq!(counter.created_at, created.created_at);
+        assert!(counter.updated_at > updated_at);
+        updated_at = counter.updated_at;
+    }
+}
+
+/// Set the reset period, also after warping to another slot
+async fn set_reset_period(ctx: &mut ProgramTestContext, admin: &Keypair, period: ResetPeriod) {
+    let recent_blockhash = ctx.banks_client.get_recent_blockhash().await.unwrap();
+    let set_period_tx = Transaction::new_signed_with_payer(
+        &[CounterInstruction::set_reset_period_instr(admin.pubkey(), period)],
+        Some(&admin.pubkey()),
+        &[admin],
+        recent_blockhash,
+    );
+    ctx.banks_client.process_transaction(set_period_tx).await.unwrap();
+}
+
+/// Apply `instr` of `user` to the default counter after warping to `slot`, when `slot` is given
+async fn apply_at(ctx: &mut ProgramTestContext, user: &Keypair, instr: Instruction, slot: Option<u64>) -> Counter {
+    if let Some(slot) = slot {
+        ctx.warp_to_slot(slot).unwrap();
+    }
+    let recent_blockhash = ctx.banks_client.get_recent_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(&[instr], Some(&user.pubkey()), &[user], recent_blockhash);
+    ctx.banks_client.process_transaction(tx).await.unwrap();
+    get_counter(ctx, &user.pubkey(), "").await
+}
+
+/// Increment the default counter after warping to `slot`, when `slot` is given
+async fn inc_at(ctx: &mut ProgramTestContext, user: &Keypair, slot: Option<u64>) -> Counter {
+    apply_at(ctx, user, CounterInstruction::inc_instr(user.pubkey(), "").unwrap(), slot).await
+}
+
+#[tokio::test]
+async fn reset_period_slots() {
+    let env = Env::new().await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+    set_reset_period(&mut ctx, &admin, ResetPeriod::Slots(1_000)).await;
+
+    let first = inc_at(&mut ctx, &user, None).await;
+    assert_eq!(first.value, 9);
+
+    let second = inc_at(&mut ctx, &user, Some(500)).await;
+    assert_eq!((second.value, second.period_id), (18, first.period_id));
+
+    let third = inc_at(&mut ctx, &user, Some(1_500)).await;
+    assert_eq!(third.value, 9);
+    assert_ne!(third.period_id, first.period_id);
+}
+
+#[tokio::test]
+async fn reset_period_epochs() {
+    let env = Env::new().await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+    set_reset_period(&mut ctx, &admin, ResetPeriod::Epochs(1)).await;
+
+    let first = inc_at(&mut ctx, &user, None).await;
+    assert_eq!(first.value, 9);
+
+    let epoch = ctx.banks_client.get_sysvar::<Clock>().await.unwrap().epoch;
+    let next_epoch_slot = ctx.genesis_config().epoch_schedule.get_first_slot_in_epoch(epoch + 1);
+    let second = inc_at(&mut ctx, &user, Some(next_epoch_slot + 1)).await;
+
+    assert_eq!(second.value, 9);
+    assert_ne!(second.period_id, first.period_id);
+}
+
+#[tokio::test]
+async fn reset_period_never() {
+    let env = Env::new().await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+    set_reset_period(&mut ctx, &admin, ResetPeriod::Never).await;
+
+    inc_at(&mut ctx, &user, None).await;
+    let counter = inc_at(&mut ctx, &user, Some(100_000)).await;
+
+    assert_eq!(counter.value, 18);
+}
+
+#[tokio::test]
+async fn reset_period_switched_to_never() {
+    let env = Env::new().await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+    set_reset_period(&mut ctx, &admin, ResetPeriod::Slots(1_000)).await;
+    inc_at(&mut ctx, &user, None).await;
+    let counter = inc_at(&mut ctx, &user, Some(500)).await;
+    assert_eq!(counter.value, 18);
+
+    set_reset_period(&mut ctx, &admin, ResetPeriod::Never).await;
+    let counter = inc_at(&mut ctx, &user, Some(100_000)).await;
+
+    assert_eq!(counter.value, 27);
+}
+
+#[tokio::test]
+async fn set_reset_period_not_admin() {
+    let env = Env::new().await;
+    let user = env.user;
+    let mut ctx = env.ctx;
+
+    let set_period_tx = Transaction::new_signed_with_payer(
+        &[CounterInstruction::set_reset_period_instr(user.pubkey(), ResetPeriod::Slots(10))],
+        Some(&user.pubkey()),
+        &[&user],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(set_period_tx).await.unwrap_err().unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::AdminRequired as u32),
+        ),
+    );
+}
+
+#[tokio::test]
+async fn freeze_and_thaw() {
+    let env = Env::new().await;
+    let admin = env.admin;
+    let user = env.user;
+    let mut ctx = env.ctx;
+
+    let freeze_instr = CounterInstruction::freeze_instr(admin.pubkey(), user.pubkey(), "").unwrap();
+    send(&mut ctx, &[freeze_instr], &admin, &[]).await.unwrap();
+    assert!(get_counter(&mut ctx, &user.pubkey(), "").await.frozen);
+
+    for instr in vec![
+        CounterInstruction::dec_instr(user.pubkey(), "").unwrap(),
+        CounterInstruction::reset_instr(user.pubkey(), "").unwrap(),
+    ] {
+        let tx = Transaction::new_signed_with_payer(
+            &[instr],
+            Some(&user.pubkey()),
+            &[&user],
+            ctx.last_blockhash,
+        );
+        let err = ctx.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(CounterError::Frozen as u32),
+            ),
+        );
+    }
+
+    let thaw_instr = CounterInstruction::thaw_instr(admin.pubkey(), user.pubkey(), "").unwrap();
+    send(&mut ctx, &[thaw_instr], &admin, &[]).await.unwrap();
+    let counter = inc_at(&mut ctx, &user, None).await;
+
+    assert!(!counter.frozen);
+    assert_eq!(counter.value, 9);
+}
+
+#[tokio::test]
+async fn admin_reset_frozen_counter() {
+    let env = Env::new().await;
+    let admin = env.admin;
+    let user = env.user;
+    let mut ctx = env.ctx;
+
+    inc_at(&mut ctx, &user, None).await;
+    let freeze_instr = CounterInstruction::freeze_instr(admin.pubkey(), user.pubkey(), "").unwrap();
+    send(&mut ctx, &[freeze_instr], &admin, &[]).await.unwrap();
+    let admin_reset_instr = CounterInstruction::admin_reset_instr(admin.pubkey(), user.pubkey(), "").unwrap();
+    send(&mut ctx, &[admin_reset_instr], &admin, &[]).await.unwrap();
+
+    let counter = get_counter(&mut ctx, &user.pubkey(), "").await;
+    assert_eq!(counter.value, 0);
+    assert_eq!(counter.op_count, 1);
+    assert!(counter.frozen);
+}
+
+#[tokio::test]
+async fn freeze_not_admin() {
+    let env = Env::new().await;
+    let user = env.user;
+    let mut ctx = env.ctx;
+
+    let freeze_instr = CounterInstruction::freeze_instr(user.pubkey(), user.pubkey(), "").unwrap();
+    let err = send(&mut ctx, &[freeze_instr], &user, &[]).await.unwrap_err();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CounterError::AdminRequired as u32),
+        ),
+    );
+    assert!(!get_counter(&mut ctx, &user.pubkey(), "").await.frozen);
+}
@@ -0,0 +1,190 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use proptest::prelude::*;
+use solana_program::instruction::{Instruction, InstructionError};
+use solana_program::rent::Rent;
+use solana_program::system_instruction;
+use solana_program::system_program;
+use solana_program_test::ProgramTestContext;
+use solana_sdk::account::Account;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use counter::instruction::CounterInstruction;
+use counter::state::{Counter, Settings};
+use counter::error::CounterError;
+use counter::id;
+use borsh::{BorshDeserialize, BorshSerialize};
+use common::{get_counter, program_test, send, Env};
+
+/// Operation of a generated sequence
+#[derive(Clone, Debug)]
+enum Op {
+    Inc,
+    Dec,
+    Reset,
+    UpdSett { inc_step: u32, dec_step: u32 },
+    /// Settings update signed by the counter owner, who is not the admin
+    UpdSettByUser { inc_step: u32, dec_step: u32 },
+}
+
+/// Reference semantics of a counter and its settings
+#[derive(Debug, PartialEq)]
+struct Model {
+    value: i64,
+    op_count: u64,
+    inc_step: u32,
+    dec_step: u32,
+}
+
+impl Model {
+    /// State of the counter seeded with `value` by `start`
+    fn new(value: i64) -> Self {
+        Model { value, op_count: 0, inc_step: 9, dec_step: 5 }
+    }
+
+    /// Failed operation leaves the state unchanged
+    fn apply(&mut self, op: &Op) -> Result<(), CounterError> {
+        match *op {
+            Op::Inc => self.set_value(self.value.checked_add(self.inc_step as i64)),
+            Op::Dec => self.set_value(self.value.checked_sub(self.dec_step as i64)),
+            Op::Reset => self.set_value(Some(0)),
+            Op::UpdSett { inc_step, dec_step } => {
+                self.inc_step = inc_step;
+                self.dec_step = dec_step;
+                Ok(())
+            }
+            Op::UpdSettByUser { .. } => Err(CounterError::AdminRequired),
+        }
+    }
+
+    fn set_value(&mut self, value: Option<i64>) -> Result<(), CounterError> {
+        self.value = value.ok_or(CounterError::Overflow)?;
+        self.op_count += 1;
+        Ok(())
+    }
+}
+
+fn step() -> impl Strategy<Value = u32> {
+    prop_oneof![0..20_u32, any::<u32>()]
+}
+
+/// Values next to the bounds reach overflow in a few steps
+fn start_value() -> impl Strategy<Value = i64> {
+    prop_oneof![
+        2 => Just(0),
+        1 => (i64::MAX - 64)..=i64::MAX,
+        1 => i64::MIN..=(i64::MIN + 64),
+    ]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => Just(Op::Inc),
+        4 => Just(Op::Dec),
+        1 => Just(Op::Reset),
+        2 => (step(), step()).prop_map(|(inc_step, dec_step)| Op::UpdSett { inc_step, dec_step }),
+        1 => (step(), step()).prop_map(|(inc_step, dec_step)| Op::UpdSettByUser { inc_step, dec_step }),
+    ]
+}
+
+/// Transfer of `index + 1` lamports makes transactions with the same operation differ
+async fn send_op(
+    ctx: &mut ProgramTestContext,
+    payer: &Keypair,
+    index: usize,
+    instr: Instruction,
+    signer: &Keypair,
+) -> Result<(), CounterError> {
+    let transfer_instr = system_instruction::transfer(&payer.pubkey(), &signer.pubkey(), index as u64 + 1);
+    match send(ctx, &[transfer_instr, instr], payer, &[signer]).await {
+        Ok(()) => Ok(()),
+        Err(TransactionError::InstructionError(1, ref err @ InstructionError::Custom(_))) => {
+            Err(CounterError::from_instruction_error(err).unwrap())
+        }
+        Err(err) => panic!("unexpected transaction error {:?}", err),
+    }
+}
+
+async fn get_model(ctx: &mut ProgramTestContext, user: &Keypair) -> Model {
+    let counter = get_counter(ctx, &user.pubkey(), "").await;
+    let settings_acc = ctx
+        .banks_client
+        .get_account(Settings::get_settings_pk_with_bump().0)
+        .await
+        .unwrap()
+        .unwrap();
+    let settings = Settings::try_from_slice(&settings_acc.data).unwrap();
+    Model {
+        value: counter.value,
+        op_count: counter.op_count,
+        inc_step: settings.inc_step,
+        dec_step: settings.dec_step,
+    }
+}
+
+/// Operations can not reach the bounds of `i64` from zero, so the counter of a new user is seeded with `value`.
+/// Returns the context, the admin and the user
+async fn start(value: i64) -> (ProgramTestContext, Keypair, Keypair) {
+    let user = Keypair::new();
+    let counter = Counter { value, ..Counter::new(user.pubkey(), "", 0) };
+    let mut program_test = program_test();
+    program_test.add_account(user.pubkey(), Account::new(1_000_000_000, 0, &system_program::id()));
+    program_test.add_account(
+        Counter::generate_counter_pk(&user.pubkey(), "").unwrap(),
+        Account {
+            lamports: Rent::default().minimum_balance(Counter::LEN),
+            data: counter.try_to_vec().unwrap(),
+            owner: id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let env = Env::start(program_test).await;
+    (env.ctx, env.admin, user)
+}
+
+async fn check_sequence(start_value: i64, ops: Vec<Op>) {
+    let (mut ctx, admin, user) = start(start_value).await;
+    let payer = Keypair::from_bytes(&ctx.payer.to_bytes()).unwrap();
+    let mut model = Model::new(start_value);
+    assert_eq!(get_model(&mut ctx, &user).await, model);
+
+    for (index, op) in ops.iter().enumerate() {
+        let (instr, signer) = match *op {
+            Op::Inc => (CounterInstruction::inc_instr(user.pubkey(), "").unwrap(), &user),
+            Op::Dec => (CounterInstruction::dec_instr(user.pubkey(), "").unwrap(), &user),
+            Op::Reset => (CounterInstruction::reset_instr(user.pubkey(), "").unwrap(), &user),
+            Op::UpdSett { inc_step, dec_step } => (
+                CounterInstruction::upd_sett_instr(admin.pubkey(), admin.pubkey(), inc_step, dec_step),
+                &admin,
+            ),
+            Op::UpdSettByUser { inc_step, dec_step } => (
+                CounterInstruction::upd_sett_instr(user.pubkey(), user.pubkey(), inc_step, dec_step),
+                &user,
+            ),
+        };
+        let result = send_op(&mut ctx, &payer, index, instr, signer).await;
+
+        assert_eq!(result, model.apply(op), "result of step {} {:?}", index, op);
+        assert_eq!(get_model(&mut ctx, &user).await, model, "state after step {} {:?}", index, op);
+    }
+}
+
+proptest! {
+    // Every case starts its own test validator
+    #![proptest_config(ProptestConfig { cases: 32, max_shrink_iters: 64, ..ProptestConfig::default() })]
+
+    #[test]
+    fn program_matches_model(start_value in start_value(), ops in prop::collection::vec(op(), 1..24)) {
+        tokio::runtime::Runtime::new().unwrap().block_on(check_sequence(start_value, ops));
+    }
+}
+
+#[tokio::test]
+async fn overflow_keeps_state() {
+    check_sequence(i64::MAX - 1, vec![Op::Inc, Op::Dec, Op::Inc, Op::Inc]).await;
+    check_sequence(i64::MIN + 1, vec![Op::Dec, Op::Inc, Op::Dec, Op::Dec]).await;
+}
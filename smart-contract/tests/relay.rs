@@ -0,0 +1,91 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use solana_program::instruction::Instruction;
+use solana_sdk::ed25519_instruction::new_ed25519_instruction;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use counter::instruction::{CounterInstruction, OperationKind};
+use counter::error::CounterError;
+use common::{custom_err, get_counter, send, Env};
+
+/// Ed25519 program instruction verifying the owner signature of a relayed operation
+fn ed25519_instr(signer: &Keypair, message: &[u8]) -> Instruction {
+    let keypair = ed25519_dalek::Keypair::from_bytes(&signer.to_bytes()).unwrap();
+    new_ed25519_instruction(&keypair, message)
+}
+
+/// Relayed operation signed by `user` on its default counter
+fn relayed_instrs(user: &Keypair, op: OperationKind, nonce: u64, expiry: i64) -> Vec<Instruction> {
+    let message = CounterInstruction::relayed_message(user.pubkey(), "", op, nonce, expiry).unwrap();
+    vec![
+        ed25519_instr(user, &message),
+        CounterInstruction::relayed_instr(user.pubkey(), "", op, nonce, expiry).unwrap(),
+    ]
+}
+
+#[tokio::test]
+async fn relayed_operations() {
+    let env = Env::new().await;
+    let (relayer, user, mut ctx) = (env.admin, env.user, env.ctx);
+
+    send(&mut ctx, &relayed_instrs(&user, OperationKind::Inc, 0, i64::MAX), &relayer, &[]).await.unwrap();
+    send(&mut ctx, &relayed_instrs(&user, OperationKind::Dec, 1, i64::MAX), &relayer, &[]).await.unwrap();
+
+    let counter = get_counter(&mut ctx, &user.pubkey(), "").await;
+    assert_eq!(counter.value, 4);
+    assert_eq!(counter.relay_nonce, 2);
+    assert_eq!(counter.op_count, 2);
+}
+
+#[tokio::test]
+async fn relayed_replay() {
+    let env = Env::new().await;
+    let (relayer, user, mut ctx) = (env.admin, env.user, env.ctx);
+    let instrs = relayed_instrs(&user, OperationKind::Inc, 0, i64::MAX);
+
+    send(&mut ctx, &instrs, &relayer, &[]).await.unwrap();
+    // Another relayer makes a new transaction of the same operation
+    let err = send(&mut ctx, &instrs, &user, &[]).await.unwrap_err();
+
+    assert_eq!(err, custom_err(1, CounterError::WrongRelayNonce));
+    assert_eq!(get_counter(&mut ctx, &user.pubkey(), "").await.value, 9);
+}
+
+#[tokio::test]
+async fn relayed_expired() {
+    let env = Env::new().await;
+    let (relayer, user, mut ctx) = (env.admin, env.user, env.ctx);
+
+    let err = send(&mut ctx, &relayed_instrs(&user, OperationKind::Inc, 0, 1), &relayer, &[]).await.unwrap_err();
+
+    assert_eq!(err, custom_err(1, CounterError::RelayExpired));
+}
+
+#[tokio::test]
+async fn relayed_signed_by_other() {
+    let env = Env::new().await;
+    let (relayer, user, mut ctx) = (env.admin, env.user, env.ctx);
+
+    let message = CounterInstruction::relayed_message(user.pubkey(), "", OperationKind::Inc, 0, i64::MAX).unwrap();
+    let instrs = [
+        ed25519_instr(&relayer, &message),
+        CounterInstruction::relayed_instr(user.pubkey(), "", OperationKind::Inc, 0, i64::MAX).unwrap(),
+    ];
+    let err = send(&mut ctx, &instrs, &relayer, &[]).await.unwrap_err();
+
+    assert_eq!(err, custom_err(1, CounterError::MissingRelaySignature));
+}
+
+#[tokio::test]
+async fn relayed_without_signature() {
+    let env = Env::new().await;
+    let (relayer, user, mut ctx) = (env.admin, env.user, env.ctx);
+
+    let instr = CounterInstruction::relayed_instr(user.pubkey(), "", OperationKind::Inc, 0, i64::MAX).unwrap();
+    let err = send(&mut ctx, &[instr], &relayer, &[]).await.unwrap_err();
+
+    assert_eq!(err, custom_err(0, CounterError::MissingRelaySignature));
+    assert_eq!(get_counter(&mut ctx, &user.pubkey(), "").await.relay_nonce, 0);
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use solana_program_test::ProgramTestContext;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use counter::instruction::CounterInstruction;
+use counter::state::{Counter, ResetPeriod, Role, Roles, Settings};
+use counter::error::CounterError;
+use borsh::BorshDeserialize;
+use common::{custom_err, get_counter, send, Env};
+
+async fn grant(ctx: &mut ProgramTestContext, admin: &Keypair, holder: &Keypair, role: Role) {
+    let grant_instr = CounterInstruction::grant_role_instr(admin.pubkey(), holder.pubkey(), role);
+    send(ctx, &[grant_instr], admin, &[]).await.unwrap();
+}
+
+async fn get_roles(ctx: &mut ProgramTestContext) -> Roles {
+    let roles_acc = ctx
+        .banks_client
+        .get_account(Roles::get_roles_pk_with_bump().0)
+        .await
+        .unwrap()
+        .unwrap();
+    Roles::try_from_slice(&roles_acc.data).unwrap()
+}
+
+async fn get_settings(ctx: &mut ProgramTestContext) -> Settings {
+    let settings_acc = ctx
+        .banks_client
+        .get_account(Settings::get_settings_pk_with_bump().0)
+        .await
+        .unwrap()
+        .unwrap();
+    Settings::try_from_slice(&settings_acc.data).unwrap()
+}
+
+#[tokio::test]
+async fn moderator_role() {
+    let env = Env::new().await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+    let moderator = Keypair::new();
+
+    grant(&mut ctx, &admin, &moderator, Role::Moderator).await;
+    assert_eq!(get_roles(&mut ctx).await.roles_of(&moderator.pubkey()), [Role::Moderator]);
+
+    let freeze_instr = CounterInstruction::freeze_instr(moderator.pubkey(), user.pubkey(), "").unwrap();
+    send(&mut ctx, &[freeze_instr], &admin, &[&moderator]).await.unwrap();
+    assert!(get_counter(&mut ctx, &user.pubkey(), "").await.frozen);
+
+    let set_period_instr = CounterInstruction::set_reset_period_instr(moderator.pubkey(), ResetPeriod::Slots(10));
+    let err = send(&mut ctx, &[set_period_instr], &admin, &[&moderator]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::AdminRequired));
+
+    let revoke_instr = CounterInstruction::revoke_role_instr(admin.pubkey(), moderator.pubkey(), Role::Moderator);
+    send(&mut ctx, &[revoke_instr], &admin, &[]).await.unwrap();
+    assert_eq!(get_roles(&mut ctx).await.len, 0);
+
+    let thaw_instr = CounterInstruction::thaw_instr(moderator.pubkey(), user.pubkey(), "").unwrap();
+    let err = send(&mut ctx, &[thaw_instr], &admin, &[&moderator]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::AdminRequired));
+}
+
+#[tokio::test]
+async fn settings_manager_role() {
+    let env = Env::new().await;
+    let (admin, mut ctx) = (env.admin, env.ctx);
+    let manager = Keypair::new();
+
+    grant(&mut ctx, &admin, &manager, Role::SettingsManager).await;
+
+    let upd_sett_instr = CounterInstruction::upd_sett_instr(manager.pubkey(), admin.pubkey(), 3, 4);
+    send(&mut ctx, &[upd_sett_instr], &admin, &[&manager]).await.unwrap();
+    let settings = get_settings(&mut ctx).await;
+    assert_eq!((settings.inc_step, settings.dec_step), (3, 4));
+
+    // Transfer of admin requires SuperAdmin
+    let upd_sett_instr = CounterInstruction::upd_sett_instr(manager.pubkey(), manager.pubkey(), 3, 4);
+    let err = send(&mut ctx, &[upd_sett_instr], &admin, &[&manager]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::AdminRequired));
+    assert_eq!(get_settings(&mut ctx).await.admin, admin.pubkey());
+}
+
+#[tokio::test]
+async fn super_admin_role() {
+    let env = Env::new().await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+    let super_admin = Keypair::new();
+    let moderator = Keypair::new();
+
+    // Only SuperAdmin grants roles
+    let grant_instr = CounterInstruction::grant_role_instr(user.pubkey(), moderator.pubkey(), Role::Moderator);
+    let err = send(&mut ctx, &[grant_instr], &admin, &[&user]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::AdminRequired));
+
+    grant(&mut ctx, &admin, &super_admin, Role::SuperAdmin).await;
+    let grant_instr = CounterInstruction::grant_role_instr(super_admin.pubkey(), moderator.pubkey(), Role::Moderator);
+    send(&mut ctx, &[grant_instr], &admin, &[&super_admin]).await.unwrap();
+
+    // SuperAdmin holds every role
+    let admin_reset_instr = CounterInstruction::admin_reset_instr(super_admin.pubkey(), user.pubkey(), "").unwrap();
+    send(&mut ctx, &[admin_reset_instr], &admin, &[&super_admin]).await.unwrap();
+
+    let roles = get_roles(&mut ctx).await;
+    assert_eq!(roles.roles_of(&super_admin.pubkey()), [Role::SuperAdmin]);
+    assert_eq!(roles.roles_of(&moderator.pubkey()), [Role::Moderator]);
+}
+
+#[tokio::test]
+async fn wrong_roles_account() {
+    let env = Env::new().await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+
+    // Admin can't pass another program account as roles
+    let mut revoke_instr = CounterInstruction::revoke_role_instr(admin.pubkey(), user.pubkey(), Role::Moderator);
+    revoke_instr.accounts[2].pubkey = Counter::generate_counter_pk(&user.pubkey(), "").unwrap();
+    let err = send(&mut ctx, &[revoke_instr], &admin, &[&user]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::WrongRolesPDA));
+}
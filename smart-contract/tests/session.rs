@@ -0,0 +1,142 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use solana_program::clock::Clock;
+use solana_program::rent::Rent;
+use solana_sdk::account::Account;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use counter::id;
+use counter::instruction::{CounterInstruction, OperationKind};
+use counter::state::Session;
+use counter::error::CounterError;
+use borsh::BorshSerialize;
+use common::{custom_err, get_counter, program_test, send, Env};
+
+#[tokio::test]
+async fn session_ops() {
+    let env = Env::new().await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+    let session_key = Keypair::new();
+
+    let create_instr = CounterInstruction::create_session_instr(
+        user.pubkey(),
+        session_key.pubkey(),
+        i64::MAX,
+        &[OperationKind::Inc],
+    );
+    send(&mut ctx, &[create_instr], &user, &[]).await.unwrap();
+
+    let inc_instr = CounterInstruction::session_op_instr(session_key.pubkey(), user.pubkey(), "", OperationKind::Inc).unwrap();
+    send(&mut ctx, &[inc_instr], &admin, &[&session_key]).await.unwrap();
+    assert_eq!(get_counter(&mut ctx, &user.pubkey(), "").await.value, 9);
+
+    let dec_instr = CounterInstruction::session_op_instr(session_key.pubkey(), user.pubkey(), "", OperationKind::Dec).unwrap();
+    let err = send(&mut ctx, &[dec_instr], &admin, &[&session_key]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::SessionOpNotAllowed));
+}
+
+#[tokio::test]
+async fn session_expired() {
+    // Sessions can't be created expired and the test clock does not move, so the session is preloaded
+    let (user, session_key) = (Keypair::new(), Keypair::new());
+    let session = Session {
+        owner: user.pubkey(),
+        session_key: session_key.pubkey(),
+        expiry: 1,
+        allowed_ops: Session::pack_ops(&[OperationKind::Inc, OperationKind::Dec]),
+    };
+    let mut program_test = program_test();
+    program_test.add_account(
+        Session::get_session_pk_with_bump(&user.pubkey(), &session_key.pubkey()).0,
+        Account {
+            lamports: Rent::default().minimum_balance(Session::LEN),
+            data: session.try_to_vec().unwrap(),
+            owner: id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let env = Env::start_with_user(program_test, user).await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+
+    let inc_instr = CounterInstruction::session_op_instr(session_key.pubkey(), user.pubkey(), "", OperationKind::Inc).unwrap();
+    let err = send(&mut ctx, &[inc_instr], &admin, &[&session_key]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::SessionExpired));
+}
+
+#[tokio::test]
+async fn session_revoked() {
+    let env = Env::new().await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+    let session_key = Keypair::new();
+    let (session_pk, _) = Session::get_session_pk_with_bump(&user.pubkey(), &session_key.pubkey());
+
+    let create_instr = CounterInstruction::create_session_instr(
+        user.pubkey(),
+        session_key.pubkey(),
+        i64::MAX,
+        &[OperationKind::Inc],
+    );
+    send(&mut ctx, &[create_instr], &user, &[]).await.unwrap();
+    let revoke_instr = CounterInstruction::revoke_session_instr(user.pubkey(), session_key.pubkey());
+    send(&mut ctx, &[revoke_instr], &user, &[]).await.unwrap();
+    assert_eq!(ctx.banks_client.get_account(session_pk).await.unwrap(), None);
+
+    let inc_instr = CounterInstruction::session_op_instr(session_key.pubkey(), user.pubkey(), "", OperationKind::Inc).unwrap();
+    let err = send(&mut ctx, &[inc_instr], &admin, &[&session_key]).await.unwrap_err();
+    assert_eq!(err, custom_err(0, CounterError::SessionExpired));
+    assert_eq!(get_counter(&mut ctx, &user.pubkey(), "").await.value, 0);
+}
+
+#[tokio::test]
+async fn session_of_other_key() {
+    let env = Env::new().await;
+    let (admin, user, mut ctx) = (env.admin, env.user, env.ctx);
+    let session_key = Keypair::new();
+    let other_key = Keypair::new();
+
+    let create_instr = CounterInstruction::create_session_instr(
+        user.pubkey(),
+        session_key.pubkey(),
+        i64::MAX,
+        &[OperationKind::Inc],
+    );
+    send(&mut ctx, &[create_instr], &user, &[]).await.unwrap();
+
+    // Other key signs with the session account of the registered key
+    let mut inc_instr = CounterInstruction::session_op_instr(other_key.pubkey(), user.pubkey(), "", OperationKind::Inc).unwrap();
+    inc_instr.accounts[4].pubkey = Session::get_session_pk_with_bump(&user.pubkey(), &session_key.pubkey()).0;
+    let err = send(&mut ctx, &[inc_instr], &admin, &[&other_key]).await.unwrap_err();
+
+    assert_eq!(err, custom_err(0, CounterError::WrongSessionPDA));
+}
+
+#[tokio::test]
+async fn session_expiry_not_in_future() {
+    let env = Env::new().await;
+    let (user, mut ctx) = (env.user, env.ctx);
+    let now = ctx.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+
+    let create_instr = CounterInstruction::create_session_instr(
+        user.pubkey(),
+        Keypair::new().pubkey(),
+        now,
+        &[OperationKind::Inc],
+    );
+    let err = send(&mut ctx, &[create_instr], &user, &[]).await.unwrap_err();
+
+    assert_eq!(err, custom_err(0, CounterError::SessionExpiryNotInFuture));
+}
+
+#[tokio::test]
+async fn session_without_ops() {
+    let env = Env::new().await;
+    let (user, mut ctx) = (env.user, env.ctx);
+
+    let create_instr = CounterInstruction::create_session_instr(user.pubkey(), Keypair::new().pubkey(), i64::MAX, &[]);
+    let err = send(&mut ctx, &[create_instr], &user, &[]).await.unwrap_err();
+
+    assert_eq!(err, custom_err(0, CounterError::EmptySessionOps));
+}